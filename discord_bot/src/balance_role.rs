@@ -0,0 +1,187 @@
+use core::{
+    balance_role::{BalanceRole, BalanceRoleMember, BALANCE_ROLE_EVALUATIONS_PER_RUN},
+    error::Error as SpectreError,
+    tip_context::TipContext,
+    utils::{get_addresses_balance, now_unix_secs},
+};
+use poise::serenity_prelude::{GuildId, Http, RoleId, UserId};
+use spectre_wallet_core::{prelude::Address, utils::sompi_to_spectre_string_with_suffix};
+use std::{sync::Arc, time::Duration};
+use tracing::{error, info};
+
+// members due for an evaluation are picked up on the next run
+const BALANCE_ROLE_EVALUATION_RUN_INTERVAL: Duration = Duration::from_secs(60);
+
+pub fn format_balance_role(tip_context: &Arc<TipContext>, balance_role: &BalanceRole) -> String {
+    format!(
+        "<@&{}>: hold at least {}",
+        balance_role.role_id,
+        sompi_to_spectre_string_with_suffix(
+            balance_role.min_balance_sompi,
+            &tip_context.network_id()
+        )
+    )
+}
+
+/**
+ * receive address of the owned wallet and verified linked addresses of the member
+ */
+async fn find_member_addresses(
+    tip_context: &Arc<TipContext>,
+    owner_identifier: &str,
+) -> Vec<Address> {
+    let mut addresses = Vec::new();
+
+    if let Ok(metadata) = tip_context
+        .owned_wallet_metadata_store
+        .find_owned_wallet_metadata_by_owner_identifier(owner_identifier)
+        .await
+    {
+        addresses.push(metadata.receive_address);
+    }
+
+    if let Ok(linked_addresses) = tip_context
+        .linked_address_metadata_store
+        .find_linked_addresses_by_owner_identifier(owner_identifier)
+        .await
+    {
+        for linked_address in linked_addresses {
+            if !addresses.contains(&linked_address.address) {
+                addresses.push(linked_address.address);
+            }
+        }
+    }
+
+    addresses
+}
+
+/**
+ * grant or revoke every balance-gated role of the guild according to the member balance
+ */
+async fn evaluate_balance_role_member(
+    http: &Http,
+    tip_context: &Arc<TipContext>,
+    member: &BalanceRoleMember,
+) -> Result<(), SpectreError> {
+    let guild_id = GuildId::new(member.guild_id);
+    let user_id = UserId::new(
+        member
+            .owner_identifier
+            .parse::<u64>()
+            .map_err(|e| SpectreError::custom(e.to_string()))?,
+    );
+
+    let addresses = find_member_addresses(tip_context, &member.owner_identifier).await;
+    let balance_sompi = get_addresses_balance(tip_context.rpc_api(), addresses).await?;
+
+    // recorded first, a member who left the server is not retried before the next interval
+    tip_context
+        .balance_role_member_store
+        .record_evaluation(
+            member.guild_id,
+            &member.owner_identifier,
+            balance_sompi,
+            now_unix_secs(),
+        )
+        .await?;
+
+    let guild_member = guild_id
+        .member(http, user_id)
+        .await
+        .map_err(|e| SpectreError::custom(e.to_string()))?;
+
+    for balance_role in tip_context
+        .balance_role_store
+        .find_by_guild_id(member.guild_id)
+        .await
+    {
+        let role_id = RoleId::new(balance_role.role_id);
+        let has_role = guild_member.roles.contains(&role_id);
+        let is_qualified = balance_role.is_qualified(balance_sompi);
+
+        if is_qualified && !has_role {
+            info!(
+                "Granting balance role {} to {} in guild {}",
+                role_id, user_id, guild_id
+            );
+
+            if let Err(e) = http
+                .add_member_role(guild_id, user_id, role_id, Some("Balance requirement met"))
+                .await
+            {
+                error!("Error while granting balance role {}: {}", role_id, e);
+            }
+        } else if !is_qualified && has_role {
+            info!(
+                "Revoking balance role {} from {} in guild {}",
+                role_id, user_id, guild_id
+            );
+
+            if let Err(e) = http
+                .remove_member_role(
+                    guild_id,
+                    user_id,
+                    role_id,
+                    Some("Balance requirement no longer met"),
+                )
+                .await
+            {
+                error!("Error while revoking balance role {}: {}", role_id, e);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/**
+ * remove the balance-gated roles of a member who opted out, members may have lost them already
+ */
+pub async fn revoke_balance_roles(
+    http: &Http,
+    tip_context: &Arc<TipContext>,
+    guild_id: u64,
+    user_id: UserId,
+) {
+    for balance_role in tip_context
+        .balance_role_store
+        .find_by_guild_id(guild_id)
+        .await
+    {
+        let _ = http
+            .remove_member_role(
+                GuildId::new(guild_id),
+                user_id,
+                RoleId::new(balance_role.role_id),
+                Some("Opted out of balance roles"),
+            )
+            .await;
+    }
+}
+
+/**
+ * evaluate opted-in members in the background, a few at a time and each at most once per
+ * evaluation interval to spare the node
+ */
+pub fn spawn_balance_role_evaluator(http: Arc<Http>, tip_context: Arc<TipContext>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(BALANCE_ROLE_EVALUATION_RUN_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            for member in tip_context
+                .balance_role_member_store
+                .find_due(now_unix_secs(), BALANCE_ROLE_EVALUATIONS_PER_RUN)
+                .await
+            {
+                if let Err(e) = evaluate_balance_role_member(&http, &tip_context, &member).await {
+                    error!(
+                        "Error while evaluating balance roles of {} in guild {}: {}",
+                        member.owner_identifier, member.guild_id, e
+                    );
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,272 @@
+use core::{
+    bounty::{Bounty, BountyStatus},
+    error::Error as SpectreError,
+    tip_context::TipContext,
+    tip_custodial_wallet::TipCustodialWallet,
+    utils::{build_idempotency_key, get_tx_explorer_url, now_unix_secs},
+};
+use poise::serenity_prelude::{CacheHttp, ChannelId, Colour, CreateEmbed, CreateMessage, Http};
+use spectre_wallet_core::utils::sompi_to_spectre_string_with_suffix;
+use spectre_wallet_keys::secret::Secret;
+use std::{sync::Arc, time::Duration};
+use tracing::{error, info};
+
+use crate::{transfer::*, utils::*};
+
+// open bounties past their expiry are refunded on the next check
+const BOUNTY_EXPIRY_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/**
+ * sponsors are users or the server treasury
+ */
+pub fn format_bounty_sponsor(bounty: &Bounty) -> String {
+    match bounty.sponsor_identifier.parse::<u64>() {
+        Ok(_) => format!("<@{}>", bounty.sponsor_identifier),
+        Err(_) => "the server treasury".to_string(),
+    }
+}
+
+pub fn create_bounty_embed(tip_context: &Arc<TipContext>, bounty: &Bounty) -> CreateEmbed {
+    let network_id = tip_context.network_id();
+
+    let mut embed = create_warning_embed(
+        &format!("Bounty #{}: {}", bounty.id, bounty.title),
+        bounty.description.as_deref().unwrap_or_default(),
+    )
+    .field(
+        "Reward",
+        sompi_to_spectre_string_with_suffix(bounty.reward_sompi, &network_id),
+        true,
+    )
+    .field("Status", bounty.status.to_string(), true)
+    .field("Expires", format!("<t:{}:R>", bounty.expires_at), true)
+    .field(
+        "Created By",
+        format!(
+            "<@{}>, sponsored by {}",
+            bounty.creator_identifier,
+            format_bounty_sponsor(bounty)
+        ),
+        false,
+    )
+    .field("Claims", bounty.claims.len().to_string(), true);
+
+    match bounty.status {
+        BountyStatus::Pending | BountyStatus::Open | BountyStatus::Settling => (),
+        BountyStatus::Awarded => embed = embed.colour(Colour::DARK_GREEN),
+        BountyStatus::Refunded | BountyStatus::Cancelled | BountyStatus::FundingFailed => {
+            embed = embed.colour(Colour::DARK_RED)
+        }
+    }
+
+    if let Some(winner_identifier) = &bounty.winner_identifier {
+        embed = embed.field("Winner", format!("<@{}>", winner_identifier), true);
+    }
+
+    if let Some(tx_id) = &bounty.settle_tx_id {
+        embed = embed.field(
+            "Explorer",
+            get_tx_explorer_url(tx_id, network_id.network_type()),
+            false,
+        );
+    }
+
+    embed
+}
+
+/**
+ * one line per claim, oldest first
+ */
+pub fn format_bounty_claims(bounty: &Bounty) -> Vec<String> {
+    bounty
+        .claims
+        .iter()
+        .map(|claim| match &claim.note {
+            Some(note) => format!(
+                "<t:{}:f> <@{}>: {}",
+                claim.at, claim.claimant_identifier, note
+            ),
+            None => format!("<t:{}:f> <@{}>", claim.at, claim.claimant_identifier),
+        })
+        .collect()
+}
+
+/**
+ * post the bounty update in its channel
+ */
+pub async fn announce_bounty(
+    cache_http: impl CacheHttp,
+    bounty: &Bounty,
+    embed: CreateEmbed,
+) -> Result<(), SpectreError> {
+    ChannelId::new(bounty.channel_id)
+        .send_message(cache_http, CreateMessage::new().embed(embed))
+        .await
+        .map_err(|e| SpectreError::custom(e.to_string()))?;
+
+    Ok(())
+}
+
+/**
+ * pay the reward to the winner, or back to the sponsor without winner, and record it,
+ * a settlement interrupted after broadcasting is recorded without paying twice
+ */
+pub async fn settle_bounty(
+    cache_http: impl CacheHttp,
+    tip_context: &Arc<TipContext>,
+    bounty: &Bounty,
+    winner_identifier: Option<&str>,
+) -> Result<Bounty, SpectreError> {
+    // an award and the expiry refund must not both pay, the first claim wins
+    let bounty = &tip_context
+        .bounty_store
+        .begin_settlement(bounty.id, winner_identifier)
+        .await?;
+
+    let network_id = tip_context.network_id();
+    let amount_formatted = sompi_to_spectre_string_with_suffix(bounty.reward_sompi, &network_id);
+
+    // the treasury identifier resolves to the treasury owned wallet
+    let recipient_identifier = winner_identifier.unwrap_or(&bounty.sponsor_identifier);
+
+    let receipt = match winner_identifier {
+        Some(winner_identifier) => format!(
+            "Bounty #{} awarded: {} to <@{}>",
+            bounty.id, amount_formatted, winner_identifier
+        ),
+        None => format!(
+            "Bounty #{} expired: {} refunded to {}",
+            bounty.id,
+            amount_formatted,
+            format_bounty_sponsor(bounty)
+        ),
+    };
+
+    let transfer = BackgroundTransfer {
+        idempotency_key: build_idempotency_key("bounty-settle", bounty.id),
+        sender_identifier: bounty.creator_identifier.clone(),
+        recipient: TransferRecipient::User(recipient_identifier.to_string()),
+        amount_sompi: bounty.reward_sompi,
+        receiver_pays: true,
+        channel_id: bounty.channel_id,
+        receipt,
+    };
+
+    let result = async {
+        let bounty_wallet = TipCustodialWallet::open(
+            tip_context.clone(),
+            &Secret::from(bounty.secret.clone()),
+            &bounty.wallet_identifier,
+        )
+        .await?;
+
+        let result = send_background_transfer(
+            tip_context,
+            &bounty_wallet.wallet().account()?,
+            Secret::from(bounty.secret.clone()),
+            None,
+            &transfer,
+        )
+        .await;
+
+        if let Err(e) = bounty_wallet.wallet().stop().await {
+            error!("Error while stopping bounty wallet: {}", e);
+        }
+
+        result
+    }
+    .await;
+
+    let tx_id = match result {
+        Ok(tx_id) => tx_id,
+        Err(SpectreError::OutboxEntryAlreadyExists()) => {
+            find_submitted_transfer(tip_context, &transfer.idempotency_key)
+                .await
+                .ok_or(SpectreError::OutboxEntryAlreadyExists())?
+        }
+        Err(e) => {
+            if is_transfer_forgotten(tip_context, &transfer.idempotency_key).await {
+                tip_context.bounty_store.abort_settlement(bounty.id).await?;
+            }
+
+            return Err(e);
+        }
+    };
+
+    let settled = match winner_identifier {
+        Some(winner_identifier) => {
+            tip_context
+                .bounty_store
+                .award(bounty.id, winner_identifier, tx_id, now_unix_secs())
+                .await?
+        }
+        None => {
+            tip_context
+                .bounty_store
+                .refund(bounty.id, tx_id, now_unix_secs())
+                .await?
+        }
+    };
+
+    announce_bounty(
+        cache_http,
+        &settled,
+        create_bounty_embed(tip_context, &settled),
+    )
+    .await?;
+
+    tip_context
+        .outbox_store
+        .mark_delivered(&transfer.idempotency_key)
+        .await?;
+
+    Ok(settled)
+}
+
+/**
+ * refund the open bounties past their expiry, cancel the ones never funded
+ */
+pub fn spawn_bounty_expiry_watcher(http: Arc<Http>, tip_context: Arc<TipContext>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(BOUNTY_EXPIRY_CHECK_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            for bounty in tip_context.bounty_store.find_expired(now_unix_secs()).await {
+                let result = match bounty.status {
+                    BountyStatus::Pending => {
+                        info!("Cancelling unfunded bounty #{}", bounty.id);
+
+                        match tip_context
+                            .bounty_store
+                            .cancel(bounty.id, now_unix_secs())
+                            .await
+                        {
+                            Ok(cancelled) => {
+                                announce_bounty(
+                                    &http,
+                                    &cancelled,
+                                    create_bounty_embed(&tip_context, &cancelled),
+                                )
+                                .await
+                            }
+                            Err(e) => Err(e),
+                        }
+                    }
+                    _ => {
+                        info!("Refunding bounty #{} past its expiry", bounty.id);
+
+                        settle_bounty(&http, &tip_context, &bounty, None)
+                            .await
+                            .map(|_| ())
+                    }
+                };
+
+                if let Err(e) = result {
+                    error!("Error while expiring bounty #{}: {}", bounty.id, e);
+                }
+            }
+        }
+    });
+}
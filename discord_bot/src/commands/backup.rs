@@ -0,0 +1,67 @@
+use crate::utils::*;
+use core::tip_owned_wallet::TipOwnedWallet;
+use poise::serenity_prelude::{CreateAttachment, CreateMessage};
+
+use crate::models::{Context, Error};
+
+#[poise::command(slash_command, category = "wallet")]
+/// receive your encrypted wallet file by direct message, still protected by your password
+pub async fn backup(ctx: Context<'_>) -> Result<(), Error> {
+    let author = ctx.author();
+    let wallet_owner_identifier = author.id.to_string();
+
+    let tip_context = ctx.data();
+
+    let wallet_exists = tip_context
+        .local_store()?
+        .exists(Some(&wallet_owner_identifier))
+        .await?;
+
+    if !wallet_exists {
+        let embed = create_error_embed("Error", "Wallet not found.");
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let is_watch_only = tip_context
+        .owned_wallet_metadata_store
+        .find_owned_wallet_metadata_by_owner_identifier(&wallet_owner_identifier)
+        .await
+        .map(|metadata| metadata.is_watch_only())
+        .unwrap_or(false);
+
+    if is_watch_only {
+        let embed = create_error_embed(
+            "Error",
+            "This is a watch-only wallet, register it again from its xpub instead.",
+        );
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let wallet_file_data = TipOwnedWallet::read_wallet_file(&wallet_owner_identifier).await?;
+
+    let embed = create_success_embed(
+        "Wallet Backup",
+        "This file is encrypted with your wallet password, use `/wallet import_file` to restore it.",
+    );
+
+    let message = CreateMessage::new()
+        .embed(embed)
+        .add_file(CreateAttachment::bytes(
+            wallet_file_data,
+            format!("{}.wallet", wallet_owner_identifier),
+        ));
+
+    if let Err(e) = author.direct_message(ctx, message).await {
+        let embed = create_error_embed(
+            "Error while sending the backup",
+            &format!("Unable to send you a direct message: {}", e),
+        );
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let embed = create_success_embed(
+        "Wallet Backup",
+        "Your encrypted wallet file has been sent by direct message.",
+    );
+    send_reply(ctx, embed, true).await
+}
@@ -0,0 +1,254 @@
+use crate::{balance_role::*, utils::*};
+use core::{
+    balance_role::BalanceRole,
+    error::Error as SpectreError,
+    utils::{now_unix_secs, try_parse_required_nonzero_spectre_as_sompi_u64},
+};
+use poise::serenity_prelude as serenity;
+use spectre_wallet_core::utils::sompi_to_spectre_string_with_suffix;
+
+use crate::models::{Context, Error};
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommands(
+        "balance_roles_list",
+        "balance_roles_opt_in",
+        "balance_roles_status",
+        "balance_roles_add",
+        "balance_roles_remove"
+    ),
+    category = "wallet"
+)]
+/// roles granted to members holding enough SPR
+pub async fn balance_roles(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+#[poise::command(slash_command, guild_only, rename = "list", category = "wallet")]
+/// show the balance-gated roles of this server
+pub async fn balance_roles_list(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) => guild_id.get(),
+        None => {
+            let embed = create_error_embed("Error", "Balance roles are only available in a server");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let tip_context = ctx.data();
+
+    let balance_roles = tip_context
+        .balance_role_store
+        .find_by_guild_id(guild_id)
+        .await;
+
+    if balance_roles.is_empty() {
+        let embed = create_success_embed("Balance Roles", "No role is gated by balance");
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let lines: Vec<String> = balance_roles
+        .iter()
+        .map(|balance_role| format_balance_role(tip_context, balance_role))
+        .collect();
+
+    let embed = create_success_embed(
+        "Balance Roles",
+        "Opt in with `/wallet balance_roles opt_in` to have the balance of your wallet and linked addresses checked",
+    )
+    .field("Roles", format_field_lines(lines), false);
+    send_reply(ctx, embed, false).await
+}
+
+#[poise::command(slash_command, guild_only, rename = "opt_in", category = "wallet")]
+/// have your balance checked to get (or stop getting) balance-gated roles
+pub async fn balance_roles_opt_in(
+    ctx: Context<'_>,
+    #[description = "Have your balance checked"] enabled: bool,
+) -> Result<(), Error> {
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) => guild_id.get(),
+        None => {
+            let embed = create_error_embed("Error", "Balance roles are only available in a server");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let tip_context = ctx.data();
+    let owner_identifier = ctx.author().id.to_string();
+
+    if enabled {
+        tip_context
+            .balance_role_member_store
+            .opt_in(guild_id, &owner_identifier, now_unix_secs())
+            .await?;
+
+        let embed = create_success_embed(
+            "Opted In",
+            "The balance of your wallet and linked addresses will be checked shortly, roles are granted and revoked as it changes",
+        );
+        return send_reply(ctx, embed, true).await;
+    }
+
+    match tip_context
+        .balance_role_member_store
+        .opt_out(guild_id, &owner_identifier)
+        .await
+    {
+        Ok(()) => {
+            ctx.defer_ephemeral().await?;
+
+            revoke_balance_roles(
+                &ctx.serenity_context().http,
+                tip_context,
+                guild_id,
+                ctx.author().id,
+            )
+            .await;
+
+            let embed = create_success_embed(
+                "Opted Out",
+                "Your balance is no longer checked and your balance-gated roles were removed",
+            );
+            send_reply(ctx, embed, true).await
+        }
+        Err(SpectreError::BalanceRoleMemberNotFound()) => {
+            let embed = create_error_embed("Error", "You have not opted in");
+            send_reply(ctx, embed, true).await
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[poise::command(slash_command, guild_only, rename = "status", category = "wallet")]
+/// show the balance last checked for your balance-gated roles
+pub async fn balance_roles_status(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) => guild_id.get(),
+        None => {
+            let embed = create_error_embed("Error", "Balance roles are only available in a server");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let tip_context = ctx.data();
+
+    let member = match tip_context
+        .balance_role_member_store
+        .find(guild_id, &ctx.author().id.to_string())
+        .await
+    {
+        Some(member) => member,
+        None => {
+            let embed = create_error_embed(
+                "Error",
+                "You have not opted in, use `/wallet balance_roles opt_in`",
+            );
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let embed = match (member.last_evaluated_at, member.last_balance_sompi) {
+        (Some(last_evaluated_at), Some(last_balance_sompi)) => create_success_embed(
+            "Balance Roles",
+            &format!("Last checked <t:{}:R>", last_evaluated_at),
+        )
+        .field(
+            "Balance",
+            sompi_to_spectre_string_with_suffix(last_balance_sompi, &tip_context.network_id()),
+            true,
+        ),
+        _ => create_success_embed("Balance Roles", "Your balance has not been checked yet"),
+    };
+
+    send_reply(ctx, embed, true).await
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    rename = "add",
+    check = "check_guild_admin",
+    category = "wallet"
+)]
+/// gate a role by balance, or change its minimum balance
+pub async fn balance_roles_add(
+    ctx: Context<'_>,
+    #[description = "Role"] role: serenity::Role,
+    #[description = "Minimum balance"] min_balance: String,
+) -> Result<(), Error> {
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) => guild_id.get(),
+        None => {
+            let embed = create_error_embed("Error", "Balance roles are only available in a server");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    if role.managed || role.id.get() == guild_id {
+        let embed = create_error_embed("Error", "This role cannot be assigned by the bot");
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let tip_context = ctx.data();
+
+    let balance_role = BalanceRole {
+        guild_id,
+        role_id: role.id.get(),
+        min_balance_sompi: try_parse_required_nonzero_spectre_as_sompi_u64(Some(min_balance))?,
+    };
+
+    tip_context.balance_role_store.set(&balance_role).await?;
+
+    let embed = create_success_embed(
+        "Balance Role Added",
+        &format!(
+            "{}, the bot needs the Manage Roles permission and a role above it",
+            format_balance_role(tip_context, &balance_role)
+        ),
+    );
+    send_reply(ctx, embed, true).await
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    rename = "remove",
+    check = "check_guild_admin",
+    category = "wallet"
+)]
+/// stop gating a role by balance, members keep the role until removed by hand
+pub async fn balance_roles_remove(
+    ctx: Context<'_>,
+    #[description = "Role"] role: serenity::Role,
+) -> Result<(), Error> {
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) => guild_id.get(),
+        None => {
+            let embed = create_error_embed("Error", "Balance roles are only available in a server");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    match ctx
+        .data()
+        .balance_role_store
+        .remove(guild_id, role.id.get())
+        .await
+    {
+        Ok(()) => {
+            let embed = create_success_embed(
+                "Balance Role Removed",
+                &format!("<@&{}> is no longer gated by balance", role.id),
+            );
+            send_reply(ctx, embed, true).await
+        }
+        Err(SpectreError::BalanceRoleNotFound()) => {
+            let embed = create_error_embed("Error", "This role is not gated by balance");
+            send_reply(ctx, embed, true).await
+        }
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,620 @@
+use crate::{bounty::*, transfer::*, treasury::*, utils::*};
+use core::{
+    bounty::{Bounty, BountyStatus, DEFAULT_BOUNTY_DURATION_SECS, MAX_BOUNTY_DURATION_SECS},
+    error::Error as SpectreError,
+    fee_policy::FeePriority,
+    tip_custodial_wallet::TipCustodialWallet,
+    treasury::{TreasuryPayout, TreasuryPayoutStatus},
+    utils::{
+        build_custodial_wallet_identifier, build_idempotency_key, estimate_fees, now_unix_secs,
+        try_parse_required_nonzero_spectre_as_sompi_u64,
+    },
+};
+use poise::{
+    serenity_prelude::{self as serenity, RoleId},
+    CreateReply,
+};
+use spectre_wallet_core::{tx::PaymentOutputs, utils::sompi_to_spectre_string_with_suffix};
+use spectre_wallet_keys::secret::Secret;
+use std::str::FromStr;
+
+use crate::models::{Context, Error};
+
+const SECONDS_PER_HOUR: u64 = 60 * 60;
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommands(
+        "bounty_create",
+        "bounty_treasury",
+        "bounty_claim",
+        "bounty_award",
+        "bounty_list",
+        "bounty_info"
+    ),
+    category = "wallet"
+)]
+/// rewards locked up front, paid to the member picked among the claimants
+pub async fn bounty(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+fn resolve_duration_secs(hours: Option<u64>) -> u64 {
+    hours
+        .map(|hours| (hours * SECONDS_PER_HOUR).min(MAX_BOUNTY_DURATION_SECS))
+        .unwrap_or(DEFAULT_BOUNTY_DURATION_SECS)
+}
+
+/**
+ * whether the author can pick the winner: the creator or a server admin
+ */
+async fn can_award(ctx: Context<'_>, bounty: &Bounty) -> bool {
+    if ctx.author().id.to_string() == bounty.creator_identifier {
+        return true;
+    }
+
+    ctx.guild_id().map(|guild_id| guild_id.get()) == Some(bounty.guild_id)
+        && is_guild_admin(ctx).await
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    rename = "create",
+    check = "check_not_frozen",
+    category = "wallet"
+)]
+/// post a bounty, the reward is locked from your wallet until awarded or expired
+pub async fn bounty_create(
+    ctx: Context<'_>,
+    #[max_length = 100]
+    #[description = "Title"]
+    title: String,
+    #[description = "Reward"] reward: String,
+    #[min_length = 10]
+    #[description = "password"]
+    password: String,
+    #[min = 1]
+    #[max = 2160]
+    #[description = "Hours before the reward is refunded (default 168)"]
+    hours: Option<u64>,
+    #[max_length = 1000]
+    #[description = "Description"]
+    description: Option<String>,
+    #[description = "Fee priority: low, normal, high or sompi amount"] priority: Option<String>,
+) -> Result<(), Error> {
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) => guild_id,
+        None => {
+            let embed = create_error_embed("Error", "Bounties are only available in a server");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let creator_identifier = ctx.author().id.to_string();
+
+    let tip_context = ctx.data();
+
+    let tip_wallet = match tip_context.get_opened_owned_wallet(&creator_identifier) {
+        Some(w) => w,
+        None => {
+            let embed = create_error_embed("Error", "Wallet not opened");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    if tip_wallet.is_watch_only() {
+        let embed = create_error_embed(
+            "Error",
+            "This is a watch-only wallet, it cannot sign transactions",
+        );
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let reward_sompi = try_parse_required_nonzero_spectre_as_sompi_u64(Some(reward))?;
+
+    if let Err(reason) = check_guild_tip_amount(tip_context, ctx.guild_id(), reward_sompi).await {
+        let embed = create_error_embed("Error", &reason);
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let fee_priority = match priority.map(|p| FeePriority::from_str(&p)).transpose() {
+        Ok(fee_priority) => fee_priority,
+        Err(e) => {
+            let embed = create_error_embed("Error", &e.to_string());
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let idempotency_key = build_idempotency_key("bounty-fund", ctx.id());
+
+    if tip_context
+        .outbox_store
+        .find_by_idempotency_key(&idempotency_key)
+        .await
+        .is_some()
+    {
+        let embed = create_error_embed("Error", "This request has already been processed");
+        return send_reply(ctx, embed, true).await;
+    }
+
+    ctx.defer_ephemeral().await?;
+
+    let account = tip_wallet.wallet().account()?;
+
+    // the bounty wallet only exists once confirmed, fees are estimated on an equivalent output
+    let outputs = PaymentOutputs::from((account.receive_address()?, reward_sompi));
+    let base_fees_summary = estimate_fees(&account, outputs).await?;
+    let priority_fee = tip_context
+        .fee_policy()
+        .resolve_priority_fee(
+            tip_context.rpc_api(),
+            fee_priority,
+            base_fees_summary.aggregated_fees,
+        )
+        .await?;
+
+    let network_id = tip_context.network_id();
+    let now = now_unix_secs();
+    let expires_at = now + resolve_duration_secs(hours);
+
+    let preview = create_warning_embed(
+        "Bounty Preview",
+        "The reward is held by the bot until you or an admin award it to a claimant. It is refunded to you if nobody is awarded before expiry.",
+    )
+    .field("Title", &title, false)
+    .field(
+        "Reward",
+        sompi_to_spectre_string_with_suffix(reward_sompi, &network_id),
+        true,
+    )
+    .field(
+        "Estimated Fee",
+        sompi_to_spectre_string_with_suffix(
+            base_fees_summary.aggregated_fees + priority_fee,
+            &network_id,
+        ),
+        true,
+    )
+    .field("Expires", format!("<t:{}:f>", expires_at), true);
+
+    if !confirm_transaction_preview(ctx, preview).await? {
+        return Ok(());
+    }
+
+    let wallet_identifier = build_custodial_wallet_identifier("bounty", ctx.id());
+    let (bounty_wallet, bounty_secret) =
+        TipCustodialWallet::create(tip_context.clone(), &wallet_identifier).await?;
+
+    let bounty = tip_context
+        .bounty_store
+        .add(Bounty {
+            id: 0,
+            guild_id: guild_id.get(),
+            channel_id: ctx.channel_id().get(),
+            title,
+            description,
+            creator_identifier: creator_identifier.clone(),
+            sponsor_identifier: creator_identifier.clone(),
+            reward_sompi,
+            wallet_identifier,
+            receive_address: bounty_wallet.receive_address(),
+            secret: bounty_secret,
+            status: BountyStatus::Pending,
+            claims: vec![],
+            winner_identifier: None,
+            fund_tx_id: None,
+            settle_tx_id: None,
+            expires_at,
+            created_at: now,
+            settled_at: None,
+        })
+        .await?;
+
+    let transfer = BackgroundTransfer {
+        idempotency_key: idempotency_key.clone(),
+        sender_identifier: creator_identifier,
+        recipient: TransferRecipient::Address(bounty.receive_address.clone()),
+        amount_sompi: reward_sompi,
+        receiver_pays: false,
+        channel_id: bounty.channel_id,
+        receipt: format!(
+            "Reward of {} locked for bounty #{}",
+            sompi_to_spectre_string_with_suffix(reward_sompi, &network_id),
+            bounty.id
+        ),
+    };
+
+    let tx_id = match send_background_transfer(
+        tip_context,
+        &account,
+        Secret::from(password),
+        fee_priority,
+        &transfer,
+    )
+    .await
+    {
+        Ok(tx_id) => tx_id,
+        Err(SpectreError::OutboxEntryAlreadyExists()) => {
+            tip_context.bounty_store.remove(bounty.id).await?;
+
+            let embed = create_error_embed("Error", "This request has already been processed");
+            return send_reply(ctx, embed, true).await;
+        }
+        Err(e) if is_transfer_forgotten(tip_context, &idempotency_key).await => {
+            tip_context.bounty_store.remove(bounty.id).await?;
+
+            let embed = create_error_embed("Error", &format!("Transaction failed: {}", e));
+            return send_reply(ctx, embed, true).await;
+        }
+        Err(e) => {
+            // part of the reward may be on the bounty wallet, its secret must be kept
+            tip_context
+                .bounty_store
+                .mark_funding_failed(bounty.id)
+                .await?;
+
+            let embed = create_error_embed(
+                "Error",
+                &format!(
+                    "Transaction failed after reaching the network: {}\nBounty #{} is kept, please contact the operators to recover the funds",
+                    e, bounty.id
+                ),
+            );
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let bounty = tip_context.bounty_store.fund(bounty.id, tx_id).await?;
+
+    announce_bounty(ctx, &bounty, create_bounty_embed(tip_context, &bounty)).await?;
+
+    tip_context
+        .outbox_store
+        .mark_delivered(&idempotency_key)
+        .await?;
+
+    let embed = create_success_embed(
+        "Bounty Posted",
+        &format!(
+            "Members can claim it with `/wallet bounty claim {}`, pick the winner with `/wallet bounty award {} <user>`",
+            bounty.id, bounty.id
+        ),
+    );
+    send_reply(ctx, embed, true).await
+}
+
+#[poise::command(slash_command, guild_only, rename = "treasury", category = "wallet")]
+/// propose a bounty funded by the server treasury, posted once the approvers confirmed it
+pub async fn bounty_treasury(
+    ctx: Context<'_>,
+    #[max_length = 100]
+    #[description = "Title"]
+    title: String,
+    #[description = "Reward"] reward: String,
+    #[min = 1]
+    #[max = 2160]
+    #[description = "Hours before the reward is refunded (default 168)"]
+    hours: Option<u64>,
+    #[max_length = 1000]
+    #[description = "Description"]
+    description: Option<String>,
+) -> Result<(), Error> {
+    let tip_context = ctx.data();
+
+    let treasury = match ctx.guild_id() {
+        Some(guild_id) => {
+            tip_context
+                .treasury_store
+                .find_by_guild_id(guild_id.get())
+                .await
+        }
+        None => None,
+    };
+
+    let treasury = match treasury {
+        Some(treasury) => treasury,
+        None => {
+            let embed = create_error_embed("Error", "This server has no treasury");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let is_approver = ctx.interaction.member.as_ref().is_some_and(|member| {
+        member
+            .roles
+            .contains(&RoleId::new(treasury.approver_role_id))
+    });
+
+    if !is_approver {
+        let embed = create_error_embed(
+            "Error",
+            &format!(
+                "Only <@&{}> can propose treasury bounties",
+                treasury.approver_role_id
+            ),
+        );
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let reward_sompi = try_parse_required_nonzero_spectre_as_sompi_u64(Some(reward))?;
+
+    if let Err(reason) = check_guild_tip_amount(tip_context, ctx.guild_id(), reward_sompi).await {
+        let embed = create_error_embed("Error", &reason);
+        return send_reply(ctx, embed, true).await;
+    }
+
+    ctx.defer().await?;
+
+    let creator_identifier = ctx.author().id.to_string();
+    let now = now_unix_secs();
+
+    let wallet_identifier = build_custodial_wallet_identifier("bounty", ctx.id());
+    let (bounty_wallet, bounty_secret) =
+        TipCustodialWallet::create(tip_context.clone(), &wallet_identifier).await?;
+
+    let bounty = tip_context
+        .bounty_store
+        .add(Bounty {
+            id: 0,
+            guild_id: treasury.guild_id,
+            channel_id: ctx.channel_id().get(),
+            title: title.clone(),
+            description,
+            creator_identifier: creator_identifier.clone(),
+            sponsor_identifier: treasury.wallet_identifier.clone(),
+            reward_sompi,
+            wallet_identifier: wallet_identifier.clone(),
+            receive_address: bounty_wallet.receive_address(),
+            secret: bounty_secret,
+            status: BountyStatus::Pending,
+            claims: vec![],
+            winner_identifier: None,
+            fund_tx_id: None,
+            settle_tx_id: None,
+            expires_at: now + resolve_duration_secs(hours),
+            created_at: now,
+            settled_at: None,
+        })
+        .await?;
+
+    let mut payout = TreasuryPayout {
+        id: 0,
+        guild_id: treasury.guild_id,
+        proposer_identifier: creator_identifier.clone(),
+        recipient_identifier: wallet_identifier,
+        amount_sompi: reward_sompi,
+        reason: format!("Bounty: {}", title),
+        bounty_id: Some(bounty.id),
+        channel_id: ctx.channel_id().get(),
+        message_id: 0,
+        approvals: vec![],
+        status: TreasuryPayoutStatus::Proposed,
+        rejected_by: None,
+        error: None,
+        tx_id: None,
+        created_at: now,
+        settled_at: None,
+    };
+    payout.approve(&creator_identifier, now, treasury.threshold)?;
+
+    let payout = tip_context.treasury_payout_store.add(payout).await?;
+
+    // a threshold of one needs no other approver
+    if payout.status == TreasuryPayoutStatus::Executing {
+        let executed =
+            execute_treasury_payout(ctx, tip_context, &treasury, &payout, &creator_identifier)
+                .await?;
+
+        let embed = create_treasury_payout_embed(tip_context, &executed, treasury.threshold);
+        return send_reply(ctx, embed, false).await;
+    }
+
+    let reply = ctx
+        .send(CreateReply {
+            content: Some(format!("<@&{}>", treasury.approver_role_id)),
+            embeds: vec![create_treasury_payout_embed(
+                tip_context,
+                &payout,
+                treasury.threshold,
+            )],
+            components: Some(create_treasury_payout_buttons(payout.id)),
+            ephemeral: Some(false),
+            ..Default::default()
+        })
+        .await?;
+
+    tip_context
+        .treasury_payout_store
+        .set_message_id(payout.id, reply.message().await?.id.get())
+        .await?;
+
+    Ok(())
+}
+
+#[poise::command(slash_command, guild_only, rename = "claim", category = "wallet")]
+/// claim an open bounty, the creator picks the winner among the claimants
+pub async fn bounty_claim(
+    ctx: Context<'_>,
+    #[description = "Bounty id"] id: u64,
+    #[max_length = 200]
+    #[description = "Note for the creator, e.g. a link to your work"]
+    note: Option<String>,
+) -> Result<(), Error> {
+    let claimant_identifier = ctx.author().id.to_string();
+
+    let tip_context = ctx.data();
+
+    let bounty = match tip_context
+        .bounty_store
+        .claim(id, &claimant_identifier, note, now_unix_secs())
+        .await
+    {
+        Ok(bounty) => bounty,
+        Err(SpectreError::BountyNotFound()) => {
+            let embed = create_error_embed("Error", "Bounty not found");
+            return send_reply(ctx, embed, true).await;
+        }
+        Err(SpectreError::BountyTransitionNotAllowed()) => {
+            let embed = create_error_embed("Error", "This bounty is not open");
+            return send_reply(ctx, embed, true).await;
+        }
+        Err(SpectreError::BountyAlreadyClaimed()) => {
+            let embed = create_error_embed("Error", "You cannot claim this bounty again");
+            return send_reply(ctx, embed, true).await;
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let embed = create_success_embed(
+        &format!("Bounty #{} Claimed", bounty.id),
+        &format!(
+            "<@{}> claimed **{}**, <@{}> can award it with `/wallet bounty award {} <user>`",
+            claimant_identifier, bounty.title, bounty.creator_identifier, bounty.id
+        ),
+    );
+    send_reply(ctx, embed, false).await
+}
+
+#[poise::command(slash_command, guild_only, rename = "award", category = "wallet")]
+/// pay the bounty to one of its claimants (creator or admin)
+pub async fn bounty_award(
+    ctx: Context<'_>,
+    #[description = "Bounty id"] id: u64,
+    #[description = "Winner"] winner: serenity::User,
+) -> Result<(), Error> {
+    let tip_context = ctx.data();
+
+    let bounty = match tip_context.bounty_store.find_by_id(id).await {
+        Some(bounty) => bounty,
+        None => {
+            let embed = create_error_embed("Error", "Bounty not found");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    if !can_award(ctx, &bounty).await {
+        let embed = create_error_embed(
+            "Error",
+            "Only the creator or a server admin can award this bounty",
+        );
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let winner_identifier = winner.id.to_string();
+
+    if !bounty.can_award(&winner_identifier) {
+        let embed = create_error_embed(
+            "Error",
+            &format!(
+                "Bounty #{} is {}, the winner must be one of its claimants",
+                bounty.id, bounty.status
+            ),
+        );
+        return send_reply(ctx, embed, true).await;
+    }
+
+    ctx.defer_ephemeral().await?;
+
+    match settle_bounty(ctx, tip_context, &bounty, Some(&winner_identifier)).await {
+        Ok(awarded) => {
+            let embed = create_success_embed(
+                &format!("Bounty #{} Awarded", awarded.id),
+                &format!("<@{}> has been paid", winner_identifier),
+            );
+            send_reply(ctx, embed, true).await
+        }
+        Err(SpectreError::BountyTransitionNotAllowed())
+        | Err(SpectreError::OutboxEntryAlreadyExists()) => {
+            let embed =
+                create_error_embed("Error", "This bounty is already settled or being settled");
+            send_reply(ctx, embed, true).await
+        }
+        Err(e) => {
+            let embed = create_error_embed("Error", &format!("Payment failed: {}", e));
+            send_reply(ctx, embed, true).await
+        }
+    }
+}
+
+#[poise::command(slash_command, guild_only, rename = "list", category = "wallet")]
+/// list the bounties of this server with their status
+pub async fn bounty_list(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) => guild_id.get(),
+        None => {
+            let embed = create_error_embed("Error", "Bounties are only available in a server");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let tip_context = ctx.data();
+
+    let bounties = tip_context.bounty_store.find_by_guild_id(guild_id).await;
+
+    if bounties.is_empty() {
+        let embed = create_success_embed("Bounties", "No bounty has been posted");
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let network_id = tip_context.network_id();
+
+    let lines: Vec<String> = bounties
+        .iter()
+        .map(|bounty| {
+            let status = match bounty.status {
+                BountyStatus::Open => format!(
+                    "open, {} claims, expires <t:{}:R>",
+                    bounty.claims.len(),
+                    bounty.expires_at
+                ),
+                status => status.to_string(),
+            };
+
+            format!(
+                "#{}: **{}** {}, {}",
+                bounty.id,
+                bounty.title,
+                sompi_to_spectre_string_with_suffix(bounty.reward_sompi, &network_id),
+                status
+            )
+        })
+        .collect();
+
+    let embed = create_success_embed(
+        "Bounties",
+        "Most recent first, use `/wallet bounty info <id>` for details",
+    )
+    .field("Bounties", format_field_lines(lines), false);
+    send_reply(ctx, embed, false).await
+}
+
+#[poise::command(slash_command, guild_only, rename = "info", category = "wallet")]
+/// show a bounty and its claims
+pub async fn bounty_info(
+    ctx: Context<'_>,
+    #[description = "Bounty id"] id: u64,
+) -> Result<(), Error> {
+    let tip_context = ctx.data();
+
+    let bounty = match tip_context.bounty_store.find_by_id(id).await {
+        Some(bounty) if Some(bounty.guild_id) == ctx.guild_id().map(|g| g.get()) => bounty,
+        _ => {
+            let embed = create_error_embed("Error", "Bounty not found");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let mut embed = create_bounty_embed(tip_context, &bounty);
+
+    if !bounty.claims.is_empty() {
+        embed = embed.field(
+            "Claimants",
+            format_field_lines(format_bounty_claims(&bounty)),
+            false,
+        );
+    }
+
+    send_reply(ctx, embed, true).await
+}
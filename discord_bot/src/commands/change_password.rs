@@ -32,12 +32,23 @@ pub async fn change_password(
         .get_opened_owned_wallet(&wallet_owner_identifier)
         .ok_or("Wallet not found")?;
 
+    if tip_wallet.is_watch_only() {
+        let embed = create_error_embed(
+            "Error while changing the wallet password",
+            "Watch-only wallets are not protected by a password.",
+        );
+        return send_reply(ctx, embed, true).await;
+    }
+
     // change secret
     match tip_wallet
         .change_secret(&Secret::from(old_password), &Secret::from(new_password))
         .await
     {
         Ok(_) => {
+            // the in-memory secret is the old password, reaction tips have to be enabled again
+            tip_context.disable_reaction_tips(&wallet_owner_identifier);
+
             let embed = create_success_embed("Success", "Password changed successfully.");
             send_reply(ctx, embed, true).await
         }
@@ -1,18 +1,34 @@
-use std::sync::Arc;
+use std::{str::FromStr, sync::Arc};
 
 use crate::utils::*;
-use core::{tip_transition_wallet::TipTransitionWallet, utils::estimate_fees};
+use core::{
+    fee_policy::FeePriority, tip_transition_wallet::TipTransitionWallet, utils::estimate_fees,
+};
 use futures::future::join_all;
-use spectre_wallet_core::tx::{Fees, PaymentOutputs};
+use spectre_wallet_core::{
+    tx::{Fees, PaymentOutputs},
+    utils::sompi_to_spectre_string_with_suffix,
+};
 use spectre_wallet_keys::secret::Secret;
 
 use workflow_core::abortable::Abortable;
 
 use crate::models::{Context, Error};
 
-#[poise::command(slash_command, category = "wallet")]
+#[poise::command(slash_command, check = "check_not_frozen", category = "wallet")]
 /// transfers funds from transition_wallet to owned_wallet
-pub async fn claim(ctx: Context<'_>) -> Result<(), Error> {
+pub async fn claim(
+    ctx: Context<'_>,
+    #[description = "Fee priority: low, normal, high or sompi amount"] priority: Option<String>,
+) -> Result<(), Error> {
+    let fee_priority = match priority.map(|p| FeePriority::from_str(&p)).transpose() {
+        Ok(fee_priority) => fee_priority,
+        Err(e) => {
+            let embed = create_error_embed("Error", &e.to_string());
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
     let user = ctx.author().id;
     let wallet_owner_identifier = user.to_string();
 
@@ -154,13 +170,22 @@ pub async fn claim(ctx: Context<'_>) -> Result<(), Error> {
                             amount_sompi, amount_minus_gas_fee
                         );
 
+                        let priority_fee = tip_context
+                            .fee_policy()
+                            .resolve_priority_fee(
+                                tip_context.rpc_api(),
+                                fee_priority,
+                                generator_summary_option.aggregated_fees,
+                            )
+                            .await?;
+
                         let outputs = PaymentOutputs::from((address, amount_minus_gas_fee));
                         let abortable = Abortable::default();
 
                         let (summary, hashes) = account
                             .send(
                                 outputs.into(),
-                                Fees::ReceiverPays(0),
+                                Fees::ReceiverPays(priority_fee),
                                 None,
                                 secret,
                                 None,
@@ -179,6 +204,14 @@ pub async fn claim(ctx: Context<'_>) -> Result<(), Error> {
                         let embed = create_success_embed(
                             "Successfully claimed funds from transition wallets.",
                             &format!("summary {:?}\n hashes: {:?}", summary, hashes),
+                        )
+                        .field(
+                            "Fee",
+                            sompi_to_spectre_string_with_suffix(
+                                summary.aggregated_fees,
+                                &tip_context.network_id(),
+                            ),
+                            false,
                         );
                         send_reply(ctx, embed, true).await?;
                     }
@@ -11,6 +11,9 @@ pub async fn close(ctx: Context<'_>) -> Result<(), Error> {
 
     let is_opened = tip_context.does_opened_owned_wallet_exists(&wallet_owner_identifier);
 
+    // the in-memory secret must not outlive the opened wallet
+    tip_context.disable_reaction_tips(&wallet_owner_identifier);
+
     if is_opened {
         let tip_wallet_result = tip_context.remove_opened_owned_wallet(&wallet_owner_identifier);
 
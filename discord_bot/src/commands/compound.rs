@@ -4,7 +4,7 @@ use workflow_core::abortable::Abortable;
 use crate::models::{Context, Error};
 use crate::utils::*;
 
-#[poise::command(slash_command, category = "wallet")]
+#[poise::command(slash_command, check = "check_not_frozen", category = "wallet")]
 /// compound utxo
 pub async fn compound(
     ctx: Context<'_>,
@@ -46,6 +46,14 @@ pub async fn compound(
         }
     };
 
+    if tip_wallet.is_watch_only() {
+        let embed = create_error_embed(
+            "Error",
+            "This is a watch-only wallet, it cannot sign transactions",
+        );
+        return send_reply(ctx, embed, true).await;
+    }
+
     let wallet = tip_wallet.wallet();
 
     let abortable = Abortable::default();
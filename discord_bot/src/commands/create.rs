@@ -44,5 +44,7 @@ pub async fn create(
         .field("Mnemonic Phrase", mnemonic.phrase(), false)
         .field("Receive Address", tip_wallet.receive_address(), false);
 
-    send_reply(ctx, embed, true).await
+    let attachment = create_qr_code_attachment(&tip_wallet.receive_address().to_string())?;
+
+    send_reply_with_attachment(ctx, with_qr_code_image(embed), attachment, true).await
 }
@@ -1,6 +1,6 @@
 use crate::utils::*;
+use core::utils::get_wallet_file_path;
 use poise::{serenity_prelude::Colour, Modal};
-use spectre_wallet_core::settings::application_folder;
 use tokio::fs;
 
 use crate::models::{Context, Error};
@@ -43,6 +43,9 @@ pub async fn destroy(ctx: Context<'_>) -> Result<(), Error> {
 
     if let Some(data) = result {
         if data.first_input == "destroy" {
+            // the in-memory secret must not outlive the destroyed wallet
+            tip_context.disable_reaction_tips(&wallet_owner_identifier);
+
             if is_opened {
                 let tip_wallet_result =
                     tip_context.remove_opened_owned_wallet(&wallet_owner_identifier);
@@ -59,8 +62,7 @@ pub async fn destroy(ctx: Context<'_>) -> Result<(), Error> {
                 .await?;
 
             // delete wallet file
-            let wallet_folder = application_folder()?;
-            let wallet_file = wallet_folder.join(format!("{}.wallet", wallet_owner_identifier));
+            let wallet_file = get_wallet_file_path(&wallet_owner_identifier)?;
 
             if wallet_file.exists() {
                 fs::remove_file(&wallet_file).await?;
@@ -0,0 +1,603 @@
+use crate::{escrow::*, transfer::*, utils::*};
+use core::{
+    error::Error as SpectreError,
+    escrow::{
+        Escrow, EscrowAction, EscrowActor, EscrowEvent, EscrowStatus, DEFAULT_ESCROW_DEADLINE_SECS,
+        MAX_ESCROW_DEADLINE_SECS,
+    },
+    fee_policy::FeePriority,
+    tip_custodial_wallet::TipCustodialWallet,
+    utils::{
+        build_custodial_wallet_identifier, build_idempotency_key, estimate_fees, now_unix_secs,
+        try_parse_required_nonzero_spectre_as_sompi_u64,
+    },
+};
+use poise::serenity_prelude::{self as serenity, RoleId};
+use spectre_wallet_core::{tx::PaymentOutputs, utils::sompi_to_spectre_string_with_suffix};
+use spectre_wallet_keys::secret::Secret;
+use std::str::FromStr;
+
+use crate::models::{Context, Error};
+
+const SECONDS_PER_HOUR: u64 = 60 * 60;
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommands(
+        "escrow_create",
+        "escrow_release",
+        "escrow_refund",
+        "escrow_dispute",
+        "escrow_info",
+        "escrow_list",
+        "escrow_arbiter"
+    ),
+    category = "wallet"
+)]
+/// hold funds until the buyer releases them to the seller, or they are refunded
+pub async fn escrow(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/**
+ * whether the author holds the arbiter role of the escrow guild
+ */
+async fn is_arbiter(ctx: Context<'_>, escrow: &Escrow) -> bool {
+    if ctx.guild_id().map(|guild_id| guild_id.get()) != Some(escrow.guild_id) {
+        return false;
+    }
+
+    let role_id = match ctx
+        .data()
+        .escrow_arbiter_store
+        .find_arbiter_role(escrow.guild_id)
+        .await
+    {
+        Some(role_id) => RoleId::new(role_id),
+        None => return false,
+    };
+
+    ctx.interaction
+        .member
+        .as_ref()
+        .is_some_and(|member| member.roles.contains(&role_id))
+}
+
+/**
+ * role the author acts as for `action`, parties first then the arbiter
+ */
+async fn resolve_actor(
+    ctx: Context<'_>,
+    escrow: &Escrow,
+    action: EscrowAction,
+) -> Option<EscrowActor> {
+    let owner_identifier = ctx.author().id.to_string();
+
+    if let Some(party) = escrow.party_of(&owner_identifier) {
+        if escrow.can_settle(action, party) {
+            return Some(party);
+        }
+    }
+
+    match is_arbiter(ctx, escrow).await {
+        true => Some(EscrowActor::Arbiter),
+        false => None,
+    }
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    rename = "create",
+    check = "check_not_frozen",
+    category = "wallet"
+)]
+/// deposit funds in a new escrow for a seller
+pub async fn escrow_create(
+    ctx: Context<'_>,
+    #[description = "Seller"] seller: serenity::User,
+    #[description = "Amount"] amount: String,
+    #[min_length = 10]
+    #[description = "password"]
+    password: String,
+    #[min = 1]
+    #[max = 720]
+    #[description = "Hours before automatic refund (default 72)"]
+    hours: Option<u64>,
+    #[max_length = 200]
+    #[description = "Memo"]
+    memo: Option<String>,
+    #[description = "Fee priority: low, normal, high or sompi amount"] priority: Option<String>,
+) -> Result<(), Error> {
+    if seller.bot || seller.system {
+        let embed = create_error_embed("Error", "User is a bot or a system user");
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let author = ctx.author();
+
+    if seller.id == author.id {
+        let embed = create_error_embed("Error", "You cannot open an escrow with yourself");
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) => guild_id,
+        None => {
+            let embed = create_error_embed("Error", "Escrows are only available in a server");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let buyer_identifier = author.id.to_string();
+
+    let tip_context = ctx.data();
+
+    let tip_wallet = match tip_context.get_opened_owned_wallet(&buyer_identifier) {
+        Some(w) => w,
+        None => {
+            let embed = create_error_embed("Error", "Wallet not opened");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    if tip_wallet.is_watch_only() {
+        let embed = create_error_embed(
+            "Error",
+            "This is a watch-only wallet, it cannot sign transactions",
+        );
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let amount_sompi = try_parse_required_nonzero_spectre_as_sompi_u64(Some(amount))?;
+
+    if let Err(reason) = check_guild_tip_amount(tip_context, ctx.guild_id(), amount_sompi).await {
+        let embed = create_error_embed("Error", &reason);
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let fee_priority = match priority.map(|p| FeePriority::from_str(&p)).transpose() {
+        Ok(fee_priority) => fee_priority,
+        Err(e) => {
+            let embed = create_error_embed("Error", &e.to_string());
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let deadline_secs = hours
+        .map(|hours| (hours * SECONDS_PER_HOUR).min(MAX_ESCROW_DEADLINE_SECS))
+        .unwrap_or(DEFAULT_ESCROW_DEADLINE_SECS);
+
+    let idempotency_key = build_idempotency_key("escrow-fund", ctx.id());
+
+    if tip_context
+        .outbox_store
+        .find_by_idempotency_key(&idempotency_key)
+        .await
+        .is_some()
+    {
+        let embed = create_error_embed("Error", "This request has already been processed");
+        return send_reply(ctx, embed, true).await;
+    }
+
+    ctx.defer_ephemeral().await?;
+
+    let account = tip_wallet.wallet().account()?;
+
+    // the escrow wallet only exists once confirmed, fees are estimated on an equivalent output
+    let outputs = PaymentOutputs::from((account.receive_address()?, amount_sompi));
+    let base_fees_summary = estimate_fees(&account, outputs).await?;
+    let priority_fee = tip_context
+        .fee_policy()
+        .resolve_priority_fee(
+            tip_context.rpc_api(),
+            fee_priority,
+            base_fees_summary.aggregated_fees,
+        )
+        .await?;
+
+    let network_id = tip_context.network_id();
+    let now = now_unix_secs();
+
+    let preview = create_warning_embed(
+        "Escrow Preview",
+        "The amount is held by the bot until you release it to the seller, the seller refunds you, or an arbiter decides. It is refunded automatically at the deadline unless disputed.",
+    )
+    .field("Seller", format!("<@{}>", seller.id), false)
+    .field(
+        "Amount",
+        sompi_to_spectre_string_with_suffix(amount_sompi, &network_id),
+        true,
+    )
+    .field(
+        "Estimated Fee",
+        sompi_to_spectre_string_with_suffix(
+            base_fees_summary.aggregated_fees + priority_fee,
+            &network_id,
+        ),
+        true,
+    )
+    .field("Deadline", format!("<t:{}:f>", now + deadline_secs), true);
+
+    if !confirm_transaction_preview(ctx, preview).await? {
+        return Ok(());
+    }
+
+    let wallet_identifier = build_custodial_wallet_identifier("escrow", ctx.id());
+    let (escrow_wallet, escrow_secret) =
+        TipCustodialWallet::create(tip_context.clone(), &wallet_identifier).await?;
+
+    let escrow = tip_context
+        .escrow_store
+        .add(Escrow {
+            id: 0,
+            guild_id: guild_id.get(),
+            channel_id: ctx.channel_id().get(),
+            buyer_identifier: buyer_identifier.clone(),
+            seller_identifier: seller.id.to_string(),
+            amount_sompi,
+            memo,
+            wallet_identifier,
+            receive_address: escrow_wallet.receive_address(),
+            secret: escrow_secret,
+            status: EscrowStatus::Pending,
+            deadline: now + deadline_secs,
+            events: vec![],
+            created_at: now,
+        })
+        .await?;
+
+    let transfer = BackgroundTransfer {
+        idempotency_key: idempotency_key.clone(),
+        sender_identifier: buyer_identifier.clone(),
+        recipient: TransferRecipient::Address(escrow.receive_address.clone()),
+        amount_sompi,
+        receiver_pays: false,
+        channel_id: escrow.channel_id,
+        receipt: format!(
+            "Deposit of {} in escrow #{} for <@{}>",
+            sompi_to_spectre_string_with_suffix(amount_sompi, &network_id),
+            escrow.id,
+            seller.id
+        ),
+    };
+
+    let tx_id = match send_background_transfer(
+        tip_context,
+        &account,
+        Secret::from(password),
+        fee_priority,
+        &transfer,
+    )
+    .await
+    {
+        Ok(tx_id) => tx_id,
+        Err(SpectreError::OutboxEntryAlreadyExists()) => {
+            tip_context.escrow_store.remove(escrow.id).await?;
+
+            let embed = create_error_embed("Error", "This request has already been processed");
+            return send_reply(ctx, embed, true).await;
+        }
+        Err(e) if is_transfer_forgotten(tip_context, &idempotency_key).await => {
+            tip_context.escrow_store.remove(escrow.id).await?;
+
+            let embed = create_error_embed("Error", &format!("Transaction failed: {}", e));
+            return send_reply(ctx, embed, true).await;
+        }
+        Err(e) => {
+            // part of the deposit may be on the escrow wallet, its secret must be kept
+            tip_context
+                .escrow_store
+                .mark_funding_failed(escrow.id)
+                .await?;
+
+            let embed = create_error_embed(
+                "Error",
+                &format!(
+                    "Transaction failed after reaching the network: {}\nEscrow #{} is kept, please contact the operators to recover the funds",
+                    e, escrow.id
+                ),
+            );
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let escrow = tip_context
+        .escrow_store
+        .apply(
+            escrow.id,
+            EscrowEvent {
+                action: EscrowAction::Fund,
+                actor: EscrowActor::Buyer,
+                actor_identifier: Some(buyer_identifier),
+                tx_id: Some(tx_id),
+                at: now_unix_secs(),
+            },
+        )
+        .await?;
+
+    announce_escrow(ctx, &escrow, create_escrow_embed(tip_context, &escrow)).await?;
+
+    tip_context
+        .outbox_store
+        .mark_delivered(&idempotency_key)
+        .await?;
+
+    let embed = create_success_embed(
+        "Escrow Funded",
+        &format!(
+            "Use `/wallet escrow release {}` once <@{}> delivered",
+            escrow.id, seller.id
+        ),
+    );
+    send_reply(ctx, embed, true).await
+}
+
+/**
+ * release or refund on behalf of the author
+ */
+async fn settle(ctx: Context<'_>, id: u64, action: EscrowAction) -> Result<(), Error> {
+    let tip_context = ctx.data();
+
+    let escrow = match tip_context.escrow_store.find_by_id(id).await {
+        Some(escrow) => escrow,
+        None => {
+            let embed = create_error_embed("Error", "Escrow not found");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let actor = match resolve_actor(ctx, &escrow, action).await {
+        Some(actor) => actor,
+        None => {
+            let embed = create_error_embed(
+                "Error",
+                &format!(
+                    "You cannot do this on escrow #{}, it is {}",
+                    escrow.id, escrow.status
+                ),
+            );
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    ctx.defer_ephemeral().await?;
+
+    match settle_escrow(
+        ctx,
+        tip_context,
+        &escrow,
+        action,
+        actor,
+        Some(ctx.author().id.to_string()),
+    )
+    .await
+    {
+        Ok(settled) => {
+            let embed = create_success_embed(
+                &format!("Escrow #{} {}", settled.id, settled.status),
+                "Both parties have been notified in the escrow channel",
+            );
+            send_reply(ctx, embed, true).await
+        }
+        Err(SpectreError::EscrowTransitionNotAllowed())
+        | Err(SpectreError::OutboxEntryAlreadyExists()) => {
+            let embed =
+                create_error_embed("Error", "This escrow is already settled or being settled");
+            send_reply(ctx, embed, true).await
+        }
+        Err(e) => {
+            let embed = create_error_embed("Error", &format!("Settlement failed: {}", e));
+            send_reply(ctx, embed, true).await
+        }
+    }
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    rename = "release",
+    check = "check_not_frozen",
+    category = "wallet"
+)]
+/// pay the escrow to the seller (buyer or arbiter)
+pub async fn escrow_release(
+    ctx: Context<'_>,
+    #[description = "Escrow id"] id: u64,
+) -> Result<(), Error> {
+    settle(ctx, id, EscrowAction::Release).await
+}
+
+// not frozen: the funds go back to the buyer who deposited them
+#[poise::command(slash_command, guild_only, rename = "refund", category = "wallet")]
+/// give the escrow back to the buyer (seller or arbiter)
+pub async fn escrow_refund(
+    ctx: Context<'_>,
+    #[description = "Escrow id"] id: u64,
+) -> Result<(), Error> {
+    settle(ctx, id, EscrowAction::Refund).await
+}
+
+#[poise::command(slash_command, guild_only, rename = "dispute", category = "wallet")]
+/// stop the automatic refund and ask the arbiters to decide
+pub async fn escrow_dispute(
+    ctx: Context<'_>,
+    #[description = "Escrow id"] id: u64,
+) -> Result<(), Error> {
+    let owner_identifier = ctx.author().id.to_string();
+
+    let tip_context = ctx.data();
+
+    let escrow = match tip_context.escrow_store.find_by_id(id).await {
+        Some(escrow) => escrow,
+        None => {
+            let embed = create_error_embed("Error", "Escrow not found");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let party = match escrow.party_of(&owner_identifier) {
+        Some(party) => party,
+        None => {
+            let embed = create_error_embed("Error", "Only the buyer or the seller can dispute");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let disputed = match tip_context
+        .escrow_store
+        .apply(
+            escrow.id,
+            EscrowEvent {
+                action: EscrowAction::Dispute,
+                actor: party,
+                actor_identifier: Some(owner_identifier),
+                tx_id: None,
+                at: now_unix_secs(),
+            },
+        )
+        .await
+    {
+        Ok(disputed) => disputed,
+        Err(SpectreError::EscrowTransitionNotAllowed()) => {
+            let embed = create_error_embed(
+                "Error",
+                &format!(
+                    "Escrow #{} is {}, it cannot be disputed",
+                    escrow.id, escrow.status
+                ),
+            );
+            return send_reply(ctx, embed, true).await;
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let arbiters = match tip_context
+        .escrow_arbiter_store
+        .find_arbiter_role(disputed.guild_id)
+        .await
+    {
+        Some(role_id) => format!("<@&{}> please decide with release or refund", role_id),
+        None => "No arbiter role is configured, ask an admin to set one".to_string(),
+    };
+
+    let embed = create_escrow_embed(tip_context, &disputed)
+        .title(format!("Escrow #{} Disputed", disputed.id))
+        .field("Arbiters", arbiters, false);
+    announce_escrow(ctx, &disputed, embed).await?;
+
+    let embed = create_success_embed(
+        "Escrow Disputed",
+        "The automatic refund is stopped, the arbiters have been notified",
+    );
+    send_reply(ctx, embed, true).await
+}
+
+#[poise::command(slash_command, guild_only, rename = "info", category = "wallet")]
+/// show an escrow and its history
+pub async fn escrow_info(
+    ctx: Context<'_>,
+    #[description = "Escrow id"] id: u64,
+) -> Result<(), Error> {
+    let tip_context = ctx.data();
+
+    let escrow = match tip_context.escrow_store.find_by_id(id).await {
+        Some(escrow) => escrow,
+        None => {
+            let embed = create_error_embed("Error", "Escrow not found");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    if escrow.party_of(&ctx.author().id.to_string()).is_none() && !is_arbiter(ctx, &escrow).await {
+        let embed =
+            create_error_embed("Error", "Only the parties and arbiters can see this escrow");
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let embed = create_escrow_embed(tip_context, &escrow).field(
+        "History",
+        format_field_lines(format_escrow_history(tip_context, &escrow)),
+        false,
+    );
+    send_reply(ctx, embed, true).await
+}
+
+#[poise::command(slash_command, guild_only, rename = "list", category = "wallet")]
+/// list your escrows, as buyer or seller
+pub async fn escrow_list(ctx: Context<'_>) -> Result<(), Error> {
+    let owner_identifier = ctx.author().id.to_string();
+
+    let tip_context = ctx.data();
+
+    let escrows = tip_context
+        .escrow_store
+        .find_by_owner(&owner_identifier)
+        .await;
+
+    if escrows.is_empty() {
+        let embed = create_success_embed("Escrows", "You have no escrow");
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let network_id = tip_context.network_id();
+
+    let lines: Vec<String> = escrows
+        .iter()
+        .rev()
+        .map(|escrow| {
+            let counterpart = match escrow.party_of(&owner_identifier) {
+                Some(EscrowActor::Buyer) => format!("to <@{}>", escrow.seller_identifier),
+                _ => format!("from <@{}>", escrow.buyer_identifier),
+            };
+
+            format!(
+                "#{}: {} {}, {}",
+                escrow.id,
+                sompi_to_spectre_string_with_suffix(escrow.amount_sompi, &network_id),
+                counterpart,
+                escrow.status
+            )
+        })
+        .collect();
+
+    let embed = create_success_embed("Escrows", "Most recent first").field(
+        "Escrows",
+        format_field_lines(lines),
+        false,
+    );
+    send_reply(ctx, embed, true).await
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    rename = "arbiter",
+    check = "check_guild_admin",
+    category = "wallet"
+)]
+/// set the role allowed to settle the escrows of this server
+pub async fn escrow_arbiter(
+    ctx: Context<'_>,
+    #[description = "Arbiter role"] role: serenity::Role,
+) -> Result<(), Error> {
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) => guild_id,
+        None => {
+            let embed = create_error_embed("Error", "Escrows are only available in a server");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    ctx.data()
+        .escrow_arbiter_store
+        .set_arbiter_role(guild_id.get(), role.id.get())
+        .await?;
+
+    let embed = create_success_embed(
+        "Escrow Arbiter Set",
+        &format!("<@&{}> can now release and refund escrows", role.id),
+    );
+    send_reply(ctx, embed, true).await
+}
@@ -29,6 +29,21 @@ pub async fn export(
         return send_reply(ctx, embed, true).await;
     }
 
+    let is_watch_only = tip_context
+        .owned_wallet_metadata_store
+        .find_owned_wallet_metadata_by_owner_identifier(&wallet_owner_identifier)
+        .await
+        .map(|metadata| metadata.is_watch_only())
+        .unwrap_or(false);
+
+    if is_watch_only {
+        let embed = create_error_embed(
+            "Error",
+            "This is a watch-only wallet, there is no mnemonic to export.",
+        );
+        return send_reply(ctx, embed, true).await;
+    }
+
     let tip_wallet = TipOwnedWallet::open(
         tip_context.clone(),
         &Secret::from(password.clone()),
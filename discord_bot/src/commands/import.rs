@@ -0,0 +1,105 @@
+use crate::utils::*;
+use core::tip_owned_wallet::TipOwnedWallet;
+use poise::{serenity_prelude::Colour, Modal};
+use spectre_wallet_keys::secret::Secret;
+
+use crate::models::{Context, Error};
+
+#[derive(Debug, poise::Modal)]
+#[name = "Confirm wallet replacement"]
+pub(crate) struct ReplacementModalConfirmation {
+    #[name = "write replace to confirm"]
+    pub(crate) first_input: String,
+}
+
+#[poise::command(slash_command, category = "wallet")]
+/// import a wallet from a private key (hex) or an extended private key (xprv)
+pub async fn import(
+    ctx: Context<'_>,
+    #[description = "private key (hex) or extended private key (xprv)"] key: String,
+    #[min_length = 10]
+    #[description = "new password"]
+    password: String,
+) -> Result<(), Error> {
+    let user = ctx.author().id;
+    let wallet_owner_identifier = user.to_string();
+
+    let tip_context = ctx.data();
+
+    let is_initiated = match tip_context.does_opened_owned_wallet_exists(&wallet_owner_identifier) {
+        true => true,
+        false => {
+            tip_context
+                .local_store()?
+                .exists(Some(&wallet_owner_identifier))
+                .await?
+        }
+    };
+
+    // the existing wallet may hold funds, it is only replaced on explicit confirmation
+    if is_initiated {
+        let confirmed = ReplacementModalConfirmation::execute(ctx)
+            .await?
+            .is_some_and(|data| data.first_input == "replace");
+
+        if !confirmed {
+            let embed = create_embed(
+                "Wallet Import Aborted",
+                "Your existing wallet was kept",
+                Colour::DARK_ORANGE,
+            );
+            return send_reply(ctx, embed, true).await;
+        }
+    }
+
+    ctx.defer_ephemeral().await?;
+
+    let key = key.trim();
+    let is_private_key = key.len() == 64 && key.chars().all(|c| c.is_ascii_hexdigit());
+
+    let import_result = match is_private_key {
+        true => {
+            TipOwnedWallet::import_private_key(
+                tip_context.clone(),
+                &Secret::from(password),
+                key,
+                &wallet_owner_identifier,
+            )
+            .await
+        }
+        false => {
+            TipOwnedWallet::import_xprv(
+                tip_context.clone(),
+                &Secret::from(password),
+                key,
+                &wallet_owner_identifier,
+            )
+            .await
+        }
+    };
+
+    let tip_wallet = match import_result {
+        Ok(w) => w,
+        Err(e) => {
+            let embed = create_error_embed("Error while importing the wallet", &e.to_string());
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let account_kind = match is_private_key {
+        true => "keypair",
+        false => "bip32",
+    };
+
+    let embed = create_success_embed(
+        "Wallet Imported Successfully",
+        &format!("Your {} wallet has been imported", account_kind),
+    )
+    .field(
+        "Receive Address",
+        tip_wallet.receive_address().to_string(),
+        false,
+    );
+
+    send_reply(ctx, embed, true).await
+}
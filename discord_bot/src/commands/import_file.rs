@@ -0,0 +1,99 @@
+use crate::{commands::import::ReplacementModalConfirmation, utils::*};
+use core::{error::Error as SpectreError, tip_owned_wallet::TipOwnedWallet};
+use poise::{
+    serenity_prelude::{self as serenity, Colour},
+    Modal,
+};
+use spectre_wallet_keys::secret::Secret;
+
+use crate::models::{Context, Error};
+
+// wallet files are a few kilobytes, anything bigger isn't a wallet backup
+const MAX_WALLET_FILE_SIZE: u32 = 1024 * 1024;
+
+#[poise::command(slash_command, category = "wallet")]
+/// import an encrypted wallet file produced by the backup command
+pub async fn import_file(
+    ctx: Context<'_>,
+    #[description = "wallet file"] file: serenity::Attachment,
+    #[min_length = 10]
+    #[description = "password of the wallet file"]
+    password: String,
+) -> Result<(), Error> {
+    let user = ctx.author().id;
+    let wallet_owner_identifier = user.to_string();
+
+    let tip_context = ctx.data();
+
+    if file.size > MAX_WALLET_FILE_SIZE || !file.filename.ends_with(".wallet") {
+        let embed = create_error_embed(
+            "Error while importing the wallet file",
+            "The attachment is not a wallet file",
+        );
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let is_initiated = match tip_context.does_opened_owned_wallet_exists(&wallet_owner_identifier) {
+        true => true,
+        false => {
+            tip_context
+                .local_store()?
+                .exists(Some(&wallet_owner_identifier))
+                .await?
+        }
+    };
+
+    // the existing wallet may hold funds, it is only replaced on explicit confirmation
+    if is_initiated {
+        let confirmed = ReplacementModalConfirmation::execute(ctx)
+            .await?
+            .is_some_and(|data| data.first_input == "replace");
+
+        if !confirmed {
+            let embed = create_embed(
+                "Wallet Import Aborted",
+                "Your existing wallet was kept",
+                Colour::DARK_ORANGE,
+            );
+            return send_reply(ctx, embed, true).await;
+        }
+    }
+
+    ctx.defer_ephemeral().await?;
+
+    let wallet_file_data = file.download().await?;
+
+    // the currently opened wallet is only replaced once the file decrypts with the password
+    let import_result = TipOwnedWallet::import_wallet_file(
+        tip_context.clone(),
+        &Secret::from(password),
+        &wallet_file_data,
+        &wallet_owner_identifier,
+    )
+    .await;
+
+    let tip_wallet = match import_result {
+        Ok(w) => w,
+        Err(SpectreError::WalletError(spectre_wallet_core::error::Error::WalletDecrypt(_))) => {
+            let embed =
+                create_error_embed("Error while importing the wallet file", "Password is wrong");
+            return send_reply(ctx, embed, true).await;
+        }
+        Err(e) => {
+            let embed = create_error_embed("Error while importing the wallet file", &e.to_string());
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let embed = create_success_embed(
+        "Wallet Imported Successfully",
+        "Your wallet file has been installed and opened",
+    )
+    .field(
+        "Receive Address",
+        tip_wallet.receive_address().to_string(),
+        false,
+    );
+
+    send_reply(ctx, embed, true).await
+}
@@ -0,0 +1,54 @@
+use crate::utils::*;
+use core::{
+    linked_address_metadata::LinkChallenge,
+    utils::{ensure_address_network, generate_link_challenge_message, now_unix_secs},
+};
+use spectre_wallet_core::prelude::Address;
+
+use crate::models::{Context, Error};
+
+const LINK_CHALLENGE_TTL_SECS: u64 = 10 * 60;
+
+#[poise::command(slash_command, category = "wallet")]
+/// start linking an external Spectre address to your discord account
+pub async fn link(
+    ctx: Context<'_>,
+    #[description = "External Spectre address"] address: String,
+) -> Result<(), Error> {
+    let external_address = match Address::try_from(address.as_str()) {
+        Ok(address) => address,
+        Err(_) => {
+            let embed =
+                create_error_embed("Error while linking the address", "Invalid Spectre address");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let owner_identifier = ctx.author().id.to_string();
+    let tip_context = ctx.data();
+
+    if let Err(e) = ensure_address_network(&external_address, tip_context.network_id()) {
+        let embed = create_error_embed("Error while linking the address", &e.to_string());
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let message = generate_link_challenge_message(&owner_identifier);
+
+    tip_context.add_link_challenge(
+        owner_identifier,
+        LinkChallenge {
+            address: external_address.clone(),
+            message: message.clone(),
+            expires_at: now_unix_secs() + LINK_CHALLENGE_TTL_SECS,
+        },
+    );
+
+    let embed = create_success_embed(
+        "Address Link Challenge",
+        "Sign the message below with the external wallet holding this address, then submit the signature with `/wallet link_confirm` within 10 minutes.",
+    )
+    .field("Address", external_address.to_string(), false)
+    .field("Message", format!("`{}`", message), false);
+
+    send_reply(ctx, embed, true).await
+}
@@ -0,0 +1,64 @@
+use crate::utils::*;
+use core::{
+    linked_address_metadata::LinkedAddressMetadata,
+    utils::{now_unix_secs, verify_message_signature},
+};
+
+use crate::models::{Context, Error};
+
+#[poise::command(slash_command, category = "wallet")]
+/// submit the signed link challenge to verify your external Spectre address
+pub async fn link_confirm(
+    ctx: Context<'_>,
+    #[description = "Signature of the challenge message (hex)"] signature: String,
+) -> Result<(), Error> {
+    let owner_identifier = ctx.author().id.to_string();
+    let tip_context = ctx.data();
+
+    let challenge = match tip_context.take_link_challenge(&owner_identifier) {
+        Some(challenge) if challenge.expires_at >= now_unix_secs() => challenge,
+        _ => {
+            let embed = create_error_embed(
+                "Error while linking the address",
+                "No pending challenge, start again with `/wallet link`",
+            );
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    match verify_message_signature(&challenge.address, &challenge.message, &signature) {
+        Ok(true) => (),
+        Ok(false) => {
+            let embed = create_error_embed(
+                "Error while linking the address",
+                "Signature does not match the challenge, start again with `/wallet link`",
+            );
+            return send_reply(ctx, embed, true).await;
+        }
+        Err(e) => {
+            let embed = create_error_embed("Error while linking the address", &e.to_string());
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    if let Err(e) = tip_context
+        .linked_address_metadata_store
+        .add(&LinkedAddressMetadata::new(
+            owner_identifier,
+            challenge.address.clone(),
+            now_unix_secs(),
+        ))
+        .await
+    {
+        let embed = create_error_embed("Error while linking the address", &e.to_string());
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let embed = create_success_embed(
+        "Address Linked",
+        "This address is now the default destination of `/wallet withdraw`",
+    )
+    .field("Address", challenge.address.to_string(), false);
+
+    send_reply(ctx, embed, true).await
+}
@@ -1,25 +1,77 @@
+pub mod backup;
+pub mod balance_roles;
+pub mod bounty;
 pub mod change_password;
 pub mod claim;
 pub mod close;
 pub mod compound;
 pub mod create;
 pub mod destroy;
+pub mod escrow;
 pub mod export;
+pub mod import;
+pub mod import_file;
+pub mod link;
+pub mod link_confirm;
 pub mod open;
+pub mod opt_out;
+pub mod rain;
+pub mod reaction_tips;
+pub mod receive;
+pub mod request;
+pub mod request_uri;
+pub mod requests;
 pub mod restore;
+pub mod schedule;
 pub mod send;
+pub mod send_many;
+pub mod shop;
+pub mod sign;
 pub mod status;
+pub mod tip_author;
+pub mod tip_role;
+pub mod tipadmin;
+pub mod treasury;
+pub mod unlink;
+pub mod verify;
+pub mod watch;
 pub mod withdraw;
 
+pub use backup::backup;
+pub use balance_roles::balance_roles;
+pub use bounty::bounty;
 pub use change_password::change_password;
 pub use claim::claim;
 pub use close::close;
 pub use compound::compound;
 pub use create::create;
 pub use destroy::destroy;
+pub use escrow::escrow;
 pub use export::export;
+pub use import::import;
+pub use import_file::import_file;
+pub use link::link;
+pub use link_confirm::link_confirm;
 pub use open::open;
+pub use opt_out::opt_out;
+pub use rain::rain;
+pub use reaction_tips::reaction_tips;
+pub use receive::receive;
+pub use request::request;
+pub use request_uri::request_uri;
+pub use requests::requests;
 pub use restore::restore;
+pub use schedule::schedule;
 pub use send::send;
+pub use send_many::send_many;
+pub use shop::shop;
+pub use sign::sign;
 pub use status::status;
+pub use tip_author::tip_author;
+pub use tip_role::tip_role;
+pub use tipadmin::tipadmin;
+pub use treasury::treasury;
+pub use unlink::unlink;
+pub use verify::verify;
+pub use watch::watch;
 pub use withdraw::withdraw;
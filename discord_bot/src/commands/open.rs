@@ -26,6 +26,24 @@ pub async fn open(
         return send_reply(ctx, embed, true).await;
     }
 
+    let is_watch_only = tip_context
+        .owned_wallet_metadata_store
+        .find_owned_wallet_metadata_by_owner_identifier(&wallet_owner_identifier)
+        .await
+        .map(|metadata| metadata.is_watch_only())
+        .unwrap_or(false);
+
+    if is_watch_only {
+        let tip_wallet =
+            TipOwnedWallet::open_watch_only(tip_context.clone(), &wallet_owner_identifier).await?;
+
+        let embed = create_success_embed(
+            "Watch-Only Wallet Opened",
+            &format!("Your wallet address: {}", tip_wallet.receive_address()),
+        );
+        return send_reply(ctx, embed, true).await;
+    }
+
     let tip_wallet_result = TipOwnedWallet::open(
         tip_context.clone(),
         &Secret::from(password),
@@ -46,5 +64,8 @@ pub async fn open(
         "Wallet Opened Successfully",
         &format!("Your wallet address: {}", tip_wallet.receive_address()),
     );
-    send_reply(ctx, embed, true).await
+
+    let attachment = create_qr_code_attachment(&tip_wallet.receive_address().to_string())?;
+
+    send_reply_with_attachment(ctx, with_qr_code_image(embed), attachment, true).await
 }
@@ -0,0 +1,29 @@
+use crate::utils::*;
+
+use crate::models::{Context, Error};
+
+#[poise::command(slash_command, category = "wallet")]
+/// exclude yourself from (or include yourself back in) role tips and rains
+pub async fn opt_out(
+    ctx: Context<'_>,
+    #[description = "Exclude yourself from mass tips"] enabled: bool,
+) -> Result<(), Error> {
+    let owner_identifier = ctx.author().id.to_string();
+
+    let tip_context = ctx.data();
+
+    tip_context
+        .opt_out_store
+        .set_opted_out(&owner_identifier, enabled)
+        .await?;
+
+    let embed = match enabled {
+        true => create_success_embed(
+            "Opted Out",
+            "You will no longer receive role tips and rains",
+        ),
+        false => create_success_embed("Opted In", "You will receive role tips and rains again"),
+    };
+
+    send_reply(ctx, embed, true).await
+}
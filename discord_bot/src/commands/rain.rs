@@ -0,0 +1,116 @@
+use crate::{distribution::*, utils::*};
+use core::utils::{now_unix_secs, try_parse_required_nonzero_spectre_as_sompi_u64};
+use poise::serenity_prelude::UserId;
+
+use crate::models::{Context, Error};
+
+const DEFAULT_RAIN_COUNT: usize = 10;
+const DEFAULT_RAIN_MINUTES: u64 = 10;
+
+// anti-abuse rules, freshly created accounts and single messages do not qualify
+const MIN_ACCOUNT_AGE_SECS: u64 = 7 * 24 * 60 * 60;
+const MIN_MESSAGES: usize = 3;
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    check = "check_not_frozen",
+    category = "wallet"
+)]
+/// split an amount among the users who recently posted in this channel
+pub async fn rain(
+    ctx: Context<'_>,
+    #[description = "Total amount"] amount: String,
+    #[min_length = 10]
+    #[description = "password"]
+    password: String,
+    #[min = 1]
+    #[max = 50]
+    #[description = "Maximum number of users (default 10)"]
+    count: Option<usize>,
+    #[min = 1]
+    #[max = 60]
+    #[description = "Activity window in minutes (default 10)"]
+    minutes: Option<u64>,
+    #[description = "Fee priority: low, normal, high or sompi amount"] priority: Option<String>,
+) -> Result<(), Error> {
+    let amount_sompi = try_parse_required_nonzero_spectre_as_sompi_u64(Some(amount))?;
+    let count = count.unwrap_or(DEFAULT_RAIN_COUNT);
+    let minutes = minutes.unwrap_or(DEFAULT_RAIN_MINUTES);
+
+    let author_id = ctx.author().id;
+    let tip_context = ctx.data();
+
+    let now = now_unix_secs();
+    let active_authors = tip_context.activity_tracker().find_active_authors(
+        ctx.channel_id().get(),
+        now.saturating_sub(minutes * 60),
+        MIN_MESSAGES,
+    );
+
+    let mut user_ids: Vec<UserId> = vec![];
+
+    for author in active_authors {
+        if user_ids.len() >= count {
+            break;
+        }
+
+        let user_id = UserId::new(author);
+
+        let account_age_secs =
+            now.saturating_sub(user_id.created_at().unix_timestamp().max(0) as u64);
+
+        if user_id == author_id
+            || account_age_secs < MIN_ACCOUNT_AGE_SECS
+            || tip_context
+                .opt_out_store
+                .is_opted_out(&user_id.to_string())
+                .await
+        {
+            continue;
+        }
+
+        user_ids.push(user_id);
+    }
+
+    if user_ids.is_empty() {
+        let embed = create_error_embed(
+            "Error",
+            &format!(
+                "Nobody qualifies for the rain: users need {} messages in the last {} minutes and an account older than 7 days",
+                MIN_MESSAGES, minutes
+            ),
+        );
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let amount_each_sompi = amount_sompi / user_ids.len() as u64;
+
+    if amount_each_sompi == 0 {
+        let embed = create_error_embed(
+            "Error",
+            "The amount is too small to be split among the active users",
+        );
+        return send_reply(ctx, embed, true).await;
+    }
+
+    distribute(
+        ctx,
+        Distribution {
+            operation: "rain",
+            description: format!(
+                "<@{}> made it rain on {} active users",
+                author_id,
+                user_ids.len()
+            ),
+            recipients: user_ids
+                .into_iter()
+                .map(|user_id| (user_id, amount_each_sompi))
+                .collect(),
+            mention: None,
+            password,
+            priority,
+        },
+    )
+    .await
+}
@@ -0,0 +1,70 @@
+use crate::utils::*;
+use core::error::Error as SpectreError;
+use spectre_wallet_keys::secret::Secret;
+
+use crate::models::{Context, Error};
+
+#[poise::command(slash_command, category = "wallet")]
+/// enable or disable tipping with emoji reactions from your opened wallet
+pub async fn reaction_tips(
+    ctx: Context<'_>,
+    #[description = "Enable reaction tips"] enabled: bool,
+    #[description = "password (required to enable)"] password: Option<String>,
+) -> Result<(), Error> {
+    let wallet_owner_identifier = ctx.author().id.to_string();
+
+    let tip_context = ctx.data();
+
+    if !enabled {
+        tip_context.disable_reaction_tips(&wallet_owner_identifier);
+
+        let embed = create_success_embed(
+            "Reaction Tips Disabled",
+            "Your reactions no longer send tips",
+        );
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let tip_wallet = match tip_context.get_opened_owned_wallet(&wallet_owner_identifier) {
+        Some(w) => w,
+        None => {
+            let embed = create_error_embed("Error", "Wallet not opened");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let wallet_secret = match password {
+        Some(password) => Secret::from(password),
+        None => {
+            let embed =
+                create_error_embed("Error", "The password is required to enable reaction tips");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    ctx.defer_ephemeral().await?;
+
+    match tip_wallet.verify_secret(&wallet_secret).await {
+        Ok(_) => (),
+        Err(SpectreError::WatchOnlyWallet()) => {
+            let embed = create_error_embed(
+                "Error",
+                "This is a watch-only wallet, it cannot sign transactions",
+            );
+            return send_reply(ctx, embed, true).await;
+        }
+        Err(SpectreError::WalletError(spectre_wallet_core::error::Error::WalletDecrypt(_))) => {
+            let embed = create_error_embed("Error", "Wrong password");
+            return send_reply(ctx, embed, true).await;
+        }
+        Err(e) => return Err(e.into()),
+    }
+
+    tip_context.enable_reaction_tips(wallet_owner_identifier, wallet_secret);
+
+    let embed = create_success_embed(
+        "Reaction Tips Enabled",
+        "Reacting with a configured emoji tips the author of the message, within the daily cap of the server. The password is kept in memory until you disable reaction tips or close the wallet.",
+    );
+    send_reply(ctx, embed, true).await
+}
@@ -0,0 +1,46 @@
+use crate::utils::*;
+use core::{payment_uri::PaymentUri, utils::try_parse_required_nonzero_spectre_as_sompi_u64};
+
+use crate::models::{Context, Error};
+
+#[poise::command(slash_command, category = "wallet")]
+/// show your receive address as a QR code, optionally as a payment request for an amount
+pub async fn receive(
+    ctx: Context<'_>,
+    #[description = "Requested amount"] amount: Option<String>,
+) -> Result<(), Error> {
+    let wallet_owner_identifier = ctx.author().id.to_string();
+    let tip_context = ctx.data();
+
+    let owned_wallet_metadata = match tip_context
+        .owned_wallet_metadata_store
+        .find_owned_wallet_metadata_by_owner_identifier(&wallet_owner_identifier)
+        .await
+    {
+        Ok(metadata) => metadata,
+        Err(_) => {
+            let embed = create_error_embed("Error", "Wallet not initiated yet");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let amount_sompi = match amount {
+        Some(amount) => Some(try_parse_required_nonzero_spectre_as_sompi_u64(Some(
+            amount,
+        ))?),
+        None => None,
+    };
+
+    // a bare address is encoded unless an amount turns it into a payment request
+    let payment_uri = PaymentUri::new(
+        owned_wallet_metadata.receive_address.clone(),
+        amount_sompi,
+        None,
+        None,
+    );
+
+    let embed = create_success_embed("Receive Address", &format!("`{}`", payment_uri));
+    let attachment = create_qr_code_attachment(&payment_uri.to_string())?;
+
+    send_reply_with_attachment(ctx, with_qr_code_image(embed), attachment, true).await
+}
@@ -0,0 +1,100 @@
+use crate::{payment_request::*, utils::*};
+use core::{
+    payment_request::{PaymentRequest, PAYMENT_REQUEST_TTL_SECS},
+    utils::{now_unix_secs, try_parse_required_nonzero_spectre_as_sompi_u64},
+};
+use poise::{
+    serenity_prelude::{self as serenity, CreateMessage},
+    CreateReply,
+};
+
+use crate::models::{Context, Error};
+
+#[poise::command(slash_command, category = "wallet")]
+/// request a payment from a user, who can pay it with a button
+pub async fn request(
+    ctx: Context<'_>,
+    #[description = "Request from"] user: serenity::User,
+    #[description = "Amount"] amount: String,
+    #[max_length = 200]
+    #[description = "Memo"]
+    memo: Option<String>,
+) -> Result<(), Error> {
+    if user.bot || user.system {
+        let embed = create_error_embed("Error", "User is a bot or a system user");
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let author = ctx.author();
+
+    if user.id == author.id {
+        let embed = create_error_embed("Error", "You cannot request a payment from yourself");
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let amount_sompi = try_parse_required_nonzero_spectre_as_sompi_u64(Some(amount))?;
+
+    let tip_context = ctx.data();
+
+    let created_at = now_unix_secs();
+
+    let mut request = PaymentRequest {
+        id: ctx.id(),
+        requester_identifier: author.id.to_string(),
+        payer_identifier: user.id.to_string(),
+        amount_sompi,
+        memo,
+        channel_id: ctx.channel_id().get(),
+        message_id: 0,
+        created_at,
+        expires_at: created_at + PAYMENT_REQUEST_TTL_SECS,
+    };
+
+    // the payer is asked by direct message when the guild keeps receipts out of this channel
+    if find_receipt_channel(ctx).await.is_none() {
+        let message = CreateMessage::new()
+            .embed(create_payment_request_embed(tip_context, &request))
+            .components(create_payment_request_buttons(request.id));
+
+        let direct_message = match user.direct_message(ctx, message).await {
+            Ok(direct_message) => direct_message,
+            Err(_) => {
+                let embed = create_error_embed(
+                    "Error",
+                    &format!(
+                        "<@{}> does not accept direct messages and requests are not public in this channel",
+                        user.id
+                    ),
+                );
+                return send_reply(ctx, embed, true).await;
+            }
+        };
+
+        // the buttons only work once the request is stored
+        request.channel_id = direct_message.channel_id.get();
+        request.message_id = direct_message.id.get();
+
+        tip_context.payment_request_store.add(&request).await?;
+
+        let embed = create_payment_request_embed(tip_context, &request)
+            .title("Payment Request Sent by Direct Message");
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let reply = ctx
+        .send(CreateReply {
+            content: Some(format!("<@{}>", user.id)),
+            embeds: vec![create_payment_request_embed(tip_context, &request)],
+            components: Some(create_payment_request_buttons(request.id)),
+            ephemeral: Some(false),
+            ..Default::default()
+        })
+        .await?;
+
+    // the buttons only work once the request is stored
+    request.message_id = reply.message().await?.id.get();
+
+    tip_context.payment_request_store.add(&request).await?;
+
+    Ok(())
+}
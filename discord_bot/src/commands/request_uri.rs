@@ -0,0 +1,45 @@
+use crate::utils::*;
+use core::{payment_uri::PaymentUri, utils::try_parse_required_nonzero_spectre_as_sompi_u64};
+
+use crate::models::{Context, Error};
+
+#[poise::command(slash_command, category = "wallet")]
+/// generate a spectre: payment uri for your receive address
+pub async fn request_uri(
+    ctx: Context<'_>,
+    #[description = "Requested amount"] amount: Option<String>,
+    #[description = "Label"] label: Option<String>,
+    #[description = "Message"] message: Option<String>,
+) -> Result<(), Error> {
+    let wallet_owner_identifier = ctx.author().id.to_string();
+    let tip_context = ctx.data();
+
+    let owned_wallet_metadata = match tip_context
+        .owned_wallet_metadata_store
+        .find_owned_wallet_metadata_by_owner_identifier(&wallet_owner_identifier)
+        .await
+    {
+        Ok(metadata) => metadata,
+        Err(_) => {
+            let embed = create_error_embed("Error", "Wallet not initiated yet");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let amount_sompi = match amount {
+        Some(amount) => Some(try_parse_required_nonzero_spectre_as_sompi_u64(Some(
+            amount,
+        ))?),
+        None => None,
+    };
+
+    let payment_uri = PaymentUri::new(
+        owned_wallet_metadata.receive_address,
+        amount_sompi,
+        label,
+        message,
+    );
+
+    let embed = create_success_embed("Payment Request URI", &format!("`{}`", payment_uri));
+    send_reply(ctx, embed, true).await
+}
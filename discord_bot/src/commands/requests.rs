@@ -0,0 +1,71 @@
+use crate::{payment_request::*, utils::*};
+use core::utils::now_unix_secs;
+use spectre_wallet_core::utils::sompi_to_spectre_string_with_suffix;
+
+use crate::models::{Context, Error};
+
+#[poise::command(slash_command, category = "wallet")]
+/// list your open payment requests, sent and received
+pub async fn requests(ctx: Context<'_>) -> Result<(), Error> {
+    let owner_identifier = ctx.author().id.to_string();
+
+    let tip_context = ctx.data();
+
+    ctx.defer_ephemeral().await?;
+
+    expire_payment_requests(ctx, tip_context).await?;
+
+    let open_requests = tip_context
+        .payment_request_store
+        .find_open_by_owner(&owner_identifier, now_unix_secs())
+        .await;
+
+    if open_requests.is_empty() {
+        let embed = create_success_embed("Payment Requests", "You have no open payment request");
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let network_id = tip_context.network_id();
+
+    let format_request = |counterpart: &str, request: &core::payment_request::PaymentRequest| {
+        format!(
+            "<@{}>: {} in <#{}>, expires <t:{}:R>{}",
+            counterpart,
+            sompi_to_spectre_string_with_suffix(request.amount_sompi, &network_id),
+            request.channel_id,
+            request.expires_at,
+            request
+                .memo
+                .as_ref()
+                .map(|memo| format!(" ({})", memo))
+                .unwrap_or_default()
+        )
+    };
+
+    let sent: Vec<String> = open_requests
+        .iter()
+        .filter(|request| request.requester_identifier == owner_identifier)
+        .map(|request| format_request(&request.payer_identifier, request))
+        .collect();
+
+    let received: Vec<String> = open_requests
+        .iter()
+        .filter(|request| request.payer_identifier == owner_identifier)
+        .map(|request| format_request(&request.requester_identifier, request))
+        .collect();
+
+    let mut embed = create_success_embed(
+        "Payment Requests",
+        "Open requests can be paid, declined or cancelled from their message",
+    );
+
+    if !received.is_empty() {
+        embed = embed.field("To Pay", format_field_lines(received), false);
+    }
+
+    if !sent.is_empty() {
+        embed = embed.field("Requested", format_field_lines(sent), false);
+    }
+
+    send_reply(ctx, embed, true).await
+}
@@ -0,0 +1,454 @@
+use crate::{schedule::*, transfer::*, utils::*};
+use core::{
+    error::Error as SpectreError,
+    fee_policy::FeePriority,
+    schedule::{Schedule, ScheduleStatus},
+    tip_custodial_wallet::TipCustodialWallet,
+    utils::{
+        build_custodial_wallet_identifier, build_idempotency_key, estimate_fees,
+        get_tx_explorer_url, now_unix_secs, try_parse_required_nonzero_spectre_as_sompi_u64,
+    },
+};
+use poise::{serenity_prelude as serenity, ChoiceParameter};
+use spectre_wallet_core::{tx::PaymentOutputs, utils::sompi_to_spectre_string_with_suffix};
+use spectre_wallet_keys::secret::Secret;
+use std::str::FromStr;
+
+use crate::models::{Context, Error};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+#[derive(Debug, poise::ChoiceParameter)]
+pub enum ScheduleInterval {
+    #[name = "daily"]
+    Daily,
+    #[name = "weekly"]
+    Weekly,
+    #[name = "biweekly"]
+    Biweekly,
+    #[name = "monthly"]
+    Monthly,
+}
+
+impl ScheduleInterval {
+    fn as_secs(&self) -> u64 {
+        match self {
+            ScheduleInterval::Daily => SECONDS_PER_DAY,
+            ScheduleInterval::Weekly => 7 * SECONDS_PER_DAY,
+            ScheduleInterval::Biweekly => 14 * SECONDS_PER_DAY,
+            // 30 days, payments do not follow calendar months
+            ScheduleInterval::Monthly => 30 * SECONDS_PER_DAY,
+        }
+    }
+}
+
+#[poise::command(
+    slash_command,
+    subcommands(
+        "schedule_create",
+        "schedule_list",
+        "schedule_pause",
+        "schedule_resume",
+        "schedule_cancel"
+    ),
+    category = "wallet"
+)]
+/// recurring tips paid from a pre-funded allowance
+pub async fn schedule(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/**
+ * schedule of the author, or an error embed to reply with
+ */
+async fn find_owned_schedule(ctx: Context<'_>, id: u64) -> Result<Schedule, serenity::CreateEmbed> {
+    match ctx.data().schedule_store.find_by_id(id).await {
+        Some(schedule) if schedule.owner_identifier == ctx.author().id.to_string() => Ok(schedule),
+        _ => Err(create_error_embed("Error", "Schedule not found")),
+    }
+}
+
+#[poise::command(
+    slash_command,
+    rename = "create",
+    check = "check_not_frozen",
+    category = "wallet"
+)]
+/// tip a user at a regular interval, from an allowance funded now
+pub async fn schedule_create(
+    ctx: Context<'_>,
+    #[description = "Tip to"] user: serenity::User,
+    #[description = "Amount of each tip"] amount: String,
+    #[description = "Interval between tips"] interval: ScheduleInterval,
+    #[description = "Amount set aside for the tips"] allowance: String,
+    #[min_length = 10]
+    #[description = "password"]
+    password: String,
+    #[description = "Fee priority: low, normal, high or sompi amount"] priority: Option<String>,
+) -> Result<(), Error> {
+    if user.bot || user.system {
+        let embed = create_error_embed("Error", "User is a bot or a system user");
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let author = ctx.author();
+
+    if user.id == author.id {
+        let embed = create_error_embed("Error", "You cannot schedule tips to yourself");
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let owner_identifier = author.id.to_string();
+
+    let tip_context = ctx.data();
+
+    let tip_wallet = match tip_context.get_opened_owned_wallet(&owner_identifier) {
+        Some(w) => w,
+        None => {
+            let embed = create_error_embed("Error", "Wallet not opened");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    if tip_wallet.is_watch_only() {
+        let embed = create_error_embed(
+            "Error",
+            "This is a watch-only wallet, it cannot sign transactions",
+        );
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let amount_sompi = try_parse_required_nonzero_spectre_as_sompi_u64(Some(amount))?;
+    let allowance_sompi = try_parse_required_nonzero_spectre_as_sompi_u64(Some(allowance))?;
+
+    if let Err(reason) = check_guild_tip_amount(tip_context, ctx.guild_id(), amount_sompi).await {
+        let embed = create_error_embed("Error", &reason);
+        return send_reply(ctx, embed, true).await;
+    }
+
+    if allowance_sompi < amount_sompi {
+        let embed = create_error_embed("Error", "The allowance must cover at least one tip");
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let fee_priority = match priority.map(|p| FeePriority::from_str(&p)).transpose() {
+        Ok(fee_priority) => fee_priority,
+        Err(e) => {
+            let embed = create_error_embed("Error", &e.to_string());
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let idempotency_key = build_idempotency_key("schedule-fund", ctx.id());
+
+    if tip_context
+        .outbox_store
+        .find_by_idempotency_key(&idempotency_key)
+        .await
+        .is_some()
+    {
+        let embed = create_error_embed("Error", "This request has already been processed");
+        return send_reply(ctx, embed, true).await;
+    }
+
+    ctx.defer_ephemeral().await?;
+
+    let account = tip_wallet.wallet().account()?;
+
+    // the allowance wallet only exists once confirmed, fees are estimated on an equivalent output
+    let outputs = PaymentOutputs::from((account.receive_address()?, allowance_sompi));
+    let base_fees_summary = estimate_fees(&account, outputs).await?;
+    let priority_fee = tip_context
+        .fee_policy()
+        .resolve_priority_fee(
+            tip_context.rpc_api(),
+            fee_priority,
+            base_fees_summary.aggregated_fees,
+        )
+        .await?;
+
+    let network_id = tip_context.network_id();
+    let now = now_unix_secs();
+    let interval_secs = interval.as_secs();
+
+    let preview = create_warning_embed(
+        "Scheduled Tip Preview",
+        "The allowance is moved to a wallet held by the bot, tips are paid from it without your password. Cancelling the schedule refunds what is left.",
+    )
+    .field("Recipient", format!("<@{}>", user.id), false)
+    .field(
+        "Amount",
+        sompi_to_spectre_string_with_suffix(amount_sompi, &network_id),
+        true,
+    )
+    .field("Interval", interval.name(), true)
+    .field("First Tip", format!("<t:{}:f>", now + interval_secs), true)
+    .field(
+        "Allowance",
+        sompi_to_spectre_string_with_suffix(allowance_sompi, &network_id),
+        true,
+    )
+    .field(
+        "Estimated Fee",
+        sompi_to_spectre_string_with_suffix(
+            base_fees_summary.aggregated_fees + priority_fee,
+            &network_id,
+        ),
+        true,
+    );
+
+    if !confirm_transaction_preview(ctx, preview).await? {
+        return Ok(());
+    }
+
+    let wallet_identifier = build_custodial_wallet_identifier("schedule", ctx.id());
+    let (allowance_wallet, allowance_secret) =
+        TipCustodialWallet::create(tip_context.clone(), &wallet_identifier).await?;
+
+    let schedule = tip_context
+        .schedule_store
+        .add(Schedule {
+            id: 0,
+            owner_identifier: owner_identifier.clone(),
+            recipient_identifier: user.id.to_string(),
+            amount_sompi,
+            interval_secs,
+            next_run_at: now + interval_secs,
+            wallet_identifier,
+            receive_address: allowance_wallet.receive_address(),
+            secret: allowance_secret,
+            // paused until the allowance is funded
+            status: ScheduleStatus::Paused,
+            runs: 0,
+            last_error: None,
+            created_at: now,
+        })
+        .await?;
+
+    let transfer = BackgroundTransfer {
+        idempotency_key: idempotency_key.clone(),
+        sender_identifier: owner_identifier,
+        recipient: TransferRecipient::Address(schedule.receive_address.clone()),
+        amount_sompi: allowance_sompi,
+        receiver_pays: false,
+        channel_id: ctx.channel_id().get(),
+        receipt: format!(
+            "Allowance of {} for scheduled tip #{} to <@{}>",
+            sompi_to_spectre_string_with_suffix(allowance_sompi, &network_id),
+            schedule.id,
+            user.id
+        ),
+    };
+
+    let tx_id = match send_background_transfer(
+        tip_context,
+        &account,
+        Secret::from(password),
+        fee_priority,
+        &transfer,
+    )
+    .await
+    {
+        Ok(tx_id) => tx_id,
+        Err(SpectreError::OutboxEntryAlreadyExists()) => {
+            tip_context.schedule_store.remove(schedule.id).await?;
+
+            let embed = create_error_embed("Error", "This request has already been processed");
+            return send_reply(ctx, embed, true).await;
+        }
+        Err(e) => {
+            tip_context.schedule_store.remove(schedule.id).await?;
+
+            let embed = create_error_embed("Error", &format!("Transaction failed: {}", e));
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    tip_context
+        .schedule_store
+        .set_status(schedule.id, ScheduleStatus::Active)
+        .await?;
+
+    tip_context
+        .outbox_store
+        .mark_delivered(&idempotency_key)
+        .await?;
+
+    let embed = create_success_embed(
+        &format!("Scheduled Tip #{} Created", schedule.id),
+        &format!(
+            "<@{}> receives {} {}, the first tip is sent <t:{}:R>",
+            user.id,
+            sompi_to_spectre_string_with_suffix(amount_sompi, &network_id),
+            interval.name(),
+            schedule.next_run_at
+        ),
+    )
+    .field(
+        "Allowance Transaction",
+        get_tx_explorer_url(&tx_id, network_id.network_type()),
+        false,
+    );
+    send_reply(ctx, embed, true).await
+}
+
+#[poise::command(slash_command, rename = "list", category = "wallet")]
+/// list your scheduled tips
+pub async fn schedule_list(ctx: Context<'_>) -> Result<(), Error> {
+    let tip_context = ctx.data();
+
+    let schedules: Vec<Schedule> = tip_context
+        .schedule_store
+        .find_by_owner(&ctx.author().id.to_string())
+        .await
+        .into_iter()
+        .filter(|schedule| schedule.status != ScheduleStatus::Cancelled)
+        .collect();
+
+    if schedules.is_empty() {
+        let embed = create_success_embed("Scheduled Tips", "You have no scheduled tip");
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let network_id = tip_context.network_id();
+
+    let lines: Vec<String> = schedules
+        .iter()
+        .map(|schedule| {
+            let state = match schedule.status {
+                ScheduleStatus::Active => format!("next <t:{}:R>", schedule.next_run_at),
+                _ => match &schedule.last_error {
+                    Some(error) => format!("paused ({})", error),
+                    None => "paused".to_string(),
+                },
+            };
+
+            format!(
+                "#{}: {} to <@{}> every {} days, {} paid, {}",
+                schedule.id,
+                sompi_to_spectre_string_with_suffix(schedule.amount_sompi, &network_id),
+                schedule.recipient_identifier,
+                schedule.interval_secs / SECONDS_PER_DAY,
+                schedule.runs,
+                state
+            )
+        })
+        .collect();
+
+    let embed = create_success_embed(
+        "Scheduled Tips",
+        "Allowances can be refilled by withdrawing to their address",
+    )
+    .field("Schedules", format_field_lines(lines), false);
+    send_reply(ctx, embed, true).await
+}
+
+#[poise::command(slash_command, rename = "pause", category = "wallet")]
+/// stop a scheduled tip until it is resumed
+pub async fn schedule_pause(
+    ctx: Context<'_>,
+    #[description = "Schedule id"] id: u64,
+) -> Result<(), Error> {
+    let schedule = match find_owned_schedule(ctx, id).await {
+        Ok(schedule) => schedule,
+        Err(embed) => return send_reply(ctx, embed, true).await,
+    };
+
+    if schedule.status != ScheduleStatus::Active {
+        let embed = create_error_embed(
+            "Error",
+            &format!("Scheduled tip #{} is {}", schedule.id, schedule.status),
+        );
+        return send_reply(ctx, embed, true).await;
+    }
+
+    ctx.data()
+        .schedule_store
+        .set_status(schedule.id, ScheduleStatus::Paused)
+        .await?;
+
+    let embed = create_success_embed(
+        "Scheduled Tip Paused",
+        &format!(
+            "Use `/wallet schedule resume {}` to restart it",
+            schedule.id
+        ),
+    );
+    send_reply(ctx, embed, true).await
+}
+
+#[poise::command(slash_command, rename = "resume", category = "wallet")]
+/// restart a paused scheduled tip
+pub async fn schedule_resume(
+    ctx: Context<'_>,
+    #[description = "Schedule id"] id: u64,
+) -> Result<(), Error> {
+    let schedule = match find_owned_schedule(ctx, id).await {
+        Ok(schedule) => schedule,
+        Err(embed) => return send_reply(ctx, embed, true).await,
+    };
+
+    if schedule.status != ScheduleStatus::Paused {
+        let embed = create_error_embed(
+            "Error",
+            &format!("Scheduled tip #{} is {}", schedule.id, schedule.status),
+        );
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let resumed = ctx
+        .data()
+        .schedule_store
+        .resume(schedule.id, now_unix_secs())
+        .await?;
+
+    let embed = create_success_embed(
+        "Scheduled Tip Resumed",
+        &format!("The next tip is sent <t:{}:R>", resumed.next_run_at),
+    );
+    send_reply(ctx, embed, true).await
+}
+
+#[poise::command(slash_command, rename = "cancel", category = "wallet")]
+/// stop a scheduled tip for good and get its remaining allowance back
+pub async fn schedule_cancel(
+    ctx: Context<'_>,
+    #[description = "Schedule id"] id: u64,
+) -> Result<(), Error> {
+    let schedule = match find_owned_schedule(ctx, id).await {
+        Ok(schedule) => schedule,
+        Err(embed) => return send_reply(ctx, embed, true).await,
+    };
+
+    let tip_context = ctx.data();
+
+    ctx.defer_ephemeral().await?;
+
+    // cancelled first, so the scheduler does not spend while the allowance is refunded
+    tip_context
+        .schedule_store
+        .set_status(schedule.id, ScheduleStatus::Cancelled)
+        .await?;
+
+    let embed = match refund_schedule_allowance(tip_context, &schedule).await {
+        Ok(Some(tx_id)) => create_success_embed(
+            "Scheduled Tip Cancelled",
+            "The remaining allowance has been sent back to your wallet",
+        )
+        .field(
+            "Explorer",
+            get_tx_explorer_url(&tx_id, tip_context.network_id().network_type()),
+            false,
+        ),
+        Ok(None) => create_success_embed("Scheduled Tip Cancelled", "The allowance was empty"),
+        Err(e) => create_warning_embed(
+            "Scheduled Tip Cancelled",
+            &format!(
+                "No more tips are sent, but refunding the allowance failed, cancel it again to retry: {}",
+                e
+            ),
+        ),
+    };
+
+    send_reply(ctx, embed, true).await
+}
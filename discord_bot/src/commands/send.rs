@@ -1,22 +1,34 @@
-use crate::utils::*;
+use crate::{
+    confirmation::*,
+    outbox::{find_chain_start_hash, send_with_outbox},
+    utils::*,
+};
 use core::{
     error::Error as SpectreError,
-    tip_transition_wallet::TipTransitionWallet,
-    utils::{get_tx_explorer_url, try_parse_required_nonzero_spectre_as_sompi_u64},
+    fee_policy::FeePriority,
+    outbox::OutboxEntry,
+    transaction_tracker::{TrackedTransaction, TransactionState},
+    utils::{
+        build_idempotency_key, estimate_fees, get_tx_explorer_url, now_unix_secs,
+        try_parse_required_nonzero_spectre_as_sompi_u64,
+    },
 };
 use poise::{
     serenity_prelude::{self as serenity, CreateMessage},
     CreateReply,
 };
-use spectre_wallet_core::tx::{Fees, PaymentOutputs};
+use spectre_wallet_core::{
+    tx::{Fees, PaymentOutputs},
+    utils::sompi_to_spectre_string_with_suffix,
+};
 use spectre_wallet_keys::secret::Secret;
-use std::sync::Arc;
+use std::str::FromStr;
 
 use workflow_core::abortable::Abortable;
 
 use crate::models::{Context, Error};
 
-#[poise::command(slash_command, category = "wallet")]
+#[poise::command(slash_command, check = "check_not_frozen", category = "wallet")]
 /// send to user the given amount
 pub async fn send(
     ctx: Context<'_>,
@@ -25,6 +37,21 @@ pub async fn send(
     #[min_length = 10]
     #[description = "password"]
     password: String,
+    #[description = "Fee priority: low, normal, high or sompi amount"] priority: Option<String>,
+) -> Result<(), Error> {
+    send_to_user(ctx, &user, amount, password, priority, None).await
+}
+
+/**
+ * send `amount` to `user` from the opened wallet of the author, the public receipt replies to `reply_to` if any
+ */
+pub async fn send_to_user(
+    ctx: Context<'_>,
+    user: &serenity::User,
+    amount: String,
+    password: String,
+    priority: Option<String>,
+    reply_to: Option<&serenity::Message>,
 ) -> Result<(), Error> {
     if user.bot || user.system {
         let embed = create_error_embed("Error", "User is a bot or a system user");
@@ -67,103 +94,251 @@ pub async fn send(
         }
     };
 
+    if tip_wallet.is_watch_only() {
+        let embed = create_error_embed(
+            "Error",
+            "This is a watch-only wallet, it cannot sign transactions",
+        );
+        return send_reply(ctx, embed, true).await;
+    }
+
     let amount_sompi = try_parse_required_nonzero_spectre_as_sompi_u64(Some(amount))?;
     println!("amount sompi {}", amount_sompi);
 
+    if let Err(reason) = check_guild_tip_amount(tip_context, ctx.guild_id(), amount_sompi).await {
+        let embed = create_error_embed("Error", &reason);
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let fee_priority = match priority.map(|p| FeePriority::from_str(&p)).transpose() {
+        Ok(fee_priority) => fee_priority,
+        Err(e) => {
+            let embed = create_error_embed("Error", &e.to_string());
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let idempotency_key = build_idempotency_key("send", ctx.id());
+
+    if tip_context
+        .outbox_store
+        .find_by_idempotency_key(&idempotency_key)
+        .await
+        .is_some()
+    {
+        let embed = create_error_embed("Error", "This request has already been processed");
+        return send_reply(ctx, embed, true).await;
+    }
+
+    ctx.defer_ephemeral().await?;
+
     let wallet = tip_wallet.wallet();
 
     // find address of recipient or create a temporary wallet
-    let existing_owned_wallet = tip_context
-        .owned_wallet_metadata_store
-        .find_owned_wallet_metadata_by_owner_identifier(&recipient_identifier)
-        .await;
-
-    let recipient_address = match existing_owned_wallet {
-        Ok(wallet) => wallet.receive_address,
-        Err(SpectreError::OwnedWalletNotFound()) => {
-            // find or create a temporary wallet
-            let transition_wallet_result = tip_context
-                .transition_wallet_metadata_store
-                .find_transition_wallet_metadata_by_identifier_couple(
-                    &author.id.to_string(),
-                    &recipient_identifier,
-                )
-                .await?;
-
-            match transition_wallet_result {
-                Some(wallet) => wallet.receive_address,
-                None => TipTransitionWallet::create(
-                    tip_context.clone(),
-                    &author.id.to_string(),
-                    &recipient_identifier,
-                )
-                .await?
-                .receive_address(),
-            }
-        }
+    let (address, recipient_has_owned_wallet) = match resolve_recipient_address(
+        tip_context,
+        &wallet_owner_identifier,
+        &recipient_identifier,
+    )
+    .await
+    {
+        Ok(resolved) => resolved,
         Err(e) => {
             let embed = create_error_embed("Error", &format!("Error: {:}", e));
             return send_reply(ctx, embed, true).await;
         }
     };
 
-    let address = recipient_address;
-
-    let outputs = PaymentOutputs::from((address, amount_sompi));
+    let outputs = PaymentOutputs::from((address.clone(), amount_sompi));
     let abortable = Abortable::default();
     let wallet_secret = Secret::from(password);
 
     let account = wallet.account()?;
 
-    let (summary, hashes) = match account
-        .send(
-            outputs.into(),
-            Fees::SenderPays(0),
-            None,
-            wallet_secret,
-            None,
-            &abortable,
-            Some(Arc::new(
-                move |ptx: &spectre_wallet_core::tx::PendingTransaction| {
-                    println!("tx notifier: {:?}", ptx);
-                },
-            )),
+    let base_fees_summary = estimate_fees(&account, outputs.clone()).await?;
+    let priority_fee = tip_context
+        .fee_policy()
+        .resolve_priority_fee(
+            tip_context.rpc_api(),
+            fee_priority,
+            base_fees_summary.aggregated_fees,
         )
-        .await
+        .await?;
+
+    let network_id = tip_context.network_id();
+    let estimated_fees = base_fees_summary.aggregated_fees + priority_fee;
+    let balance = account.balance().unwrap_or_default();
+
+    let preview = create_warning_embed(
+        "Transaction Preview",
+        "Review the transaction, it is signed and broadcasted only once confirmed",
+    )
+    .field("Recipient", format!("<@{}>", user.id), false)
+    .field(
+        "Amount",
+        sompi_to_spectre_string_with_suffix(amount_sompi, &network_id),
+        true,
+    )
+    .field(
+        "Estimated Fee",
+        sompi_to_spectre_string_with_suffix(estimated_fees, &network_id),
+        true,
+    )
+    .field(
+        "Resulting Balance",
+        sompi_to_spectre_string_with_suffix(
+            balance.mature.saturating_sub(amount_sompi + estimated_fees),
+            &network_id,
+        ),
+        true,
+    )
+    .field(
+        "UTXOs Spent",
+        base_fees_summary.aggregated_utxos.to_string(),
+        true,
+    );
+
+    if !confirm_transaction_preview(ctx, preview).await? {
+        return Ok(());
+    }
+
+    let receipt_channel_id = find_receipt_channel(ctx).await;
+
+    // recorded before signing, so the receipt can be re-posted if the bot stops midway
+    let outbox_entry = OutboxEntry::new(
+        idempotency_key.clone(),
+        wallet_owner_identifier.clone(),
+        ctx.channel_id().get(),
+        receipt_channel_id.is_some(),
+        format!(
+            "<@{}> sent <@{}> {}",
+            author.id,
+            user.id,
+            sompi_to_spectre_string_with_suffix(amount_sompi, &network_id)
+        ),
+        now_unix_secs(),
+    );
+
+    match tip_context.outbox_store.add(&outbox_entry).await {
+        Ok(_) => (),
+        Err(SpectreError::OutboxEntryAlreadyExists()) => {
+            let embed = create_error_embed("Error", "This request has already been processed");
+            return send_reply(ctx, embed, true).await;
+        }
+        Err(e) => return Err(e.into()),
+    }
+
+    let (summary, tx_ids) = match send_with_outbox(
+        tip_context,
+        &account,
+        outputs.into(),
+        Fees::SenderPays(priority_fee),
+        wallet_secret,
+        &abortable,
+        &idempotency_key,
+    )
+    .await
     {
         Ok(result) => result,
         Err(e) => {
+            forget_unsubmitted_outbox_entry(tip_context, &idempotency_key).await?;
+
             let embed = create_error_embed("Error", &format!("Transaction failed: {}", e));
             return send_reply(ctx, embed, true).await;
         }
     };
 
-    let tx_id = hashes[0].to_string();
+    // the last transaction is the one paying the recipient, the previous ones compound utxos
+    let final_tx_id = tx_ids[tx_ids.len() - 1].clone();
 
-    let embed = create_success_embed(
-        "Transaction Successful",
+    let public_embed = create_success_embed(
+        "Transaction Submitted",
         &format!("<@{}> sent <@{}>: {}", author.id, user.id, summary),
     )
-    .field("Txid", format!("{:?}", tx_id.clone()), false)
+    .field("Txid", format!("{:?}", final_tx_id.clone()), false)
     .field(
         "Explorer",
-        get_tx_explorer_url(&tx_id, tip_context.network_id().network_type()),
+        get_tx_explorer_url(&final_tx_id, tip_context.network_id().network_type()),
+        false,
+    )
+    .field(
+        "Fee",
+        sompi_to_spectre_string_with_suffix(summary.aggregated_fees, &tip_context.network_id()),
         false,
     );
 
-    // public mentionning
-    let public_message = CreateMessage::new()
-        .add_embeds(vec![embed])
-        .content(format!("<@{}>", user.id));
-    ctx.channel_id().send_message(ctx, public_message).await?;
-
-    // private mentionning
-    ctx.send(CreateReply {
-        content: Some("Transaction Successful".into()),
-        ephemeral: Some(true),
-        ..Default::default()
-    })
-    .await?;
+    // public mentionning, unless the guild keeps receipts private
+    let public_message = match receipt_channel_id {
+        Some(channel_id) => {
+            let mut public_message = CreateMessage::new()
+                .add_embeds(vec![with_transaction_state(
+                    public_embed.clone(),
+                    TransactionState::Pending,
+                )])
+                .content(format!("<@{}>", user.id));
+
+            if let Some(message) = reply_to {
+                public_message = public_message.reference_message(message);
+            }
+
+            let public_message = channel_id.send_message(ctx, public_message).await?;
+
+            Some((channel_id, public_message.id))
+        }
+        None => None,
+    };
+
+    tip_context
+        .outbox_store
+        .mark_delivered(&idempotency_key)
+        .await?;
+
+    // private mentionning, carrying the receipt when it is not public
+    let private_embeds = match public_message {
+        Some(_) => vec![],
+        None => vec![with_transaction_state(
+            public_embed.clone(),
+            TransactionState::Pending,
+        )],
+    };
+
+    let private_reply = ctx
+        .send(CreateReply {
+            content: Some(format!("Transaction {}", TransactionState::Pending)),
+            embeds: private_embeds,
+            ephemeral: Some(true),
+            ..Default::default()
+        })
+        .await?;
+    let private_message_id = private_reply.message().await?.id;
+
+    let recipient_notification = match recipient_has_owned_wallet {
+        true => format!(
+            "<@{}> sent you {}, the funds are now spendable",
+            author.id,
+            sompi_to_spectre_string_with_suffix(amount_sompi, &network_id)
+        ),
+        false => format!(
+            "<@{}> sent you {}, create a wallet and use `claim` to receive the funds",
+            author.id,
+            sompi_to_spectre_string_with_suffix(amount_sompi, &network_id)
+        ),
+    };
+
+    let chain_start_hash = find_chain_start_hash(tip_context, &idempotency_key).await;
+
+    spawn_confirmation_tracking(
+        tip_context.clone(),
+        TrackedTransaction::new(final_tx_id, address, chain_start_hash),
+        ConfirmationMessages {
+            http: ctx.serenity_context().http.clone(),
+            public_message,
+            public_embed,
+            interaction: ctx.interaction.clone(),
+            private_message_id,
+            recipients: vec![(user.id, recipient_notification)],
+        },
+    );
 
     Ok(())
 }
@@ -0,0 +1,82 @@
+use crate::{distribution::*, utils::*};
+use core::utils::try_parse_required_nonzero_spectre_as_sompi_u64;
+use poise::serenity_prelude::{self as serenity, UserId};
+
+use crate::models::{Context, Error};
+
+#[poise::command(slash_command, check = "check_not_frozen", category = "wallet")]
+/// send to several users in a single transaction
+pub async fn send_many(
+    ctx: Context<'_>,
+    #[description = "Users to send to (mentions)"] users: String,
+    #[description = "One amount for everyone, or one per user (comma separated)"] amounts: String,
+    #[min_length = 10]
+    #[description = "password"]
+    password: String,
+    #[description = "Fee priority: low, normal, high or sompi amount"] priority: Option<String>,
+) -> Result<(), Error> {
+    let mut user_ids: Vec<UserId> = vec![];
+
+    for mention in users.replace('>', "> ").split_whitespace() {
+        let user_id = match serenity::utils::parse_user_mention(mention) {
+            Some(user_id) => user_id,
+            None => {
+                let embed =
+                    create_error_embed("Error", &format!("'{}' is not a user mention", mention));
+                return send_reply(ctx, embed, true).await;
+            }
+        };
+
+        if !user_ids.contains(&user_id) {
+            user_ids.push(user_id);
+        }
+    }
+
+    let mut amounts_sompi = vec![];
+    for amount in amounts.split(',') {
+        amounts_sompi.push(try_parse_required_nonzero_spectre_as_sompi_u64(Some(
+            amount.trim(),
+        ))?);
+    }
+
+    let amounts_sompi = match amounts_sompi.len() {
+        1 => vec![amounts_sompi[0]; user_ids.len()],
+        count if count == user_ids.len() => amounts_sompi,
+        _ => {
+            let embed = create_error_embed(
+                "Error",
+                "Give either one amount, or exactly one amount per user",
+            );
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    for user_id in &user_ids {
+        let user = user_id.to_user(ctx).await?;
+
+        if user.bot || user.system {
+            let embed = create_error_embed(
+                "Error",
+                &format!("<@{}> is a bot or a system user", user.id),
+            );
+            return send_reply(ctx, embed, true).await;
+        }
+    }
+
+    distribute(
+        ctx,
+        Distribution {
+            operation: "send_many",
+            description: format!(
+                "<@{}> sent funds to {} users",
+                ctx.author().id,
+                user_ids.len()
+            ),
+            recipients: user_ids.into_iter().zip(amounts_sompi).collect(),
+            mention: None,
+            password,
+            priority,
+        },
+    )
+    .await
+}
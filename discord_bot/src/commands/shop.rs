@@ -0,0 +1,426 @@
+use crate::{role_shop::*, transfer::*, utils::*};
+use core::{
+    error::Error as SpectreError,
+    fee_policy::FeePriority,
+    role_shop::{RolePurchase, RolePurchaseStatus, ShopRole},
+    utils::{
+        build_idempotency_key, estimate_fees, now_unix_secs,
+        try_parse_required_nonzero_spectre_as_sompi_u64,
+    },
+};
+use poise::serenity_prelude as serenity;
+use spectre_wallet_core::{tx::PaymentOutputs, utils::sompi_to_spectre_string_with_suffix};
+use spectre_wallet_keys::secret::Secret;
+use std::str::FromStr;
+
+use crate::models::{Context, Error};
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommands("shop_catalog", "shop_buy", "shop_roles", "shop_add", "shop_remove"),
+    category = "wallet"
+)]
+/// roles sold by the server, paid to its treasury
+pub async fn shop(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+#[poise::command(slash_command, guild_only, rename = "catalog", category = "wallet")]
+/// show the roles on sale in this server
+pub async fn shop_catalog(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) => guild_id.get(),
+        None => {
+            let embed = create_error_embed("Error", "The shop is only available in a server");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let tip_context = ctx.data();
+
+    let shop_roles = tip_context.shop_role_store.find_by_guild_id(guild_id).await;
+
+    if shop_roles.is_empty() {
+        let embed = create_success_embed("Shop", "No role is on sale");
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let lines: Vec<String> = shop_roles
+        .iter()
+        .map(|shop_role| format_shop_role(tip_context, shop_role))
+        .collect();
+
+    let embed = create_success_embed(
+        "Shop",
+        "Buy a role with `/wallet shop buy <role>`, payments go to the server treasury",
+    )
+    .field("Roles", format_field_lines(lines), false);
+    send_reply(ctx, embed, false).await
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    rename = "buy",
+    check = "check_not_frozen",
+    category = "wallet"
+)]
+/// buy a role, it is assigned once the payment is confirmed
+pub async fn shop_buy(
+    ctx: Context<'_>,
+    #[description = "Role"] role: serenity::Role,
+    #[min_length = 10]
+    #[description = "password"]
+    password: String,
+    #[description = "Fee priority: low, normal, high or sompi amount"] priority: Option<String>,
+) -> Result<(), Error> {
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) => guild_id.get(),
+        None => {
+            let embed = create_error_embed("Error", "The shop is only available in a server");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let tip_context = ctx.data();
+
+    let shop_role = match tip_context
+        .shop_role_store
+        .find(guild_id, role.id.get())
+        .await
+    {
+        Some(shop_role) => shop_role,
+        None => {
+            let embed = create_error_embed("Error", "This role is not on sale");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    // the price may have been set before the limits of the guild changed
+    if let Err(reason) =
+        check_guild_tip_amount(tip_context, ctx.guild_id(), shop_role.price_sompi).await
+    {
+        let embed = create_error_embed("Error", &reason);
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let treasury = match tip_context.treasury_store.find_by_guild_id(guild_id).await {
+        Some(treasury) => treasury,
+        None => {
+            let embed = create_error_embed("Error", "This server has no treasury to pay");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let buyer_identifier = ctx.author().id.to_string();
+
+    let tip_wallet = match tip_context.get_opened_owned_wallet(&buyer_identifier) {
+        Some(w) => w,
+        None => {
+            let embed = create_error_embed("Error", "Wallet not opened");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    if tip_wallet.is_watch_only() {
+        let embed = create_error_embed(
+            "Error",
+            "This is a watch-only wallet, it cannot sign transactions",
+        );
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let fee_priority = match priority.map(|p| FeePriority::from_str(&p)).transpose() {
+        Ok(fee_priority) => fee_priority,
+        Err(e) => {
+            let embed = create_error_embed("Error", &e.to_string());
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let idempotency_key = build_idempotency_key("shop-buy", ctx.id());
+
+    if tip_context
+        .outbox_store
+        .find_by_idempotency_key(&idempotency_key)
+        .await
+        .is_some()
+    {
+        let embed = create_error_embed("Error", "This request has already been processed");
+        return send_reply(ctx, embed, true).await;
+    }
+
+    ctx.defer_ephemeral().await?;
+
+    let account = tip_wallet.wallet().account()?;
+
+    let outputs = PaymentOutputs::from((treasury.receive_address.clone(), shop_role.price_sompi));
+    let base_fees_summary = estimate_fees(&account, outputs).await?;
+    let priority_fee = tip_context
+        .fee_policy()
+        .resolve_priority_fee(
+            tip_context.rpc_api(),
+            fee_priority,
+            base_fees_summary.aggregated_fees,
+        )
+        .await?;
+
+    let network_id = tip_context.network_id();
+    let price_formatted = sompi_to_spectre_string_with_suffix(shop_role.price_sompi, &network_id);
+
+    let preview = create_warning_embed(
+        "Role Purchase Preview",
+        "The role is assigned once the payment to the server treasury is confirmed. Buying a role you already hold extends it.",
+    )
+    .field("Role", format!("<@&{}>", shop_role.role_id), true)
+    .field(
+        "Duration",
+        format_shop_role_duration(shop_role.duration_secs),
+        true,
+    )
+    .field("Price", price_formatted.clone(), true)
+    .field(
+        "Estimated Fee",
+        sompi_to_spectre_string_with_suffix(
+            base_fees_summary.aggregated_fees + priority_fee,
+            &network_id,
+        ),
+        true,
+    );
+
+    if !confirm_transaction_preview(ctx, preview).await? {
+        return Ok(());
+    }
+
+    let transfer = BackgroundTransfer {
+        idempotency_key: idempotency_key.clone(),
+        sender_identifier: buyer_identifier.clone(),
+        recipient: TransferRecipient::Address(treasury.receive_address.clone()),
+        amount_sompi: shop_role.price_sompi,
+        receiver_pays: false,
+        channel_id: ctx.channel_id().get(),
+        receipt: format!(
+            "Purchase of the role <@&{}> for {}",
+            shop_role.role_id, price_formatted
+        ),
+    };
+
+    let tx_id = match send_background_transfer(
+        tip_context,
+        &account,
+        Secret::from(password),
+        fee_priority,
+        &transfer,
+    )
+    .await
+    {
+        Ok(tx_id) => tx_id,
+        Err(SpectreError::OutboxEntryAlreadyExists()) => {
+            let embed = create_error_embed("Error", "This request has already been processed");
+            return send_reply(ctx, embed, true).await;
+        }
+        Err(e) => {
+            let embed = create_error_embed("Error", &format!("Transaction failed: {}", e));
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let purchase = tip_context
+        .role_purchase_store
+        .add(RolePurchase {
+            id: 0,
+            guild_id,
+            role_id: shop_role.role_id,
+            buyer_identifier,
+            price_sompi: shop_role.price_sompi,
+            duration_secs: shop_role.duration_secs,
+            tx_id,
+            status: RolePurchaseStatus::Pending,
+            error: None,
+            created_at: now_unix_secs(),
+            expires_at: None,
+            reminded: false,
+        })
+        .await?;
+
+    tip_context
+        .outbox_store
+        .mark_delivered(&idempotency_key)
+        .await?;
+
+    spawn_role_purchase_verification(
+        ctx.serenity_context().http.clone(),
+        tip_context.clone(),
+        purchase,
+    );
+
+    let embed = create_success_embed(
+        "Payment Sent",
+        &format!(
+            "<@&{}> is assigned once the payment is confirmed, you will be notified by direct message",
+            shop_role.role_id
+        ),
+    );
+    send_reply(ctx, embed, true).await
+}
+
+#[poise::command(slash_command, guild_only, rename = "roles", category = "wallet")]
+/// list the roles you bought in this server
+pub async fn shop_roles(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) => guild_id.get(),
+        None => {
+            let embed = create_error_embed("Error", "The shop is only available in a server");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let tip_context = ctx.data();
+
+    let purchases = tip_context
+        .role_purchase_store
+        .find_by_buyer(guild_id, &ctx.author().id.to_string())
+        .await;
+
+    if purchases.is_empty() {
+        let embed = create_success_embed("Purchased Roles", "You have not bought any role");
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let lines: Vec<String> = purchases
+        .iter()
+        .map(|purchase| {
+            let expiry = match (purchase.status, purchase.expires_at) {
+                (RolePurchaseStatus::Active, Some(expires_at)) => {
+                    format!(", expires <t:{}:R>", expires_at)
+                }
+                _ => String::new(),
+            };
+
+            format!(
+                "#{}: <@&{}> {}{}",
+                purchase.id, purchase.role_id, purchase.status, expiry
+            )
+        })
+        .collect();
+
+    let embed = create_success_embed("Purchased Roles", "Most recent first").field(
+        "Purchases",
+        format_field_lines(lines),
+        false,
+    );
+    send_reply(ctx, embed, true).await
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    rename = "add",
+    check = "check_guild_admin",
+    category = "wallet"
+)]
+/// put a role on sale, or change its price and duration
+pub async fn shop_add(
+    ctx: Context<'_>,
+    #[description = "Role"] role: serenity::Role,
+    #[description = "Price"] price: String,
+    #[min = 1]
+    #[max = 365]
+    #[description = "Days the role is held (permanent by default)"]
+    days: Option<u64>,
+) -> Result<(), Error> {
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) => guild_id.get(),
+        None => {
+            let embed = create_error_embed("Error", "The shop is only available in a server");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    if role.managed || role.id.get() == guild_id {
+        let embed = create_error_embed("Error", "This role cannot be assigned by the bot");
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let tip_context = ctx.data();
+
+    if tip_context
+        .treasury_store
+        .find_by_guild_id(guild_id)
+        .await
+        .is_none()
+    {
+        let embed = create_error_embed(
+            "Error",
+            "Roles are paid to the server treasury, set it up with `/wallet treasury setup` first",
+        );
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let price_sompi = try_parse_required_nonzero_spectre_as_sompi_u64(Some(price))?;
+
+    if let Err(reason) = check_guild_tip_amount(tip_context, ctx.guild_id(), price_sompi).await {
+        let embed = create_error_embed("Error", &reason);
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let shop_role = ShopRole {
+        guild_id,
+        role_id: role.id.get(),
+        price_sompi,
+        duration_secs: days.map(|days| days * SECONDS_PER_DAY),
+    };
+
+    tip_context.shop_role_store.set(&shop_role).await?;
+
+    let embed = create_success_embed(
+        "Role On Sale",
+        &format!(
+            "{}, the bot needs the Manage Roles permission and a role above it",
+            format_shop_role(tip_context, &shop_role)
+        ),
+    );
+    send_reply(ctx, embed, true).await
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    rename = "remove",
+    check = "check_guild_admin",
+    category = "wallet"
+)]
+/// remove a role from sale, roles already bought run until their expiry
+pub async fn shop_remove(
+    ctx: Context<'_>,
+    #[description = "Role"] role: serenity::Role,
+) -> Result<(), Error> {
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) => guild_id.get(),
+        None => {
+            let embed = create_error_embed("Error", "The shop is only available in a server");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    match ctx
+        .data()
+        .shop_role_store
+        .remove(guild_id, role.id.get())
+        .await
+    {
+        Ok(()) => {
+            let embed = create_success_embed(
+                "Role Removed From Sale",
+                &format!("<@&{}> can no longer be bought", role.id),
+            );
+            send_reply(ctx, embed, true).await
+        }
+        Err(SpectreError::ShopRoleNotFound()) => {
+            let embed = create_error_embed("Error", "This role is not on sale");
+            send_reply(ctx, embed, true).await
+        }
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,71 @@
+use crate::utils::*;
+use core::error::Error as SpectreError;
+use spectre_wallet_keys::secret::Secret;
+
+use crate::models::{Context, Error};
+
+#[poise::command(slash_command, category = "wallet")]
+/// sign a message with the key of your receive address
+pub async fn sign(
+    ctx: Context<'_>,
+    #[description = "Message to sign"] message: String,
+    #[min_length = 10]
+    #[description = "password"]
+    password: String,
+) -> Result<(), Error> {
+    let author = ctx.author();
+    let wallet_owner_identifier = author.id.to_string();
+
+    let tip_context = ctx.data();
+
+    let is_opened = tip_context.does_opened_owned_wallet_exists(&wallet_owner_identifier);
+    let is_initiated = match is_opened {
+        true => true,
+        false => {
+            tip_context
+                .local_store()?
+                .exists(Some(&wallet_owner_identifier))
+                .await?
+        }
+    };
+
+    if !is_initiated {
+        let embed = create_error_embed("Error", "Wallet not initiated yet");
+        return send_reply(ctx, embed, true).await;
+    }
+
+    if !is_opened {
+        let embed = create_error_embed("Error", "Wallet not opened");
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let tip_wallet = match tip_context.get_opened_owned_wallet(&wallet_owner_identifier) {
+        Some(w) => w,
+        None => {
+            let embed = create_error_embed("Error", "Unexpected error: wallet not opened");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let signature = match tip_wallet
+        .sign_message(&Secret::from(password), &message)
+        .await
+    {
+        Ok(signature) => signature,
+        Err(SpectreError::WalletError(spectre_wallet_core::error::Error::WalletDecrypt(_))) => {
+            let embed = create_error_embed("Error while signing the message", "Password is wrong");
+            return send_reply(ctx, embed, true).await;
+        }
+        Err(e) => {
+            let embed = create_error_embed("Error while signing the message", &e.to_string());
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let embed = create_success_embed("Message Signed", "")
+        .field("Address", tip_wallet.receive_address().to_string(), false)
+        .field("Message", message, false)
+        .field("Signature", signature, false);
+
+    send_reply(ctx, embed, true).await
+}
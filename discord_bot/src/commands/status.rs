@@ -1,4 +1,4 @@
-use core::tip_transition_wallet::TipTransitionWallet;
+use core::{tip_owned_wallet::TipOwnedWallet, tip_transition_wallet::TipTransitionWallet};
 use futures::future::join_all;
 
 use crate::utils::*;
@@ -34,6 +34,21 @@ pub async fn status(ctx: Context<'_>) -> Result<(), Error> {
         return send_reply(ctx, embed, true).await;
     }
 
+    let is_watch_only = tip_context
+        .owned_wallet_metadata_store
+        .find_owned_wallet_metadata_by_owner_identifier(&wallet_owner_identifier)
+        .await
+        .map(|metadata| metadata.is_watch_only())
+        .unwrap_or(false);
+
+    // watch-only wallets don't need a password, open them on demand
+    let is_opened = if !is_opened && is_watch_only {
+        TipOwnedWallet::open_watch_only(tip_context.clone(), &wallet_owner_identifier).await?;
+        true
+    } else {
+        is_opened
+    };
+
     if !is_opened {
         let embed = create_error_embed(
             "Wallet Status",
@@ -90,6 +105,13 @@ pub async fn status(ctx: Context<'_>) -> Result<(), Error> {
     .into_iter()
     .reduce(|a, b| a + b);
 
+    let recent_transactions = owned_wallet.recent_transactions(5).await?;
+
+    let linked_addresses = tip_context
+        .linked_address_metadata_store
+        .find_linked_addresses_by_owner_identifier(&wallet_owner_identifier)
+        .await?;
+
     let network_type = tip_context.network_id();
     let balance_formatted = sompi_to_spectre_string_with_suffix(balance.mature, &network_type);
     let pending_balance_formatted =
@@ -98,7 +120,12 @@ pub async fn status(ctx: Context<'_>) -> Result<(), Error> {
     let pending_transition_balance_formatted =
         sompi_to_spectre_string_with_suffix(pending_transition_balance.unwrap_or(0), &network_type);
 
-    let embed = create_success_embed("Wallet Status", "")
+    let title = match owned_wallet.is_watch_only() {
+        true => "Wallet Status (watch-only)",
+        false => "Wallet Status",
+    };
+
+    let embed = create_success_embed(title, "")
         .field("Balance", balance_formatted, true)
         .field("Pending Balance", pending_balance_formatted, true)
         .field("UTXO count", balance.mature_utxo_count.to_string(), true)
@@ -111,7 +138,27 @@ pub async fn status(ctx: Context<'_>) -> Result<(), Error> {
             "Balance to be claimed",
             pending_transition_balance_formatted,
             true,
+        )
+        .field(
+            "Recent Transactions",
+            format_transaction_history(tip_context, &recent_transactions),
+            false,
         );
 
-    send_reply(ctx, embed, true).await
+    let embed = match linked_addresses.is_empty() {
+        true => embed,
+        false => embed.field(
+            "Linked Addresses",
+            linked_addresses
+                .iter()
+                .map(|linked_address| format!("`{}`", linked_address.address))
+                .collect::<Vec<String>>()
+                .join("\n"),
+            false,
+        ),
+    };
+
+    let attachment = create_qr_code_attachment(&owned_wallet.receive_address().to_string())?;
+
+    send_reply_with_attachment(ctx, with_qr_code_image(embed), attachment, true).await
 }
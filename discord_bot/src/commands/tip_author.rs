@@ -0,0 +1,57 @@
+use crate::{commands::send::send_to_user, utils::*};
+use poise::{serenity_prelude as serenity, Modal};
+use std::time::Duration;
+
+use crate::models::{Context, Error};
+
+// time left to fill the modal before the tip is dropped
+const TIP_AUTHOR_MODAL_TIMEOUT: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Modal)]
+#[name = "Tip author"]
+struct TipAuthorModal {
+    #[name = "Amount"]
+    #[placeholder = "Amount in SPR"]
+    amount: String,
+    #[name = "Password"]
+    #[min_length = 10]
+    password: String,
+    #[name = "Fee priority"]
+    #[placeholder = "low, normal, high or an amount in sompi"]
+    priority: Option<String>,
+}
+
+#[poise::command(
+    context_menu_command = "Tip author",
+    check = "check_not_frozen",
+    category = "wallet"
+)]
+/// tip the author of a message
+pub async fn tip_author(
+    ctx: Context<'_>,
+    #[description = "Message to tip"] message: serenity::Message,
+) -> Result<(), Error> {
+    // a modal must be the first response, checks depending on it are done by the send logic
+    if message.author.bot || message.author.system {
+        let embed = create_error_embed("Error", "User is a bot or a system user");
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let modal =
+        match poise::execute_modal(ctx, None::<TipAuthorModal>, Some(TIP_AUTHOR_MODAL_TIMEOUT))
+            .await?
+        {
+            Some(modal) => modal,
+            None => return Ok(()),
+        };
+
+    send_to_user(
+        ctx,
+        &message.author,
+        modal.amount,
+        modal.password,
+        modal.priority,
+        Some(&message),
+    )
+    .await
+}
@@ -0,0 +1,134 @@
+use crate::{distribution::*, utils::*};
+use core::utils::try_parse_required_nonzero_spectre_as_sompi_u64;
+use poise::serenity_prelude::{self as serenity, UserId};
+
+use crate::models::{Context, Error};
+
+#[derive(Debug, poise::ChoiceParameter)]
+pub enum SplitMode {
+    #[name = "total"]
+    Total,
+    #[name = "each"]
+    Each,
+}
+
+// page size of the guild members listing
+const MEMBERS_PAGE_SIZE: u64 = 1000;
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    check = "check_not_frozen",
+    category = "wallet"
+)]
+/// tip every member of a role, splitting a total amount or paying a fixed amount each
+pub async fn tip_role(
+    ctx: Context<'_>,
+    #[description = "Role to tip"] role: serenity::Role,
+    #[description = "Split the amount (total) or pay it to everyone (each)"] mode: SplitMode,
+    #[description = "Amount"] amount: String,
+    #[min_length = 10]
+    #[description = "password"]
+    password: String,
+    #[description = "Fee priority: low, normal, high or sompi amount"] priority: Option<String>,
+) -> Result<(), Error> {
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) => guild_id,
+        None => {
+            let embed = create_error_embed("Error", "Role tips are only available in a server");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let amount_sompi = try_parse_required_nonzero_spectre_as_sompi_u64(Some(amount))?;
+
+    let author_id = ctx.author().id;
+    let tip_context = ctx.data();
+
+    ctx.defer_ephemeral().await?;
+
+    let mut user_ids: Vec<UserId> = vec![];
+    let mut after = None;
+
+    loop {
+        let members = guild_id
+            .members(ctx, Some(MEMBERS_PAGE_SIZE), after)
+            .await?;
+
+        for member in &members {
+            if !member.roles.contains(&role.id)
+                || member.user.bot
+                || member.user.system
+                || member.user.id == author_id
+                || tip_context
+                    .opt_out_store
+                    .is_opted_out(&member.user.id.to_string())
+                    .await
+            {
+                continue;
+            }
+
+            user_ids.push(member.user.id);
+        }
+
+        // no need to list the whole guild once the distribution is known to be too large
+        if user_ids.len() > MAX_RECIPIENTS {
+            let embed = create_error_embed(
+                "Error",
+                &format!(
+                    "<@&{}> has too many members to tip, the maximum is {}",
+                    role.id, MAX_RECIPIENTS
+                ),
+            );
+            return send_reply(ctx, embed, true).await;
+        }
+
+        match members.last() {
+            Some(last) if members.len() as u64 == MEMBERS_PAGE_SIZE => after = Some(last.user.id),
+            _ => break,
+        }
+    }
+
+    if user_ids.is_empty() {
+        let embed = create_error_embed(
+            "Error",
+            &format!("No member of <@&{}> can be tipped", role.id),
+        );
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let amount_each_sompi = match mode {
+        SplitMode::Total => amount_sompi / user_ids.len() as u64,
+        SplitMode::Each => amount_sompi,
+    };
+
+    if amount_each_sompi == 0 {
+        let embed = create_error_embed(
+            "Error",
+            "The amount is too small to be split among the role members",
+        );
+        return send_reply(ctx, embed, true).await;
+    }
+
+    distribute(
+        ctx,
+        Distribution {
+            operation: "tip_role",
+            description: format!(
+                "<@{}> tipped {} members of <@&{}>",
+                author_id,
+                user_ids.len(),
+                role.id
+            ),
+            recipients: user_ids
+                .into_iter()
+                .map(|user_id| (user_id, amount_each_sompi))
+                .collect(),
+            // the role is named without being pinged, see `distribute`
+            mention: Some(format!("<@&{}>", role.id)),
+            password,
+            priority,
+        },
+    )
+    .await
+}
@@ -0,0 +1,1005 @@
+use crate::utils::*;
+use core::{
+    error::Error as SpectreError,
+    frozen_user::FrozenUser,
+    guild_config::GuildConfig,
+    tip_owned_wallet::TipOwnedWallet,
+    utils::{
+        build_operator_watch_only_identifier, get_balances_by_address, now_unix_secs,
+        try_parse_required_nonzero_spectre_as_sompi_u64,
+    },
+};
+use poise::serenity_prelude::{self as serenity, ReactionType};
+use spectre_wallet_core::{prelude::Address, utils::sompi_to_spectre_string_with_suffix};
+
+use crate::models::{Context, Error};
+
+// entries shown by the audit command
+const AUDIT_LOG_LIMIT: usize = 20;
+
+// names of operator watch-only accounts, they become part of the wallet file name
+const WATCH_ONLY_NAME_MAX_LENGTH: usize = 32;
+
+#[poise::command(
+    slash_command,
+    subcommands(
+        "tipadmin_config",
+        "tipadmin_user",
+        "tipadmin_freeze",
+        "tipadmin_unfreeze",
+        "tipadmin_close",
+        "tipadmin_stuck",
+        "tipadmin_audit",
+        "tipadmin_watch"
+    ),
+    category = "admin"
+)]
+/// administration of the bot, server configuration and operator tools
+pub async fn tipadmin(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    rename = "config",
+    subcommands(
+        "config_show",
+        "config_public_receipts",
+        "config_receipt_channel",
+        "config_tip_limits",
+        "config_ephemeral",
+        "config_admin_role",
+        "config_reaction_emoji",
+        "config_reaction_cap"
+    ),
+    category = "admin"
+)]
+/// view and edit the configuration of this server
+pub async fn tipadmin_config(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+fn format_optional_amount(ctx: Context<'_>, amount_sompi: Option<u64>) -> String {
+    match amount_sompi {
+        Some(amount_sompi) => {
+            sompi_to_spectre_string_with_suffix(amount_sompi, &ctx.data().network_id())
+        }
+        None => "none".to_string(),
+    }
+}
+
+fn format_enabled(enabled: bool) -> String {
+    match enabled {
+        true => "enabled".to_string(),
+        false => "disabled".to_string(),
+    }
+}
+
+/**
+ * custom emojis are stored by id, any name renders them
+ */
+fn format_reaction_emoji_key(emoji_key: &str) -> String {
+    match emoji_key.parse::<u64>() {
+        Ok(emoji_id) => format!("<:emoji:{}>", emoji_id),
+        Err(_) => emoji_key.to_string(),
+    }
+}
+
+async fn reply_with_config(
+    ctx: Context<'_>,
+    title: &str,
+    config: &GuildConfig,
+) -> Result<(), Error> {
+    let tip_context = ctx.data();
+
+    let receipt_channels = match config.receipt_channel_ids.is_empty() {
+        true => "any channel".to_string(),
+        false => config
+            .receipt_channel_ids
+            .iter()
+            .map(|channel_id| format!("<#{}>", channel_id))
+            .collect::<Vec<String>>()
+            .join(", "),
+    };
+
+    let admin_role = match config.admin_role_id {
+        Some(admin_role_id) => format!("<@&{}>", admin_role_id),
+        None => "Manage Server permission only".to_string(),
+    };
+
+    let escrow_arbiter = match tip_context
+        .escrow_arbiter_store
+        .find_arbiter_role(config.guild_id)
+        .await
+    {
+        Some(role_id) => format!("<@&{}>", role_id),
+        None => "none".to_string(),
+    };
+
+    let reaction_emojis: Vec<String> = config
+        .reaction_amounts_by_emoji
+        .iter()
+        .map(|(emoji_key, amount_sompi)| {
+            format!(
+                "{}: {}",
+                format_reaction_emoji_key(emoji_key),
+                sompi_to_spectre_string_with_suffix(*amount_sompi, &tip_context.network_id())
+            )
+        })
+        .collect();
+
+    let reaction_emojis = match reaction_emojis.is_empty() {
+        true => "none".to_string(),
+        false => format_field_lines(reaction_emojis),
+    };
+
+    let embed = create_success_embed(title, "Configuration of this server")
+        .field(
+            "Public Receipts",
+            format_enabled(config.public_receipts),
+            true,
+        )
+        .field("Receipt Channels", receipt_channels, true)
+        .field(
+            "Ephemeral Replies",
+            format_enabled(config.ephemeral_replies),
+            true,
+        )
+        .field(
+            "Minimum Tip",
+            format_optional_amount(ctx, config.min_tip_sompi),
+            true,
+        )
+        .field(
+            "Maximum Tip",
+            format_optional_amount(ctx, config.max_tip_sompi),
+            true,
+        )
+        .field("Admin Role", admin_role, true)
+        .field("Escrow Arbiter", escrow_arbiter, true)
+        .field(
+            "Reaction Tip Daily Cap",
+            sompi_to_spectre_string_with_suffix(
+                config.reaction_daily_cap_sompi,
+                &tip_context.network_id(),
+            ),
+            true,
+        )
+        .field("Reaction Emojis", reaction_emojis, false);
+
+    send_reply(ctx, embed, true).await
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    rename = "show",
+    check = "check_guild_admin",
+    category = "admin"
+)]
+/// show the configuration of this server
+pub async fn config_show(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) => guild_id.get(),
+        None => {
+            let embed =
+                create_error_embed("Error", "The configuration is only available in a server");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let config = ctx
+        .data()
+        .guild_config_store
+        .find_by_guild_id(guild_id)
+        .await;
+
+    reply_with_config(ctx, "Server Configuration", &config).await
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    rename = "public_receipts",
+    check = "check_guild_admin",
+    category = "admin"
+)]
+/// post tip receipts publicly, or only to the sender
+pub async fn config_public_receipts(
+    ctx: Context<'_>,
+    #[description = "Post receipts publicly"] enabled: bool,
+) -> Result<(), Error> {
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) => guild_id.get(),
+        None => {
+            let embed =
+                create_error_embed("Error", "The configuration is only available in a server");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let config = ctx
+        .data()
+        .guild_config_store
+        .set_public_receipts(guild_id, enabled)
+        .await?;
+
+    reply_with_config(ctx, "Public Receipts Set", &config).await
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    rename = "receipt_channel",
+    check = "check_guild_admin",
+    category = "admin"
+)]
+/// allow (or disallow) public receipts in a channel, any channel is allowed while none is set
+pub async fn config_receipt_channel(
+    ctx: Context<'_>,
+    #[description = "Channel"] channel: serenity::GuildChannel,
+    #[description = "Allow public receipts in the channel"] allowed: bool,
+) -> Result<(), Error> {
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) => guild_id.get(),
+        None => {
+            let embed =
+                create_error_embed("Error", "The configuration is only available in a server");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let guild_config_store = &ctx.data().guild_config_store;
+
+    let config = match allowed {
+        true => {
+            guild_config_store
+                .add_receipt_channel(guild_id, channel.id.get())
+                .await?
+        }
+        false => {
+            guild_config_store
+                .remove_receipt_channel(guild_id, channel.id.get())
+                .await?
+        }
+    };
+
+    reply_with_config(ctx, "Receipt Channels Set", &config).await
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    rename = "tip_limits",
+    check = "check_guild_admin",
+    category = "admin"
+)]
+/// set the minimum and maximum amount of a tip, omitted limits are removed
+pub async fn config_tip_limits(
+    ctx: Context<'_>,
+    #[description = "Minimum tip"] min: Option<String>,
+    #[description = "Maximum tip"] max: Option<String>,
+) -> Result<(), Error> {
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) => guild_id.get(),
+        None => {
+            let embed =
+                create_error_embed("Error", "The configuration is only available in a server");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let min_tip_sompi = min
+        .map(|min| try_parse_required_nonzero_spectre_as_sompi_u64(Some(min)))
+        .transpose()?;
+    let max_tip_sompi = max
+        .map(|max| try_parse_required_nonzero_spectre_as_sompi_u64(Some(max)))
+        .transpose()?;
+
+    if let (Some(min_tip_sompi), Some(max_tip_sompi)) = (min_tip_sompi, max_tip_sompi) {
+        if min_tip_sompi > max_tip_sompi {
+            let embed = create_error_embed("Error", "The minimum tip cannot exceed the maximum");
+            return send_reply(ctx, embed, true).await;
+        }
+    }
+
+    let config = ctx
+        .data()
+        .guild_config_store
+        .set_tip_limits(guild_id, min_tip_sompi, max_tip_sompi)
+        .await?;
+
+    reply_with_config(ctx, "Tip Limits Set", &config).await
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    rename = "ephemeral",
+    check = "check_guild_admin",
+    category = "admin"
+)]
+/// make the replies of the bot visible only to the user who ran the command
+pub async fn config_ephemeral(
+    ctx: Context<'_>,
+    #[description = "Ephemeral replies"] enabled: bool,
+) -> Result<(), Error> {
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) => guild_id.get(),
+        None => {
+            let embed =
+                create_error_embed("Error", "The configuration is only available in a server");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let config = ctx
+        .data()
+        .guild_config_store
+        .set_ephemeral_replies(guild_id, enabled)
+        .await?;
+
+    reply_with_config(ctx, "Ephemeral Replies Set", &config).await
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    rename = "admin_role",
+    check = "check_guild_admin",
+    category = "admin"
+)]
+/// set the role allowed to use admin commands besides the Manage Server permission
+pub async fn config_admin_role(
+    ctx: Context<'_>,
+    #[description = "Admin role (omit to remove it)"] role: Option<serenity::Role>,
+) -> Result<(), Error> {
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) => guild_id.get(),
+        None => {
+            let embed =
+                create_error_embed("Error", "The configuration is only available in a server");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let config = ctx
+        .data()
+        .guild_config_store
+        .set_admin_role(guild_id, role.map(|role| role.id.get()))
+        .await?;
+
+    reply_with_config(ctx, "Admin Role Set", &config).await
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    rename = "reaction_emoji",
+    check = "check_guild_admin",
+    category = "admin"
+)]
+/// map an emoji to a tip amount for reaction tips, without amount the mapping is removed
+pub async fn config_reaction_emoji(
+    ctx: Context<'_>,
+    #[description = "Emoji"] emoji: String,
+    #[description = "Tip amount (omit to remove the emoji)"] amount: Option<String>,
+) -> Result<(), Error> {
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) => guild_id,
+        None => {
+            let embed = create_error_embed("Error", "Reaction tips are only available in a server");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let reaction_type = match ReactionType::try_from(emoji.trim().to_string()) {
+        Ok(reaction_type) => reaction_type,
+        Err(_) => {
+            let embed = create_error_embed("Error", &format!("'{}' is not an emoji", emoji));
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let emoji_key = reaction_emoji_key(&reaction_type);
+
+    let tip_context = ctx.data();
+
+    let embed = match amount {
+        Some(amount) => {
+            let amount_sompi = try_parse_required_nonzero_spectre_as_sompi_u64(Some(amount))?;
+
+            if let Err(reason) =
+                check_guild_tip_amount(tip_context, Some(guild_id), amount_sompi).await
+            {
+                let embed = create_error_embed("Error", &reason);
+                return send_reply(ctx, embed, true).await;
+            }
+
+            tip_context
+                .guild_config_store
+                .set_reaction_emoji_amount(guild_id.get(), &emoji_key, amount_sompi)
+                .await?;
+
+            create_success_embed(
+                "Reaction Tip Emoji Set",
+                &format!(
+                    "Reacting with {} tips {}",
+                    reaction_type,
+                    sompi_to_spectre_string_with_suffix(amount_sompi, &tip_context.network_id())
+                ),
+            )
+        }
+        None => {
+            tip_context
+                .guild_config_store
+                .remove_reaction_emoji(guild_id.get(), &emoji_key)
+                .await?;
+
+            create_success_embed(
+                "Reaction Tip Emoji Removed",
+                &format!("Reacting with {} no longer tips", reaction_type),
+            )
+        }
+    };
+
+    send_reply(ctx, embed, true).await
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    rename = "reaction_cap",
+    check = "check_guild_admin",
+    category = "admin"
+)]
+/// set the maximum amount a user can tip with reactions per day
+pub async fn config_reaction_cap(
+    ctx: Context<'_>,
+    #[description = "Daily cap per user"] amount: String,
+) -> Result<(), Error> {
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) => guild_id,
+        None => {
+            let embed = create_error_embed("Error", "Reaction tips are only available in a server");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let daily_cap_sompi = try_parse_required_nonzero_spectre_as_sompi_u64(Some(amount))?;
+
+    let tip_context = ctx.data();
+
+    tip_context
+        .guild_config_store
+        .set_reaction_daily_cap(guild_id.get(), daily_cap_sompi)
+        .await?;
+
+    let embed = create_success_embed(
+        "Reaction Tip Cap Set",
+        &format!(
+            "Users can tip up to {} per day with reactions",
+            sompi_to_spectre_string_with_suffix(daily_cap_sompi, &tip_context.network_id())
+        ),
+    );
+
+    send_reply(ctx, embed, true).await
+}
+
+#[poise::command(slash_command, rename = "user", owners_only, category = "admin")]
+/// look up the wallets of a user, with their balances
+pub async fn tipadmin_user(
+    ctx: Context<'_>,
+    #[description = "User"] user: serenity::User,
+) -> Result<(), Error> {
+    let tip_context = ctx.data();
+    let owner_identifier = user.id.to_string();
+    let network_id = tip_context.network_id();
+
+    ctx.defer_ephemeral().await?;
+
+    let owned_wallet_metadata = tip_context
+        .owned_wallet_metadata_store
+        .find_owned_wallet_metadata_by_owner_identifier(&owner_identifier)
+        .await
+        .ok();
+
+    let transition_wallets = tip_context
+        .transition_wallet_metadata_store
+        .find_transition_wallet_metadata_by_target_identifier(&owner_identifier)
+        .await?;
+
+    let linked_addresses = tip_context
+        .linked_address_metadata_store
+        .find_linked_addresses_by_owner_identifier(&owner_identifier)
+        .await?;
+
+    let mut addresses: Vec<_> = transition_wallets
+        .iter()
+        .map(|metadata| metadata.receive_address.clone())
+        .collect();
+
+    if let Some(metadata) = &owned_wallet_metadata {
+        addresses.push(metadata.receive_address.clone());
+    }
+
+    let balances = get_balances_by_address(tip_context.rpc_api(), addresses).await?;
+
+    let format_balance = |address: &Address| {
+        sompi_to_spectre_string_with_suffix(
+            balances.get(address).copied().unwrap_or_default(),
+            &network_id,
+        )
+    };
+
+    let owned_wallet = match &owned_wallet_metadata {
+        Some(metadata) => format!(
+            "{}{}\n{}",
+            metadata.receive_address,
+            match metadata.is_watch_only() {
+                true => " (watch-only)",
+                false => "",
+            },
+            format_balance(&metadata.receive_address)
+        ),
+        None => "none".to_string(),
+    };
+
+    let transition_wallet_lines: Vec<String> = transition_wallets
+        .iter()
+        .map(|metadata| {
+            format!(
+                "from <@{}>: {} ({})",
+                metadata.initiator_identifier,
+                format_balance(&metadata.receive_address),
+                metadata.receive_address
+            )
+        })
+        .collect();
+
+    let linked_address_lines: Vec<String> = linked_addresses
+        .iter()
+        .map(|metadata| format!("{} (<t:{}:d>)", metadata.address, metadata.linked_at))
+        .collect();
+
+    let frozen = match tip_context.frozen_user_store.find(&owner_identifier).await {
+        Some(frozen_user) => format!(
+            "by <@{}> <t:{}:f>: {}",
+            frozen_user.frozen_by, frozen_user.frozen_at, frozen_user.reason
+        ),
+        None => "no".to_string(),
+    };
+
+    let opened = match tip_context.does_opened_owned_wallet_exists(&owner_identifier) {
+        true => "yes",
+        false => "no",
+    };
+
+    let reaction_tips = match tip_context.get_reaction_tip_secret(&owner_identifier) {
+        Some(_) => "enabled",
+        None => "disabled",
+    };
+
+    let embed = create_success_embed("User Lookup", &format!("<@{}>", user.id))
+        .field("Owned Wallet", owned_wallet, false)
+        .field("Opened", opened, true)
+        .field("Reaction Tips", reaction_tips, true)
+        .field("Frozen", frozen, false)
+        .field(
+            "Transition Wallets",
+            match transition_wallet_lines.is_empty() {
+                true => "none".to_string(),
+                false => format_field_lines(transition_wallet_lines),
+            },
+            false,
+        )
+        .field(
+            "Linked Addresses",
+            match linked_address_lines.is_empty() {
+                true => "none".to_string(),
+                false => format_field_lines(linked_address_lines),
+            },
+            false,
+        );
+
+    record_audit_log(ctx, "lookup", Some(owner_identifier), String::new()).await?;
+
+    send_reply(ctx, embed, true).await
+}
+
+#[poise::command(slash_command, rename = "freeze", owners_only, category = "admin")]
+/// prevent a user from spending through the bot
+pub async fn tipadmin_freeze(
+    ctx: Context<'_>,
+    #[description = "User"] user: serenity::User,
+    #[max_length = 200]
+    #[description = "Reason"]
+    reason: String,
+) -> Result<(), Error> {
+    let tip_context = ctx.data();
+    let owner_identifier = user.id.to_string();
+
+    let frozen_user = FrozenUser {
+        owner_identifier: owner_identifier.clone(),
+        reason: reason.clone(),
+        frozen_by: ctx.author().id.to_string(),
+        frozen_at: now_unix_secs(),
+    };
+
+    match tip_context.frozen_user_store.freeze(&frozen_user).await {
+        Ok(()) => (),
+        Err(SpectreError::UserAlreadyFrozen()) => {
+            let embed = create_error_embed("Error", "This user is already frozen");
+            return send_reply(ctx, embed, true).await;
+        }
+        Err(e) => return Err(e.into()),
+    }
+
+    // reactions would be ignored anyway, the secret does not need to stay in memory
+    tip_context.disable_reaction_tips(&owner_identifier);
+
+    record_audit_log(ctx, "freeze", Some(owner_identifier), reason).await?;
+
+    let embed = create_success_embed(
+        "User Frozen",
+        &format!(
+            "<@{}> can no longer spend through the bot, running schedules are paused on their next run",
+            user.id
+        ),
+    );
+    send_reply(ctx, embed, true).await
+}
+
+#[poise::command(slash_command, rename = "unfreeze", owners_only, category = "admin")]
+/// allow a frozen user to spend through the bot again
+pub async fn tipadmin_unfreeze(
+    ctx: Context<'_>,
+    #[description = "User"] user: serenity::User,
+) -> Result<(), Error> {
+    let owner_identifier = user.id.to_string();
+
+    let unfrozen = match ctx
+        .data()
+        .frozen_user_store
+        .unfreeze(&owner_identifier)
+        .await
+    {
+        Ok(unfrozen) => unfrozen,
+        Err(SpectreError::FrozenUserNotFound()) => {
+            let embed = create_error_embed("Error", "This user is not frozen");
+            return send_reply(ctx, embed, true).await;
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    record_audit_log(
+        ctx,
+        "unfreeze",
+        Some(owner_identifier),
+        format!("frozen for: {}", unfrozen.reason),
+    )
+    .await?;
+
+    let embed = create_success_embed(
+        "User Unfrozen",
+        &format!(
+            "<@{}> can spend through the bot again, paused schedules have to be resumed by the user",
+            user.id
+        ),
+    );
+    send_reply(ctx, embed, true).await
+}
+
+#[poise::command(slash_command, rename = "close", owners_only, category = "admin")]
+/// force-close the opened wallet of a user
+pub async fn tipadmin_close(
+    ctx: Context<'_>,
+    #[description = "User"] user: serenity::User,
+) -> Result<(), Error> {
+    let tip_context = ctx.data();
+    let owner_identifier = user.id.to_string();
+
+    // the in-memory secret must not outlive the opened wallet
+    tip_context.disable_reaction_tips(&owner_identifier);
+
+    let tip_wallet = match tip_context.remove_opened_owned_wallet(&owner_identifier) {
+        Some(tip_wallet) => tip_wallet,
+        None => {
+            let embed = create_error_embed("Error", "This user has no opened wallet");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    tip_wallet.wallet().stop().await?;
+    tip_wallet.wallet().close().await?;
+
+    record_audit_log(ctx, "close", Some(owner_identifier), String::new()).await?;
+
+    let embed = create_success_embed(
+        "Wallet Closed",
+        &format!("The wallet of <@{}> has been closed", user.id),
+    );
+    send_reply(ctx, embed, true).await
+}
+
+#[poise::command(slash_command, rename = "stuck", owners_only, category = "admin")]
+/// list the transition wallets still holding funds, most funded first
+pub async fn tipadmin_stuck(ctx: Context<'_>) -> Result<(), Error> {
+    let tip_context = ctx.data();
+    let network_id = tip_context.network_id();
+
+    ctx.defer_ephemeral().await?;
+
+    let transition_wallets = tip_context
+        .transition_wallet_metadata_store
+        .find_all_transition_wallet_metadata()
+        .await;
+
+    let balances = get_balances_by_address(
+        tip_context.rpc_api(),
+        transition_wallets
+            .iter()
+            .map(|metadata| metadata.receive_address.clone())
+            .collect(),
+    )
+    .await?;
+
+    let mut funded: Vec<_> = transition_wallets
+        .into_iter()
+        .filter_map(|metadata| {
+            balances
+                .get(&metadata.receive_address)
+                .copied()
+                .filter(|balance| *balance > 0)
+                .map(|balance| (metadata, balance))
+        })
+        .collect();
+
+    funded.sort_by(|a, b| b.1.cmp(&a.1));
+
+    let mut lines = vec![];
+
+    for (metadata, balance) in &funded {
+        // the target cannot claim until they create a wallet
+        let target_has_wallet = tip_context
+            .owned_wallet_metadata_store
+            .find_owned_wallet_metadata_by_owner_identifier(&metadata.target_identifier)
+            .await
+            .is_ok();
+
+        lines.push(format!(
+            "<@{}> from <@{}>: {}{}",
+            metadata.target_identifier,
+            metadata.initiator_identifier,
+            sompi_to_spectre_string_with_suffix(*balance, &network_id),
+            match target_has_wallet {
+                true => ", not claimed",
+                false => ", no wallet",
+            }
+        ));
+    }
+
+    let total_sompi: u64 = funded.iter().map(|(_, balance)| balance).sum();
+
+    record_audit_log(
+        ctx,
+        "stuck",
+        None,
+        format!("{} funded transition wallets", funded.len()),
+    )
+    .await?;
+
+    if lines.is_empty() {
+        let embed = create_success_embed(
+            "Stuck Transition Wallets",
+            "No transition wallet holds funds",
+        );
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let embed = create_success_embed(
+        "Stuck Transition Wallets",
+        &format!(
+            "{} transition wallets hold {}",
+            funded.len(),
+            sompi_to_spectre_string_with_suffix(total_sompi, &network_id)
+        ),
+    )
+    .field("Wallets", format_field_lines(lines), false);
+    send_reply(ctx, embed, true).await
+}
+
+#[poise::command(slash_command, rename = "audit", owners_only, category = "admin")]
+/// show the most recent admin actions, optionally those targeting a user
+pub async fn tipadmin_audit(
+    ctx: Context<'_>,
+    #[description = "Target user"] user: Option<serenity::User>,
+) -> Result<(), Error> {
+    let target_identifier = user.map(|user| user.id.to_string());
+
+    let entries = ctx
+        .data()
+        .audit_log_store
+        .find_recent(target_identifier.as_deref(), AUDIT_LOG_LIMIT)
+        .await;
+
+    if entries.is_empty() {
+        let embed = create_success_embed("Audit Log", "No admin action recorded");
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let lines: Vec<String> = entries
+        .iter()
+        .map(|entry| {
+            let target = match &entry.target_identifier {
+                Some(target_identifier) => format!(" <@{}>", target_identifier),
+                None => String::new(),
+            };
+            let details = match entry.details.is_empty() {
+                true => String::new(),
+                false => format!(": {}", entry.details),
+            };
+
+            format!(
+                "#{} <t:{}:f> <@{}> {}{}{}",
+                entry.id, entry.at, entry.actor_identifier, entry.action, target, details
+            )
+        })
+        .collect();
+
+    let embed = create_success_embed("Audit Log", "Most recent first").field(
+        "Actions",
+        format_field_lines(lines),
+        false,
+    );
+    send_reply(ctx, embed, true).await
+}
+
+#[poise::command(
+    slash_command,
+    rename = "watch",
+    subcommands("watch_add", "watch_status"),
+    category = "admin"
+)]
+/// watch-only accounts registered by the operators, e.g. for treasuries
+pub async fn tipadmin_watch(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+fn is_valid_watch_only_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= WATCH_ONLY_NAME_MAX_LENGTH
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+#[poise::command(slash_command, rename = "add", owners_only, category = "admin")]
+/// register a watch-only account from an extended public key (xpub)
+pub async fn watch_add(
+    ctx: Context<'_>,
+    #[description = "Name of the account (lowercase letters, digits and dashes)"] name: String,
+    #[description = "Extended public key (xpub)"] xpub: String,
+) -> Result<(), Error> {
+    if !is_valid_watch_only_name(&name) {
+        let embed = create_error_embed(
+            "Error",
+            "The name must be made of lowercase letters, digits and dashes (32 characters at most)",
+        );
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let tip_context = ctx.data();
+    let wallet_identifier = build_operator_watch_only_identifier(&name);
+
+    if tip_context
+        .local_store()?
+        .exists(Some(&wallet_identifier))
+        .await?
+    {
+        let embed = create_error_embed(
+            "Error",
+            "A watch-only account with this name already exists",
+        );
+        return send_reply(ctx, embed, true).await;
+    }
+
+    ctx.defer_ephemeral().await?;
+
+    let watch_wallet =
+        match TipOwnedWallet::create_watch_only(tip_context.clone(), &xpub, &wallet_identifier)
+            .await
+        {
+            Ok(watch_wallet) => watch_wallet,
+            Err(SpectreError::WatchOnlyWalletKeyMissing()) => {
+                let embed = create_error_embed(
+                    "Error",
+                    "Watch-only wallets are not enabled, set `WATCH_ONLY_WALLET_KEY`",
+                );
+                return send_reply(ctx, embed, true).await;
+            }
+            Err(e) => {
+                let embed =
+                    create_error_embed("Error", &format!("Invalid extended public key: {}", e));
+                return send_reply(ctx, embed, true).await;
+            }
+        };
+
+    record_audit_log(
+        ctx,
+        "watch add",
+        None,
+        format!("{} {}", name, watch_wallet.receive_address()),
+    )
+    .await?;
+
+    let embed = create_success_embed(
+        "Watch-Only Account Registered",
+        &format!(
+            "Balance and history are available through `/tipadmin watch status {}`",
+            name
+        ),
+    )
+    .field("Receive Address", watch_wallet.receive_address(), false);
+    send_reply(ctx, embed, true).await
+}
+
+#[poise::command(slash_command, rename = "status", owners_only, category = "admin")]
+/// show the balance and history of an operator watch-only account
+pub async fn watch_status(
+    ctx: Context<'_>,
+    #[description = "Name of the account"] name: String,
+) -> Result<(), Error> {
+    let tip_context = ctx.data();
+    let wallet_identifier = build_operator_watch_only_identifier(&name);
+
+    let is_watch_only = tip_context
+        .owned_wallet_metadata_store
+        .find_owned_wallet_metadata_by_owner_identifier(&wallet_identifier)
+        .await
+        .is_ok_and(|metadata| metadata.is_watch_only());
+
+    if !is_valid_watch_only_name(&name) || !is_watch_only {
+        let embed = create_error_embed("Error", "No watch-only account has this name");
+        return send_reply(ctx, embed, true).await;
+    }
+
+    ctx.defer_ephemeral().await?;
+
+    // watch-only wallets don't need a password, open them on demand
+    let watch_wallet = match tip_context.get_opened_owned_wallet(&wallet_identifier) {
+        Some(watch_wallet) => watch_wallet,
+        None => TipOwnedWallet::open_watch_only(tip_context.clone(), &wallet_identifier).await?,
+    };
+
+    let balance = watch_wallet
+        .wallet()
+        .account()?
+        .balance()
+        .unwrap_or_default();
+    let recent_transactions = watch_wallet.recent_transactions(5).await?;
+    let network_id = tip_context.network_id();
+
+    record_audit_log(ctx, "watch status", None, name.clone()).await?;
+
+    let embed = create_success_embed(&format!("Watch-Only Account {}", name), "")
+        .field(
+            "Balance",
+            sompi_to_spectre_string_with_suffix(balance.mature, &network_id),
+            true,
+        )
+        .field(
+            "Pending Balance",
+            sompi_to_spectre_string_with_suffix(balance.pending, &network_id),
+            true,
+        )
+        .field("UTXO count", balance.mature_utxo_count.to_string(), true)
+        .field(
+            "Recent Transactions",
+            format_transaction_history(tip_context, &recent_transactions),
+            false,
+        )
+        .field("Receive Address", watch_wallet.receive_address(), false);
+    send_reply(ctx, embed, true).await
+}
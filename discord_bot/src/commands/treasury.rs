@@ -0,0 +1,405 @@
+use crate::{treasury::*, utils::*};
+use core::{
+    error::Error as SpectreError,
+    tip_owned_wallet::TipOwnedWallet,
+    treasury::{Treasury, TreasuryPayout, TreasuryPayoutStatus},
+    utils::{
+        build_treasury_identifier, now_unix_secs, try_parse_required_nonzero_spectre_as_sompi_u64,
+    },
+};
+use poise::{
+    serenity_prelude::{self as serenity, RoleId},
+    CreateReply,
+};
+use spectre_wallet_core::utils::sompi_to_spectre_string_with_suffix;
+
+use crate::models::{Context, Error};
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommands(
+        "treasury_setup",
+        "treasury_balance",
+        "treasury_payout",
+        "treasury_history",
+        "treasury_info"
+    ),
+    category = "wallet"
+)]
+/// server treasury, payouts are signed once enough approvers confirmed them
+pub async fn treasury(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/**
+ * treasury of the guild the command was used in
+ */
+async fn find_treasury(ctx: Context<'_>) -> Option<Treasury> {
+    let guild_id = ctx.guild_id()?;
+
+    ctx.data()
+        .treasury_store
+        .find_by_guild_id(guild_id.get())
+        .await
+}
+
+fn is_approver(ctx: Context<'_>, treasury: &Treasury) -> bool {
+    let role_id = RoleId::new(treasury.approver_role_id);
+
+    ctx.interaction
+        .member
+        .as_ref()
+        .is_some_and(|member| member.roles.contains(&role_id))
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    rename = "setup",
+    check = "check_guild_admin",
+    category = "wallet"
+)]
+/// create the treasury of this server, or change its roles and approval threshold
+pub async fn treasury_setup(
+    ctx: Context<'_>,
+    #[description = "Role allowed to propose, approve and reject payouts"]
+    approver_role: serenity::Role,
+    #[description = "Role payouts can be sent to"] payee_role: serenity::Role,
+    #[min = 1]
+    #[max = 10]
+    #[description = "Approvals needed to sign a payout"]
+    threshold: u32,
+) -> Result<(), Error> {
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) => guild_id.get(),
+        None => {
+            let embed = create_error_embed("Error", "Treasuries are only available in a server");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let tip_context = ctx.data();
+
+    if find_treasury(ctx).await.is_some() {
+        tip_context
+            .treasury_store
+            .configure(
+                guild_id,
+                approver_role.id.get(),
+                payee_role.id.get(),
+                threshold,
+            )
+            .await?;
+
+        let embed = create_success_embed(
+            "Treasury Updated",
+            &format!(
+                "Payouts to <@&{}> need {} approvals from <@&{}>, proposals already opened use the new threshold",
+                payee_role.id, threshold, approver_role.id
+            ),
+        );
+        return send_reply(ctx, embed, true).await;
+    }
+
+    ctx.defer_ephemeral().await?;
+
+    let wallet_identifier = build_treasury_identifier(guild_id);
+
+    let secret = match tip_context.treasury_wallet_secret(&wallet_identifier) {
+        Ok(secret) => secret,
+        Err(SpectreError::TreasuryWalletKeyMissing()) => {
+            let embed = create_error_embed(
+                "Error",
+                "Treasuries are not enabled, the operators need to set a treasury wallet key",
+            );
+            return send_reply(ctx, embed, true).await;
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let (treasury_wallet, mnemonic) =
+        TipOwnedWallet::create(tip_context.clone(), &secret, &wallet_identifier).await?;
+
+    tip_context
+        .treasury_store
+        .save(&Treasury {
+            guild_id,
+            wallet_identifier,
+            receive_address: treasury_wallet.receive_address(),
+            secret: None,
+            approver_role_id: approver_role.id.get(),
+            payee_role_id: payee_role.id.get(),
+            threshold,
+        })
+        .await?;
+
+    let embed = create_success_embed(
+        "Treasury Created",
+        &format!(
+            "Payouts to <@&{}> need {} approvals from <@&{}>. Keep the mnemonic phrase safe, it is only shown once.",
+            payee_role.id, threshold, approver_role.id
+        ),
+    )
+    .field("Mnemonic Phrase", mnemonic.phrase(), false)
+    .field("Receive Address", treasury_wallet.receive_address(), false);
+
+    let attachment = create_qr_code_attachment(&treasury_wallet.receive_address().to_string())?;
+
+    send_reply_with_attachment(ctx, with_qr_code_image(embed), attachment, true).await
+}
+
+#[poise::command(slash_command, guild_only, rename = "balance", category = "wallet")]
+/// show the balance and the donation address of the server treasury
+pub async fn treasury_balance(ctx: Context<'_>) -> Result<(), Error> {
+    let treasury = match find_treasury(ctx).await {
+        Some(treasury) => treasury,
+        None => {
+            let embed = create_error_embed("Error", "This server has no treasury");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let tip_context = ctx.data();
+
+    ctx.defer().await?;
+
+    let balance = open_treasury_wallet(tip_context, &treasury)
+        .await?
+        .wallet()
+        .account()?
+        .balance()
+        .unwrap_or_default();
+
+    let network_id = tip_context.network_id();
+
+    let embed = create_success_embed("Treasury", "Anyone can donate to the address below")
+        .field(
+            "Balance",
+            sompi_to_spectre_string_with_suffix(balance.mature, &network_id),
+            true,
+        )
+        .field(
+            "Pending Balance",
+            sompi_to_spectre_string_with_suffix(balance.pending, &network_id),
+            true,
+        )
+        .field(
+            "Approvals",
+            format!(
+                "{} of <@&{}>",
+                treasury.threshold, treasury.approver_role_id
+            ),
+            true,
+        )
+        .field(
+            "Receive Address",
+            treasury.receive_address.address_to_string(),
+            false,
+        );
+
+    let attachment = create_qr_code_attachment(&treasury.receive_address.to_string())?;
+
+    send_reply_with_attachment(ctx, with_qr_code_image(embed), attachment, false).await
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    rename = "payout",
+    check = "check_not_frozen",
+    category = "wallet"
+)]
+/// propose a payout from the treasury, it counts as your approval
+pub async fn treasury_payout(
+    ctx: Context<'_>,
+    #[description = "Recipient"] user: serenity::User,
+    #[description = "Amount"] amount: String,
+    #[max_length = 200]
+    #[description = "Reason"]
+    reason: String,
+) -> Result<(), Error> {
+    let treasury = match find_treasury(ctx).await {
+        Some(treasury) => treasury,
+        None => {
+            let embed = create_error_embed("Error", "This server has no treasury");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    if !is_approver(ctx, &treasury) {
+        let embed = create_error_embed(
+            "Error",
+            &format!("Only <@&{}> can propose payouts", treasury.approver_role_id),
+        );
+        return send_reply(ctx, embed, true).await;
+    }
+
+    if user.bot || user.system {
+        let embed = create_error_embed("Error", "User is a bot or a system user");
+        return send_reply(ctx, embed, true).await;
+    }
+
+    if !member_has_role(ctx, treasury.guild_id, user.id, treasury.payee_role_id).await {
+        let embed = create_error_embed(
+            "Error",
+            &format!("Payouts can only be sent to <@&{}>", treasury.payee_role_id),
+        );
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let amount_sompi = try_parse_required_nonzero_spectre_as_sompi_u64(Some(amount))?;
+
+    let tip_context = ctx.data();
+
+    let proposer_identifier = ctx.author().id.to_string();
+    let now = now_unix_secs();
+
+    let mut payout = TreasuryPayout {
+        id: 0,
+        guild_id: treasury.guild_id,
+        proposer_identifier: proposer_identifier.clone(),
+        recipient_identifier: user.id.to_string(),
+        amount_sompi,
+        reason,
+        bounty_id: None,
+        channel_id: ctx.channel_id().get(),
+        message_id: 0,
+        approvals: vec![],
+        status: TreasuryPayoutStatus::Proposed,
+        rejected_by: None,
+        error: None,
+        tx_id: None,
+        created_at: now,
+        settled_at: None,
+    };
+    payout.approve(&proposer_identifier, now, treasury.threshold)?;
+
+    let payout = tip_context.treasury_payout_store.add(payout).await?;
+
+    // a threshold of one needs no other approver
+    if payout.status == TreasuryPayoutStatus::Executing {
+        ctx.defer().await?;
+
+        let executed = match execute_treasury_payout(
+            ctx,
+            tip_context,
+            &treasury,
+            &payout,
+            &proposer_identifier,
+        )
+        .await
+        {
+            Ok(executed) => executed,
+            Err(SpectreError::TreasuryPayoutNotProposed()) => {
+                let embed = create_error_embed("Error", "This payout is already settled");
+                return send_reply(ctx, embed, true).await;
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let embed = create_treasury_payout_embed(tip_context, &executed, treasury.threshold);
+        return send_reply(ctx, embed, false).await;
+    }
+
+    let reply = ctx
+        .send(CreateReply {
+            content: Some(format!("<@&{}>", treasury.approver_role_id)),
+            embeds: vec![create_treasury_payout_embed(
+                tip_context,
+                &payout,
+                treasury.threshold,
+            )],
+            components: Some(create_treasury_payout_buttons(payout.id)),
+            ephemeral: Some(false),
+            ..Default::default()
+        })
+        .await?;
+
+    tip_context
+        .treasury_payout_store
+        .set_message_id(payout.id, reply.message().await?.id.get())
+        .await?;
+
+    Ok(())
+}
+
+#[poise::command(slash_command, guild_only, rename = "history", category = "wallet")]
+/// list the payouts of the server treasury
+pub async fn treasury_history(ctx: Context<'_>) -> Result<(), Error> {
+    let treasury = match find_treasury(ctx).await {
+        Some(treasury) => treasury,
+        None => {
+            let embed = create_error_embed("Error", "This server has no treasury");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let tip_context = ctx.data();
+
+    let payouts = tip_context
+        .treasury_payout_store
+        .find_by_guild_id(treasury.guild_id)
+        .await;
+
+    if payouts.is_empty() {
+        let embed = create_success_embed("Treasury Payouts", "No payout has been proposed");
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let network_id = tip_context.network_id();
+
+    let lines: Vec<String> = payouts
+        .iter()
+        .map(|payout| {
+            format!(
+                "#{}: {} to {}, {} ({} approvals) <t:{}:d>",
+                payout.id,
+                sompi_to_spectre_string_with_suffix(payout.amount_sompi, &network_id),
+                format_treasury_payout_recipient(payout),
+                payout.status,
+                payout.approvals.len(),
+                payout.created_at
+            )
+        })
+        .collect();
+
+    let embed = create_success_embed(
+        "Treasury Payouts",
+        "Most recent first, use `/wallet treasury info <id>` for the full trail",
+    )
+    .field("Payouts", format_field_lines(lines), false);
+    send_reply(ctx, embed, true).await
+}
+
+#[poise::command(slash_command, guild_only, rename = "info", category = "wallet")]
+/// show a treasury payout with its approvals
+pub async fn treasury_info(
+    ctx: Context<'_>,
+    #[description = "Payout id"] id: u64,
+) -> Result<(), Error> {
+    let treasury = match find_treasury(ctx).await {
+        Some(treasury) => treasury,
+        None => {
+            let embed = create_error_embed("Error", "This server has no treasury");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let tip_context = ctx.data();
+
+    let payout = match tip_context.treasury_payout_store.find_by_id(id).await {
+        Some(payout) if payout.guild_id == treasury.guild_id => payout,
+        _ => {
+            let embed = create_error_embed("Error", "Payout not found");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let embed = create_treasury_payout_embed(tip_context, &payout, treasury.threshold).field(
+        "Trail",
+        format_field_lines(format_treasury_payout_trail(tip_context, &payout)),
+        false,
+    );
+    send_reply(ctx, embed, true).await
+}
@@ -0,0 +1,37 @@
+use crate::utils::*;
+use spectre_wallet_core::prelude::Address;
+
+use crate::models::{Context, Error};
+
+#[poise::command(slash_command, category = "wallet")]
+/// remove a linked external Spectre address
+pub async fn unlink(
+    ctx: Context<'_>,
+    #[description = "Linked Spectre address"] address: String,
+) -> Result<(), Error> {
+    let linked_address = match Address::try_from(address.as_str()) {
+        Ok(address) => address,
+        Err(_) => {
+            let embed = create_error_embed(
+                "Error while unlinking the address",
+                "Invalid Spectre address",
+            );
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let owner_identifier = ctx.author().id.to_string();
+    let tip_context = ctx.data();
+
+    if let Err(e) = tip_context
+        .linked_address_metadata_store
+        .remove(&owner_identifier, &linked_address)
+        .await
+    {
+        let embed = create_error_embed("Error while unlinking the address", &e.to_string());
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let embed = create_success_embed("Address Unlinked", &linked_address.to_string());
+    send_reply(ctx, embed, true).await
+}
@@ -0,0 +1,39 @@
+use crate::utils::*;
+use core::utils::verify_message_signature;
+use spectre_wallet_core::prelude::Address;
+
+use crate::models::{Context, Error};
+
+#[poise::command(slash_command, category = "wallet")]
+/// verify a message signature produced by any Spectre wallet
+pub async fn verify(
+    ctx: Context<'_>,
+    #[description = "Spectre address of the signer"] address: String,
+    #[description = "Signed message"] message: String,
+    #[description = "Signature (hex)"] signature: String,
+) -> Result<(), Error> {
+    let signer_address = match Address::try_from(address.as_str()) {
+        Ok(address) => address,
+        Err(_) => {
+            let embed = create_error_embed(
+                "Error while verifying the signature",
+                "Invalid Spectre address",
+            );
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let embed = match verify_message_signature(&signer_address, &message, &signature) {
+        Ok(true) => create_success_embed(
+            "Valid Signature",
+            &format!("The message was signed by `{}`", signer_address),
+        ),
+        Ok(false) => create_error_embed(
+            "Invalid Signature",
+            &format!("The message was not signed by `{}`", signer_address),
+        ),
+        Err(e) => create_error_embed("Error while verifying the signature", &e.to_string()),
+    };
+
+    send_reply(ctx, embed, true).await
+}
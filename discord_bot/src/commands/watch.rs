@@ -0,0 +1,66 @@
+use crate::utils::*;
+use core::{error::Error as SpectreError, tip_owned_wallet::TipOwnedWallet};
+
+use crate::models::{Context, Error};
+
+#[poise::command(slash_command, category = "wallet")]
+/// register a watch-only wallet from an extended public key (xpub)
+pub async fn watch(
+    ctx: Context<'_>,
+    #[description = "Extended public key (xpub)"] xpub: String,
+) -> Result<(), Error> {
+    let user = ctx.author().id;
+    let wallet_owner_identifier = user.to_string();
+
+    let tip_context = ctx.data();
+
+    let is_opened = tip_context.does_opened_owned_wallet_exists(&wallet_owner_identifier);
+    let is_initiated = match is_opened {
+        true => true,
+        false => {
+            tip_context
+                .local_store()?
+                .exists(Some(&wallet_owner_identifier))
+                .await?
+        }
+    };
+
+    if is_initiated {
+        let embed = create_error_embed("Error", "A discord wallet already exists");
+        return send_reply(ctx, embed, true).await;
+    }
+
+    ctx.defer_ephemeral().await?;
+
+    let tip_wallet = match TipOwnedWallet::create_watch_only(
+        tip_context.clone(),
+        &xpub,
+        &wallet_owner_identifier,
+    )
+    .await
+    {
+        Ok(w) => w,
+        Err(SpectreError::WatchOnlyWalletKeyMissing()) => {
+            let embed = create_error_embed(
+                "Error while registering the watch-only wallet",
+                "Watch-only wallets are not enabled on this bot",
+            );
+            return send_reply(ctx, embed, true).await;
+        }
+        Err(e) => {
+            let embed = create_error_embed(
+                "Error while registering the watch-only wallet",
+                &format!("Invalid extended public key: {}", e),
+            );
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let embed = create_success_embed(
+        "Watch-Only Wallet Registered",
+        "Balance and history are available through `status` without password. Spending commands are disabled.",
+    )
+    .field("Receive Address", tip_wallet.receive_address(), false);
+
+    send_reply(ctx, embed, true).await
+}
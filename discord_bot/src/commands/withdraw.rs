@@ -1,42 +1,105 @@
-use core::utils::{
-    estimate_fees, get_tx_explorer_url, try_parse_required_nonzero_spectre_as_sompi_u64,
+use core::{
+    error::Error as SpectreError,
+    fee_policy::FeePriority,
+    outbox::OutboxEntry,
+    payment_uri::PaymentUri,
+    utils::{
+        build_idempotency_key, ensure_address_network, estimate_fees, get_tx_explorer_url,
+        now_unix_secs, try_parse_required_nonzero_spectre_as_sompi_u64,
+    },
 };
+use poise::serenity_prelude as serenity;
 use spectre_wallet_core::{
-    prelude::Address,
     tx::{Fees, PaymentOutputs},
+    utils::sompi_to_spectre_string_with_suffix,
 };
 use spectre_wallet_keys::secret::Secret;
-use std::sync::Arc;
+use std::str::FromStr;
 use workflow_core::abortable::Abortable;
 
-use crate::utils::*;
+use crate::{outbox::send_with_outbox, utils::*};
 
 use crate::models::{Context, Error};
 
-#[poise::command(slash_command, category = "wallet")]
-/// withdraw funds to a custom Spectre address
+#[poise::command(slash_command, check = "check_not_frozen", category = "wallet")]
+/// withdraw funds to a Spectre address or payment uri, or to a linked address
 pub async fn withdraw(
     ctx: Context<'_>,
-    #[description = "Spectre wallet address"] address: String,
-    #[description = "Amount"] amount: String,
     #[min_length = 10]
     #[description = "password"]
     password: String,
+    #[description = "Amount (optional with a payment uri amount)"] amount: Option<String>,
+    #[description = "Spectre address or spectre: payment uri"] address: Option<String>,
+    #[description = "Withdraw to the linked address of this user"] user: Option<serenity::User>,
+    #[description = "Fee priority: low, normal, high or sompi amount"] priority: Option<String>,
 ) -> Result<(), Error> {
-    let recipient_address = match Address::try_from(address.as_str()) {
-        Ok(address) => address,
-        Err(_) => {
-            let embed =
-                create_error_embed("Error while withdrawing funds", "Invalid Spectre address");
+    let tip_context = ctx.data();
+
+    let payment_uri = match (address, user) {
+        (Some(_), Some(_)) => {
+            let embed = create_error_embed(
+                "Error while withdrawing funds",
+                "Give either an address or a user, not both",
+            );
             return send_reply(ctx, embed, true).await;
         }
+        (Some(address), None) => match PaymentUri::try_parse(&address) {
+            Ok(payment_uri) => payment_uri,
+            Err(e) => {
+                let embed = create_error_embed("Error while withdrawing funds", &e.to_string());
+                return send_reply(ctx, embed, true).await;
+            }
+        },
+        (None, target) => {
+            let target_identifier = target.as_ref().unwrap_or(ctx.author()).id.to_string();
+
+            match tip_context
+                .linked_address_metadata_store
+                .find_default_linked_address_by_owner_identifier(&target_identifier)
+                .await
+            {
+                Ok(linked_address) => PaymentUri::new(linked_address.address, None, None, None),
+                Err(_) => {
+                    let embed = create_error_embed(
+                        "Error while withdrawing funds",
+                        &format!(
+                            "No address given and <@{}> has no linked address",
+                            target_identifier
+                        ),
+                    );
+                    return send_reply(ctx, embed, true).await;
+                }
+            }
+        }
     };
 
-    let amount_sompi = try_parse_required_nonzero_spectre_as_sompi_u64(Some(amount))?;
+    if let Err(e) = ensure_address_network(&payment_uri.address, tip_context.network_id()) {
+        let embed = create_error_embed("Error while withdrawing funds", &e.to_string());
+        return send_reply(ctx, embed, true).await;
+    }
+
+    // an explicit amount takes precedence over the payment uri one
+    let amount_sompi = match (amount, payment_uri.amount_sompi) {
+        (Some(amount), _) => try_parse_required_nonzero_spectre_as_sompi_u64(Some(amount))?,
+        (None, Some(amount_sompi)) => amount_sompi,
+        (None, None) => {
+            let embed = create_error_embed("Error while withdrawing funds", "Missing amount");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let recipient_address = payment_uri.address.clone();
+
+    let fee_priority = match priority.map(|p| FeePriority::from_str(&p)).transpose() {
+        Ok(fee_priority) => fee_priority,
+        Err(e) => {
+            let embed = create_error_embed("Error while withdrawing funds", &e.to_string());
+            return send_reply(ctx, embed, true).await;
+        }
+    };
 
     let author = ctx.author();
     let wallet_owner_identifier = author.id.to_string();
-    let tip_context = ctx.data();
 
     let is_opened = tip_context.does_opened_owned_wallet_exists(&wallet_owner_identifier);
     let is_initiated = match is_opened {
@@ -67,6 +130,26 @@ pub async fn withdraw(
         }
     };
 
+    if tip_wallet.is_watch_only() {
+        let embed = create_error_embed(
+            "Error",
+            "This is a watch-only wallet, it cannot sign transactions",
+        );
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let idempotency_key = build_idempotency_key("withdraw", ctx.id());
+
+    if tip_context
+        .outbox_store
+        .find_by_idempotency_key(&idempotency_key)
+        .await
+        .is_some()
+    {
+        let embed = create_error_embed("Error", "This request has already been processed");
+        return send_reply(ctx, embed, true).await;
+    }
+
     ctx.defer_ephemeral().await?;
 
     let wallet = tip_wallet.wallet();
@@ -89,34 +172,113 @@ pub async fn withdraw(
         }
     };
 
+    let priority_fee = tip_context
+        .fee_policy()
+        .resolve_priority_fee(
+            tip_context.rpc_api(),
+            fee_priority,
+            generator_summary_option.aggregated_fees,
+        )
+        .await?;
+
+    let network_id = tip_context.network_id();
+    let balance = account.balance().unwrap_or_default();
+
+    // fees are paid by the receiver, deducted from the requested amount
+    let preview = create_warning_embed(
+        "Withdrawal Preview",
+        "Review the withdrawal, it is signed and broadcasted only once confirmed",
+    )
+    .field("Recipient", format!("`{}`", recipient_address), false)
+    .field(
+        "Amount",
+        sompi_to_spectre_string_with_suffix(amount_sompi, &network_id),
+        true,
+    )
+    .field(
+        "Estimated Fee",
+        sompi_to_spectre_string_with_suffix(
+            generator_summary_option.aggregated_fees + priority_fee,
+            &network_id,
+        ),
+        true,
+    )
+    .field(
+        "Recipient Receives",
+        sompi_to_spectre_string_with_suffix(
+            amount_minus_gas_fee.saturating_sub(priority_fee),
+            &network_id,
+        ),
+        true,
+    )
+    .field(
+        "Resulting Balance",
+        sompi_to_spectre_string_with_suffix(
+            balance.mature.saturating_sub(amount_sompi),
+            &network_id,
+        ),
+        true,
+    )
+    .field(
+        "UTXOs Spent",
+        generator_summary_option.aggregated_utxos.to_string(),
+        true,
+    );
+
+    if !confirm_transaction_preview(ctx, preview).await? {
+        return Ok(());
+    }
+
+    // withdrawal receipts are private, they are re-sent by direct message if the bot stops midway
+    let outbox_entry = OutboxEntry::new(
+        idempotency_key.clone(),
+        wallet_owner_identifier.clone(),
+        ctx.channel_id().get(),
+        false,
+        format!(
+            "Withdrew {} to address `{}`",
+            sompi_to_spectre_string_with_suffix(amount_sompi, &network_id),
+            recipient_address
+        ),
+        now_unix_secs(),
+    );
+
+    match tip_context.outbox_store.add(&outbox_entry).await {
+        Ok(_) => (),
+        Err(SpectreError::OutboxEntryAlreadyExists()) => {
+            let embed = create_error_embed("Error", "This request has already been processed");
+            return send_reply(ctx, embed, true).await;
+        }
+        Err(e) => return Err(e.into()),
+    }
+
     let abortable = Abortable::default();
     let wallet_secret = Secret::from(password);
-    let outputs = PaymentOutputs::from((recipient_address.clone(), amount_minus_gas_fee));
-
-    let (summary, hashes) = match account
-        .send(
-            outputs.into(),
-            Fees::ReceiverPays(0),
-            None,
-            wallet_secret,
-            None,
-            &abortable,
-            Some(Arc::new(
-                move |ptx: &spectre_wallet_core::tx::PendingTransaction| {
-                    println!("tx notifier: {:?}", ptx);
-                },
-            )),
-        )
-        .await
+    // receiver pays: the generator deducts the fees from this output
+    let outputs = PaymentOutputs::from((recipient_address.clone(), amount_sompi));
+
+    let (summary, tx_ids) = match send_with_outbox(
+        tip_context,
+        &account,
+        outputs.into(),
+        Fees::ReceiverPays(priority_fee),
+        wallet_secret,
+        &abortable,
+        &idempotency_key,
+    )
+    .await
     {
         Ok(result) => result,
         Err(e) => {
+            forget_unsubmitted_outbox_entry(tip_context, &idempotency_key).await?;
+
             let embed = create_error_embed("Error", &format!("Withdrawal failed: {}", e));
             return send_reply(ctx, embed, true).await;
         }
     };
 
-    let tx_id = hashes[0].to_string();
+    // the last transaction is the one paying the address, the previous ones compound utxos
+    let tx_id = tx_ids[tx_ids.len() - 1].clone();
 
     let embed = create_success_embed(
         "Withdrawal Successful",
@@ -127,7 +289,24 @@ pub async fn withdraw(
         "Explorer",
         get_tx_explorer_url(&tx_id, tip_context.network_id().network_type()),
         false,
+    )
+    .field(
+        "Fee",
+        sompi_to_spectre_string_with_suffix(summary.aggregated_fees, &tip_context.network_id()),
+        false,
     );
 
-    send_reply(ctx, embed, true).await
+    let embed = match payment_uri.message.or(payment_uri.label) {
+        Some(memo) => embed.field("Memo", memo, false),
+        None => embed,
+    };
+
+    send_reply(ctx, embed, true).await?;
+
+    tip_context
+        .outbox_store
+        .mark_delivered(&idempotency_key)
+        .await?;
+
+    Ok(())
 }
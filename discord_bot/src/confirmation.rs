@@ -0,0 +1,116 @@
+use core::{
+    tip_context::TipContext,
+    transaction_tracker::{TrackedTransaction, TransactionState},
+};
+use poise::serenity_prelude::{
+    ChannelId, Colour, CommandInteraction, CreateEmbed, CreateInteractionResponseFollowup,
+    CreateMessage, EditMessage, Http, MessageId, UserId,
+};
+use std::sync::Arc;
+use tracing::error;
+
+use crate::utils::*;
+
+/**
+ * messages to update once the tracked transaction reaches a final state
+ */
+pub struct ConfirmationMessages {
+    pub http: Arc<Http>,
+    // none when the receipt is private, the private message then shows the embed
+    pub public_message: Option<(ChannelId, MessageId)>,
+    // receipt embed without its status field
+    pub public_embed: CreateEmbed,
+    pub interaction: CommandInteraction,
+    pub private_message_id: MessageId,
+    // recipients notified once the funds are spendable
+    pub recipients: Vec<(UserId, String)>,
+}
+
+pub fn with_transaction_state(embed: CreateEmbed, state: TransactionState) -> CreateEmbed {
+    let colour = match state {
+        TransactionState::Confirmed => Colour::DARK_GREEN,
+        TransactionState::Failed => Colour::DARK_RED,
+        _ => Colour::ORANGE,
+    };
+
+    embed
+        .field("Status", state.to_string(), false)
+        .colour(colour)
+}
+
+/**
+ * follow the transaction in the background, the command does not wait for its maturity
+ */
+pub fn spawn_confirmation_tracking(
+    tip_context: Arc<TipContext>,
+    tracked_transaction: TrackedTransaction,
+    messages: ConfirmationMessages,
+) {
+    tokio::spawn(async move {
+        let state = match tracked_transaction
+            .wait_for_final_state(tip_context.rpc_api())
+            .await
+        {
+            Ok(state) => state,
+            Err(e) => {
+                error!(
+                    "Error while tracking transaction {}: {}",
+                    tracked_transaction.tx_id, e
+                );
+                return;
+            }
+        };
+
+        let receipt_embed = with_transaction_state(messages.public_embed, state);
+        let mut private_followup =
+            CreateInteractionResponseFollowup::new().content(format!("Transaction {}", state));
+
+        match messages.public_message {
+            Some((public_channel_id, public_message_id)) => {
+                let public_update = public_channel_id
+                    .edit_message(
+                        &messages.http,
+                        public_message_id,
+                        EditMessage::new().embed(receipt_embed),
+                    )
+                    .await;
+
+                if let Err(e) = public_update {
+                    error!("Error while updating the public transaction message: {}", e);
+                }
+            }
+            None => private_followup = private_followup.embed(receipt_embed),
+        }
+
+        let private_update = messages
+            .interaction
+            .edit_followup(
+                &messages.http,
+                messages.private_message_id,
+                private_followup,
+            )
+            .await;
+
+        if let Err(e) = private_update {
+            error!(
+                "Error while updating the private transaction message: {}",
+                e
+            );
+        }
+
+        if state != TransactionState::Confirmed {
+            return;
+        }
+
+        for (recipient_id, description) in messages.recipients {
+            let embed = create_success_embed("Funds Received", &description);
+
+            if let Err(e) = recipient_id
+                .direct_message(&messages.http, CreateMessage::new().embed(embed))
+                .await
+            {
+                error!("Error while notifying the recipient: {}", e);
+            }
+        }
+    });
+}
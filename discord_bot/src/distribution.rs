@@ -0,0 +1,438 @@
+use core::{
+    error::Error as SpectreError,
+    fee_policy::FeePriority,
+    outbox::OutboxEntry,
+    transaction_tracker::{TrackedTransaction, TransactionState},
+    utils::{build_idempotency_key, estimate_fees, get_tx_explorer_url, now_unix_secs},
+};
+use poise::{
+    serenity_prelude::{CreateAllowedMentions, CreateMessage, UserId},
+    CreateReply,
+};
+use spectre_wallet_core::{
+    tx::{Fees, PaymentOutput, PaymentOutputs},
+    utils::sompi_to_spectre_string_with_suffix,
+};
+use spectre_wallet_keys::secret::Secret;
+use spectre_wrpc_client::prelude::NetworkId;
+use std::str::FromStr;
+use tracing::error;
+use workflow_core::abortable::Abortable;
+
+use crate::{
+    confirmation::*,
+    models::{Context, Error},
+    outbox::{find_chain_start_hash, send_with_outbox},
+    utils::*,
+};
+
+// outputs per transaction, larger distributions are batched into several transactions
+pub const MAX_OUTPUTS_PER_TRANSACTION: usize = 20;
+
+pub const MAX_RECIPIENTS: usize = 200;
+
+/**
+ * spend from the author wallet to several users, in as few transactions as possible
+ */
+pub struct Distribution {
+    // prefix of the idempotency key, e.g. the command name
+    pub operation: &'static str,
+    // public description of the distribution, recipients are listed below it
+    pub description: String,
+    pub recipients: Vec<(UserId, u64)>,
+    // content of the public message, every recipient is mentioned by default
+    pub mention: Option<String>,
+    pub password: String,
+    pub priority: Option<String>,
+}
+
+fn format_recipients(recipients: &[(UserId, u64)], network_id: NetworkId) -> String {
+    format_field_lines(
+        recipients
+            .iter()
+            .map(|(user_id, amount_sompi)| {
+                format!(
+                    "<@{}>: {}",
+                    user_id,
+                    sompi_to_spectre_string_with_suffix(*amount_sompi, &network_id)
+                )
+            })
+            .collect(),
+    )
+}
+
+pub async fn distribute(ctx: Context<'_>, distribution: Distribution) -> Result<(), Error> {
+    let Distribution {
+        operation,
+        description,
+        recipients,
+        mention,
+        password,
+        priority,
+    } = distribution;
+
+    if recipients.is_empty() {
+        let embed = create_error_embed("Error", "No recipient");
+        return send_reply(ctx, embed, true).await;
+    }
+
+    if recipients.len() > MAX_RECIPIENTS {
+        let embed = create_error_embed(
+            "Error",
+            &format!("Too many recipients, the maximum is {}", MAX_RECIPIENTS),
+        );
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let fee_priority = match priority.map(|p| FeePriority::from_str(&p)).transpose() {
+        Ok(fee_priority) => fee_priority,
+        Err(e) => {
+            let embed = create_error_embed("Error", &e.to_string());
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let author = ctx.author();
+    let wallet_owner_identifier = author.id.to_string();
+
+    let tip_context = ctx.data();
+
+    for (_, amount_sompi) in &recipients {
+        if let Err(reason) =
+            check_guild_tip_amount(tip_context, ctx.guild_id(), *amount_sompi).await
+        {
+            let embed = create_error_embed("Error", &reason);
+            return send_reply(ctx, embed, true).await;
+        }
+    }
+
+    let is_opened = tip_context.does_opened_owned_wallet_exists(&wallet_owner_identifier);
+    let is_initiated = match is_opened {
+        true => true,
+        false => {
+            tip_context
+                .local_store()?
+                .exists(Some(&wallet_owner_identifier))
+                .await?
+        }
+    };
+
+    if !is_initiated {
+        let embed = create_error_embed("Error", "Wallet not initiated yet");
+        return send_reply(ctx, embed, true).await;
+    }
+
+    if !is_opened {
+        let embed = create_error_embed("Error", "Wallet not opened");
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let tip_wallet = match tip_context.get_opened_owned_wallet(&wallet_owner_identifier) {
+        Some(w) => w,
+        None => {
+            let embed = create_error_embed("Error", "Unexpected error: wallet not opened");
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    if tip_wallet.is_watch_only() {
+        let embed = create_error_embed(
+            "Error",
+            "This is a watch-only wallet, it cannot sign transactions",
+        );
+        return send_reply(ctx, embed, true).await;
+    }
+
+    let idempotency_key = build_idempotency_key(operation, ctx.id());
+
+    if tip_context
+        .outbox_store
+        .find_by_idempotency_key(&idempotency_key)
+        .await
+        .is_some()
+    {
+        let embed = create_error_embed("Error", "This request has already been processed");
+        return send_reply(ctx, embed, true).await;
+    }
+
+    ctx.defer_ephemeral().await?;
+
+    let network_id = tip_context.network_id();
+
+    // find address of each recipient or create temporary wallets
+    let mut outputs = vec![];
+    let mut notifications = vec![];
+
+    for (user_id, amount_sompi) in &recipients {
+        let (address, recipient_has_owned_wallet) = match resolve_recipient_address(
+            tip_context,
+            &wallet_owner_identifier,
+            &user_id.to_string(),
+        )
+        .await
+        {
+            Ok(resolved) => resolved,
+            Err(e) => {
+                let embed = create_error_embed("Error", &format!("Error: {:}", e));
+                return send_reply(ctx, embed, true).await;
+            }
+        };
+
+        let amount_formatted = sompi_to_spectre_string_with_suffix(*amount_sompi, &network_id);
+        let notification = match recipient_has_owned_wallet {
+            true => format!(
+                "<@{}> sent you {}, the funds are now spendable",
+                author.id, amount_formatted
+            ),
+            false => format!(
+                "<@{}> sent you {}, create a wallet and use `claim` to receive the funds",
+                author.id, amount_formatted
+            ),
+        };
+
+        outputs.push(PaymentOutput::new(address, *amount_sompi));
+        notifications.push((*user_id, notification));
+    }
+
+    let total_amount_sompi: u64 = recipients.iter().map(|(_, amount)| amount).sum();
+    let recipients_formatted = format_recipients(&recipients, network_id);
+
+    let wallet = tip_wallet.wallet();
+    let account = wallet.account()?;
+
+    let mut batches = vec![];
+    let mut estimated_fees = 0;
+    let mut estimated_utxos = 0;
+
+    for (batch_outputs, batch_notifications) in outputs
+        .chunks(MAX_OUTPUTS_PER_TRANSACTION)
+        .zip(notifications.chunks(MAX_OUTPUTS_PER_TRANSACTION))
+    {
+        let batch_outputs = PaymentOutputs {
+            outputs: batch_outputs.to_vec(),
+        };
+
+        // estimates of later batches ignore the utxos spent by the previous ones
+        let base_fees_summary = estimate_fees(&account, batch_outputs.clone()).await?;
+        let priority_fee = tip_context
+            .fee_policy()
+            .resolve_priority_fee(
+                tip_context.rpc_api(),
+                fee_priority,
+                base_fees_summary.aggregated_fees,
+            )
+            .await?;
+
+        estimated_fees += base_fees_summary.aggregated_fees + priority_fee;
+        estimated_utxos += base_fees_summary.aggregated_utxos;
+
+        batches.push((batch_outputs, batch_notifications.to_vec(), priority_fee));
+    }
+
+    let balance = account.balance().unwrap_or_default();
+
+    let preview = create_warning_embed(
+        "Transaction Preview",
+        "Review the transaction, it is signed and broadcasted only once confirmed",
+    )
+    .field("Recipients", recipients_formatted.clone(), false)
+    .field(
+        "Total Amount",
+        sompi_to_spectre_string_with_suffix(total_amount_sompi, &network_id),
+        true,
+    )
+    .field(
+        "Estimated Fee",
+        sompi_to_spectre_string_with_suffix(estimated_fees, &network_id),
+        true,
+    )
+    .field(
+        "Resulting Balance",
+        sompi_to_spectre_string_with_suffix(
+            balance
+                .mature
+                .saturating_sub(total_amount_sompi + estimated_fees),
+            &network_id,
+        ),
+        true,
+    )
+    .field("UTXOs Spent", estimated_utxos.to_string(), true)
+    .field("Transactions", batches.len().to_string(), true);
+
+    if !confirm_transaction_preview(ctx, preview).await? {
+        return Ok(());
+    }
+
+    let receipt_channel_id = find_receipt_channel(ctx).await;
+
+    // recorded before signing, so the receipt can be re-posted if the bot stops midway
+    let outbox_entry = OutboxEntry::new(
+        idempotency_key.clone(),
+        wallet_owner_identifier.clone(),
+        ctx.channel_id().get(),
+        receipt_channel_id.is_some(),
+        format!("{}\n{}", description, recipients_formatted),
+        now_unix_secs(),
+    );
+
+    match tip_context.outbox_store.add(&outbox_entry).await {
+        Ok(_) => (),
+        Err(SpectreError::OutboxEntryAlreadyExists()) => {
+            let embed = create_error_embed("Error", "This request has already been processed");
+            return send_reply(ctx, embed, true).await;
+        }
+        Err(e) => return Err(e.into()),
+    }
+
+    let abortable = Abortable::default();
+
+    let mut tx_urls = vec![];
+    let mut total_fees = 0;
+    let mut paid_notifications = vec![];
+    let mut tracked_transaction = None;
+    let mut failure = None;
+
+    for (batch_outputs, batch_notifications, priority_fee) in batches {
+        let tracked_address = batch_outputs.outputs[0].address.clone();
+        let wallet_secret = Secret::from(password.clone());
+
+        let (summary, tx_ids) = match send_with_outbox(
+            tip_context,
+            &account,
+            batch_outputs.into(),
+            Fees::SenderPays(priority_fee),
+            wallet_secret,
+            &abortable,
+            &idempotency_key,
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => {
+                failure = Some(e);
+                break;
+            }
+        };
+
+        // the last transaction is the one paying the recipients, the previous ones compound utxos
+        let final_tx_id = tx_ids[tx_ids.len() - 1].clone();
+
+        tx_urls.push(get_tx_explorer_url(&final_tx_id, network_id.network_type()));
+        total_fees += summary.aggregated_fees;
+        paid_notifications.extend(batch_notifications);
+        tracked_transaction = Some(TrackedTransaction::new(
+            final_tx_id,
+            tracked_address,
+            find_chain_start_hash(tip_context, &idempotency_key).await,
+        ));
+    }
+
+    // nothing has been broadcasted, the distribution can be retried
+    let tracked_transaction = match (tracked_transaction, failure.as_ref()) {
+        (Some(tracked_transaction), _) => tracked_transaction,
+        (None, failure) => {
+            forget_unsubmitted_outbox_entry(tip_context, &idempotency_key).await?;
+
+            let reason = failure
+                .map(|e| e.to_string())
+                .unwrap_or("no transaction generated".into());
+            let embed = create_error_embed("Error", &format!("Transaction failed: {}", reason));
+            return send_reply(ctx, embed, true).await;
+        }
+    };
+
+    let mut public_embed = create_success_embed("Transaction Submitted", &description)
+        .field("Recipients", recipients_formatted, false)
+        .field("Explorer", format_field_lines(tx_urls), false)
+        .field(
+            "Fee",
+            sompi_to_spectre_string_with_suffix(total_fees, &network_id),
+            false,
+        );
+
+    if let Some(e) = failure {
+        public_embed = public_embed.field(
+            "Partially Sent",
+            format!(
+                "Only {} of {} recipients have been paid: {}",
+                paid_notifications.len(),
+                recipients.len(),
+                e
+            ),
+            false,
+        );
+    }
+
+    // public mentionning, unless the guild keeps receipts private
+    let mention = mention.unwrap_or(format_mentions(
+        recipients
+            .iter()
+            .map(|(user_id, _)| format!("<@{}>", user_id))
+            .collect(),
+    ));
+
+    let public_message = match receipt_channel_id {
+        Some(channel_id) => {
+            let public_message = CreateMessage::new()
+                .add_embeds(vec![with_transaction_state(
+                    public_embed.clone(),
+                    TransactionState::Pending,
+                )])
+                .content(mention)
+                // recipients are pinged, roles named in the message are not
+                .allowed_mentions(CreateAllowedMentions::new().all_users(true));
+            // the funds are sent, a failed announcement must not prevent the delivery below
+            match channel_id.send_message(ctx, public_message).await {
+                Ok(public_message) => Some((channel_id, public_message.id)),
+                Err(e) => {
+                    error!(
+                        "Impossible to post the receipt of {}: {}",
+                        idempotency_key, e
+                    );
+                    None
+                }
+            }
+        }
+        None => None,
+    };
+
+    tip_context
+        .outbox_store
+        .mark_delivered(&idempotency_key)
+        .await?;
+
+    // private mentionning, carrying the receipt when it is not public
+    let private_embeds = match public_message {
+        Some(_) => vec![],
+        None => vec![with_transaction_state(
+            public_embed.clone(),
+            TransactionState::Pending,
+        )],
+    };
+
+    let private_reply = ctx
+        .send(CreateReply {
+            content: Some(format!("Transaction {}", TransactionState::Pending)),
+            embeds: private_embeds,
+            ephemeral: Some(true),
+            ..Default::default()
+        })
+        .await?;
+    let private_message_id = private_reply.message().await?.id;
+
+    // the last batch has been submitted last, it is the last one to mature
+    spawn_confirmation_tracking(
+        tip_context.clone(),
+        tracked_transaction,
+        ConfirmationMessages {
+            http: ctx.serenity_context().http.clone(),
+            public_message,
+            public_embed,
+            interaction: ctx.interaction.clone(),
+            private_message_id,
+            recipients: paid_notifications,
+        },
+    );
+
+    Ok(())
+}
@@ -0,0 +1,243 @@
+use core::{
+    error::Error as SpectreError,
+    escrow::{Escrow, EscrowAction, EscrowActor, EscrowEvent},
+    tip_context::TipContext,
+    tip_custodial_wallet::TipCustodialWallet,
+    utils::{build_idempotency_key, get_tx_explorer_url, now_unix_secs},
+};
+use poise::serenity_prelude::{CacheHttp, ChannelId, CreateEmbed, CreateMessage, Http};
+use spectre_wallet_core::utils::sompi_to_spectre_string_with_suffix;
+use spectre_wallet_keys::secret::Secret;
+use std::{sync::Arc, time::Duration};
+use tracing::{error, info};
+
+use crate::{transfer::*, utils::*};
+
+// funded escrows past their deadline are refunded on the next check
+const ESCROW_DEADLINE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+pub fn create_escrow_embed(tip_context: &Arc<TipContext>, escrow: &Escrow) -> CreateEmbed {
+    let mut embed = create_warning_embed(
+        &format!("Escrow #{}", escrow.id),
+        &format!(
+            "<@{}> buys from <@{}>",
+            escrow.buyer_identifier, escrow.seller_identifier
+        ),
+    )
+    .field(
+        "Amount",
+        sompi_to_spectre_string_with_suffix(escrow.amount_sompi, &tip_context.network_id()),
+        true,
+    )
+    .field("Status", escrow.status.to_string(), true)
+    .field("Deadline", format!("<t:{}:R>", escrow.deadline), true);
+
+    if let Some(memo) = &escrow.memo {
+        embed = embed.field("Memo", memo, false);
+    }
+
+    embed.field(
+        "Escrow Address",
+        escrow.receive_address.address_to_string(),
+        false,
+    )
+}
+
+/**
+ * one line per state change, oldest first
+ */
+pub fn format_escrow_history(tip_context: &Arc<TipContext>, escrow: &Escrow) -> Vec<String> {
+    escrow
+        .events
+        .iter()
+        .map(|event| {
+            let actor = match (&event.actor_identifier, event.actor) {
+                (Some(identifier), EscrowActor::Arbiter) => format!("arbiter <@{}>", identifier),
+                (Some(identifier), _) => format!("<@{}>", identifier),
+                (None, _) => "deadline".to_string(),
+            };
+
+            let tx = event
+                .tx_id
+                .as_ref()
+                .map(|tx_id| {
+                    format!(
+                        " ({})",
+                        get_tx_explorer_url(tx_id, tip_context.network_id().network_type())
+                    )
+                })
+                .unwrap_or_default();
+
+            format!("<t:{}:f> {} by {}{}", event.at, event.action, actor, tx)
+        })
+        .collect()
+}
+
+/**
+ * post the escrow update in its channel, both parties are mentioned
+ */
+pub async fn announce_escrow(
+    cache_http: impl CacheHttp,
+    escrow: &Escrow,
+    embed: CreateEmbed,
+) -> Result<(), SpectreError> {
+    ChannelId::new(escrow.channel_id)
+        .send_message(
+            cache_http,
+            CreateMessage::new()
+                .content(format!(
+                    "<@{}> <@{}>",
+                    escrow.buyer_identifier, escrow.seller_identifier
+                ))
+                .embed(embed),
+        )
+        .await
+        .map_err(|e| SpectreError::custom(e.to_string()))?;
+
+    Ok(())
+}
+
+/**
+ * pay the escrow funds to the seller (release) or back to the buyer (refund) and record it,
+ * a settlement interrupted after broadcasting is recorded without paying twice
+ */
+pub async fn settle_escrow(
+    cache_http: impl CacheHttp,
+    tip_context: &Arc<TipContext>,
+    escrow: &Escrow,
+    action: EscrowAction,
+    actor: EscrowActor,
+    actor_identifier: Option<String>,
+) -> Result<Escrow, SpectreError> {
+    // a concurrent release and refund must not both pay, the first claim wins
+    let escrow = &tip_context
+        .escrow_store
+        .begin_settlement(escrow.id, action, actor)
+        .await?;
+
+    let (sender_identifier, recipient_identifier) = match action {
+        EscrowAction::Release => (&escrow.buyer_identifier, &escrow.seller_identifier),
+        _ => (&escrow.seller_identifier, &escrow.buyer_identifier),
+    };
+
+    let network_id = tip_context.network_id();
+    let amount_formatted = sompi_to_spectre_string_with_suffix(escrow.amount_sompi, &network_id);
+
+    let transfer = BackgroundTransfer {
+        idempotency_key: build_idempotency_key("escrow-settle", escrow.id),
+        sender_identifier: sender_identifier.clone(),
+        recipient: TransferRecipient::User(recipient_identifier.clone()),
+        amount_sompi: escrow.amount_sompi,
+        receiver_pays: true,
+        channel_id: escrow.channel_id,
+        receipt: format!(
+            "Escrow #{} {}: {} to <@{}>",
+            escrow.id, action, amount_formatted, recipient_identifier
+        ),
+    };
+
+    let result = async {
+        let escrow_wallet = TipCustodialWallet::open(
+            tip_context.clone(),
+            &Secret::from(escrow.secret.clone()),
+            &escrow.wallet_identifier,
+        )
+        .await?;
+
+        let result = send_background_transfer(
+            tip_context,
+            &escrow_wallet.wallet().account()?,
+            Secret::from(escrow.secret.clone()),
+            None,
+            &transfer,
+        )
+        .await;
+
+        if let Err(e) = escrow_wallet.wallet().stop().await {
+            error!("Error while stopping escrow wallet: {}", e);
+        }
+
+        result
+    }
+    .await;
+
+    let tx_id = match result {
+        Ok(tx_id) => tx_id,
+        Err(SpectreError::OutboxEntryAlreadyExists()) => {
+            find_submitted_transfer(tip_context, &transfer.idempotency_key)
+                .await
+                .ok_or(SpectreError::OutboxEntryAlreadyExists())?
+        }
+        Err(e) => {
+            if is_transfer_forgotten(tip_context, &transfer.idempotency_key).await {
+                tip_context.escrow_store.abort_settlement(escrow.id).await?;
+            }
+
+            return Err(e);
+        }
+    };
+
+    let settled = tip_context
+        .escrow_store
+        .apply(
+            escrow.id,
+            EscrowEvent {
+                action,
+                actor,
+                actor_identifier,
+                tx_id: Some(tx_id.clone()),
+                at: now_unix_secs(),
+            },
+        )
+        .await?;
+
+    let embed = create_escrow_embed(tip_context, &settled)
+        .title(format!("Escrow #{} {}", settled.id, settled.status))
+        .field(
+            "Explorer",
+            get_tx_explorer_url(&tx_id, network_id.network_type()),
+            false,
+        );
+    announce_escrow(cache_http, &settled, embed).await?;
+
+    tip_context
+        .outbox_store
+        .mark_delivered(&transfer.idempotency_key)
+        .await?;
+
+    Ok(settled)
+}
+
+/**
+ * refund the funded escrows whose deadline passed, disputed escrows wait for the arbiter
+ */
+pub fn spawn_escrow_deadline_watcher(http: Arc<Http>, tip_context: Arc<TipContext>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(ESCROW_DEADLINE_CHECK_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            for escrow in tip_context
+                .escrow_store
+                .find_past_deadline(now_unix_secs())
+                .await
+            {
+                info!("Refunding escrow #{} past its deadline", escrow.id);
+
+                if let Err(e) = settle_escrow(
+                    &http,
+                    &tip_context,
+                    &escrow,
+                    EscrowAction::Refund,
+                    EscrowActor::Deadline,
+                    None,
+                )
+                .await
+                {
+                    error!("Error while refunding escrow #{}: {}", escrow.id, e);
+                }
+            }
+        }
+    });
+}
@@ -1,3 +1,15 @@
+pub mod balance_role;
+pub mod bounty;
 pub mod commands;
+pub mod confirmation;
+pub mod distribution;
+pub mod escrow;
 pub mod models;
+pub mod outbox;
+pub mod payment_request;
+pub mod reaction_tip;
+pub mod role_shop;
+pub mod schedule;
+pub mod transfer;
+pub mod treasury;
 pub mod utils;
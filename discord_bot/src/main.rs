@@ -1,4 +1,8 @@
-use core::tip_context::TipContext;
+use core::{
+    fee_policy::{FeePolicy, FeePriority},
+    tip_context::TipContext,
+    utils::try_parse_required_nonzero_spectre_as_sompi_u64,
+};
 use poise::{
     samples::on_error,
     serenity_prelude::{self as serenity},
@@ -14,7 +18,16 @@ use tracing::{error, info};
 use tracing_subscriber::EnvFilter;
 
 use core::utils::check_node_status;
+use discord_bot::balance_role::spawn_balance_role_evaluator;
+use discord_bot::bounty::spawn_bounty_expiry_watcher;
 use discord_bot::commands::*;
+use discord_bot::escrow::spawn_escrow_deadline_watcher;
+use discord_bot::outbox::reconcile_outbox;
+use discord_bot::payment_request::*;
+use discord_bot::reaction_tip::handle_reaction_add;
+use discord_bot::role_shop::start_role_shop;
+use discord_bot::schedule::start_schedules;
+use discord_bot::treasury::*;
 use discord_bot::utils::*;
 
 type Error = Box<dyn std::error::Error + Send + Sync>;
@@ -34,7 +47,31 @@ type Context<'a> = poise::ApplicationContext<'a, Arc<TipContext>, Error>;
         "claim",
         "change_password",
         "withdraw",
-        "compound"
+        "compound",
+        "watch",
+        "import",
+        "backup",
+        "import_file",
+        "sign",
+        "verify",
+        "link",
+        "link_confirm",
+        "unlink",
+        "request_uri",
+        "receive",
+        "send_many",
+        "opt_out",
+        "tip_role",
+        "rain",
+        "reaction_tips",
+        "request",
+        "requests",
+        "escrow",
+        "schedule",
+        "treasury",
+        "bounty",
+        "shop",
+        "balance_roles"
     ),
     category = "wallet"
 )]
@@ -43,6 +80,56 @@ async fn wallet(_: Context<'_>) -> Result<(), Error> {
     Ok(())
 }
 
+async fn event_handler(
+    ctx: &serenity::Context,
+    event: &serenity::FullEvent,
+    _framework: poise::FrameworkContext<'_, Arc<TipContext>, Error>,
+    data: &Arc<TipContext>,
+) -> Result<(), Error> {
+    match event {
+        serenity::FullEvent::Message { new_message } => {
+            if !new_message.author.bot && !new_message.author.system {
+                data.activity_tracker().record_message(
+                    new_message.channel_id.get(),
+                    new_message.author.id.get(),
+                    new_message.timestamp.unix_timestamp() as u64,
+                );
+            }
+        }
+        serenity::FullEvent::ReactionAdd { add_reaction } => {
+            if let Err(e) = handle_reaction_add(ctx, data, add_reaction).await {
+                error!("Error while handling a reaction tip: {}", e);
+            }
+        }
+        serenity::FullEvent::InteractionCreate { interaction } => {
+            if let Some(component) = interaction.as_message_component() {
+                if component
+                    .data
+                    .custom_id
+                    .starts_with(PAYMENT_REQUEST_BUTTON_PREFIX)
+                {
+                    if let Err(e) = handle_payment_request_interaction(ctx, data, component).await {
+                        error!("Error while handling a payment request: {}", e);
+                    }
+                }
+
+                if component
+                    .data
+                    .custom_id
+                    .starts_with(TREASURY_PAYOUT_BUTTON_PREFIX)
+                {
+                    if let Err(e) = handle_treasury_payout_interaction(ctx, data, component).await {
+                        error!("Error while handling a treasury payout: {}", e);
+                    }
+                }
+            }
+        }
+        _ => (),
+    }
+
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() {
     // load local .env or ignore if file doesn't exists
@@ -66,6 +153,33 @@ async fn main() {
     let wallet_data_path_str =
         env::var("WALLET_DATA_PATH").expect("WALLET_DATA_PATH environment variable is missing");
 
+    // fees
+    let default_fee_priority = match env::var("DEFAULT_FEE_PRIORITY") {
+        Ok(v) => FeePriority::from_str(&v).expect("DEFAULT_FEE_PRIORITY is invalid"),
+        Err(_) => FeePriority::Low,
+    };
+
+    let max_priority_fee_sompi = match env::var("MAX_PRIORITY_FEE") {
+        Ok(v) => try_parse_required_nonzero_spectre_as_sompi_u64(Some(v))
+            .expect("MAX_PRIORITY_FEE is invalid"),
+        // 1 SPR
+        Err(_) => 100_000_000,
+    };
+
+    let fee_policy = FeePolicy::new(default_fee_priority, max_priority_fee_sompi);
+
+    // watch-only wallets
+    let watch_only_wallet_key: Option<String> = match env::var("WATCH_ONLY_WALLET_KEY") {
+        Ok(v) if !v.is_empty() => Some(v),
+        _ => None,
+    };
+
+    // treasuries
+    let treasury_wallet_key: Option<String> = match env::var("TREASURY_WALLET_KEY") {
+        Ok(v) if !v.is_empty() => Some(v),
+        _ => None,
+    };
+
     // RPC
     let forced_spectre_node: Option<String> = match env::var("FORCE_SPECTRE_NODE_ADDRESS") {
         Ok(v) => Some(v),
@@ -130,6 +244,9 @@ async fn main() {
         forced_spectre_node,
         wrpc_client,
         wallet_data_path_buf,
+        fee_policy,
+        watch_only_wallet_key,
+        treasury_wallet_key,
     );
 
     if let Err(e) = tip_context {
@@ -139,7 +256,8 @@ async fn main() {
     // discord
     let framework = poise::Framework::builder()
         .options(poise::FrameworkOptions {
-            commands: vec![wallet()],
+            // context menu commands cannot be nested under /wallet, admin commands are kept apart
+            commands: vec![wallet(), tip_author(), tipadmin()],
             on_error: |error| {
                 Box::pin(async move {
                     match error {
@@ -172,17 +290,42 @@ async fn main() {
                     }
                 })
             },
+            event_handler: |ctx, event, framework, data| {
+                Box::pin(event_handler(ctx, event, framework, data))
+            },
             ..Default::default()
         })
         .setup(|ctx, _ready, framework| {
             Box::pin(async move {
                 poise::builtins::register_globally(ctx, &framework.options().commands).await?;
-                Ok(tip_context.unwrap())
+
+                let tip_context = tip_context.unwrap();
+
+                if let Err(e) = reconcile_outbox(ctx, tip_context.clone()).await {
+                    error!("Error while reconciling the outbox: {}", e);
+                }
+
+                spawn_escrow_deadline_watcher(ctx.http.clone(), tip_context.clone());
+
+                spawn_bounty_expiry_watcher(ctx.http.clone(), tip_context.clone());
+
+                start_schedules(ctx.http.clone(), tip_context.clone());
+
+                start_role_shop(ctx.http.clone(), tip_context.clone());
+
+                spawn_balance_role_evaluator(ctx.http.clone(), tip_context.clone());
+
+                Ok(tip_context)
             })
         })
         .build();
 
-    let intents = serenity::GatewayIntents::non_privileged();
+    // message events feed the activity tracker used by rains, reaction events trigger reaction tips
+    // GUILD_MEMBERS is privileged, `/tip_role` lists role members through it
+    let intents = serenity::GatewayIntents::non_privileged()
+        | serenity::GatewayIntents::GUILD_MESSAGES
+        | serenity::GatewayIntents::GUILD_MESSAGE_REACTIONS
+        | serenity::GatewayIntents::GUILD_MEMBERS;
     let client = serenity::ClientBuilder::new(discord_token, intents)
         .framework(framework)
         .await;
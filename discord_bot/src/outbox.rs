@@ -0,0 +1,248 @@
+use core::{
+    error::Error as SpectreError,
+    outbox::{OutboxEntry, OutboxEntryStatus},
+    tip_context::TipContext,
+    utils::{
+        find_accepting_block_hash, get_tx_explorer_url, is_transaction_in_mempool, now_unix_secs,
+    },
+};
+use futures::TryStreamExt;
+use poise::serenity_prelude::{CacheHttp, ChannelId, CreateEmbed, CreateMessage, UserId};
+use spectre_wallet_core::{
+    prelude::Account,
+    tx::{Fees, Generator, GeneratorSettings, GeneratorSummary, PaymentDestination, Signer},
+};
+use spectre_wallet_keys::secret::Secret;
+use std::sync::Arc;
+use tokio::task::yield_now;
+use tracing::{error, info};
+use workflow_core::abortable::Abortable;
+
+use crate::{models::Error, utils::*};
+
+// delivered entries are kept one day to deduplicate re-delivered interactions
+const DELIVERED_ENTRY_RETENTION_SECS: u64 = 24 * 60 * 60;
+
+/**
+ * same as `Account::send`, but each transaction id is persisted in the outbox entry once signed,
+ * before its submission, and the entry is marked as submitted once the node took it
+ * a failure is recorded in the entry, the transactions submitted before it stay broadcasted
+ */
+pub async fn send_with_outbox(
+    tip_context: &Arc<TipContext>,
+    account: &Arc<dyn Account>,
+    destination: PaymentDestination,
+    fees: Fees,
+    wallet_secret: Secret,
+    abortable: &Abortable,
+    idempotency_key: &str,
+) -> Result<(GeneratorSummary, Vec<String>), SpectreError> {
+    let keydata = account.prv_key_data(wallet_secret).await?;
+    let signer = Arc::new(Signer::new(account.clone(), keydata, None));
+
+    let settings =
+        GeneratorSettings::try_new_with_account(account.clone(), destination, fees, None)?;
+    let generator = Generator::try_new(settings, Some(signer), Some(abortable))?;
+
+    // the signed transactions can only be accepted by chain blocks added after the current sink
+    let chain_start_hash = tip_context.rpc_api().get_sink().await?.sink.to_string();
+
+    let mut stream = generator.stream();
+    let mut tx_ids = vec![];
+
+    let sent: Result<(), SpectreError> = async {
+        while let Some(transaction) = stream.try_next().await? {
+            transaction.try_sign()?;
+
+            let tx_id = transaction.id().to_string();
+            tip_context
+                .outbox_store
+                .record_signed_tx_id(idempotency_key, &tx_id, &chain_start_hash)
+                .await?;
+
+            transaction.try_submit(&tip_context.rpc_api()).await?;
+
+            tip_context
+                .outbox_store
+                .record_tx_ids(idempotency_key, &[tx_id.clone()])
+                .await?;
+
+            tx_ids.push(tx_id);
+            yield_now().await;
+        }
+
+        Ok(())
+    }
+    .await;
+
+    if let Err(e) = sent {
+        if let Err(record_error) = tip_context
+            .outbox_store
+            .record_error(idempotency_key, &e.to_string())
+            .await
+        {
+            error!(
+                "Impossible to record the failure of {}: {}",
+                idempotency_key, record_error
+            );
+        }
+
+        return Err(e);
+    }
+
+    Ok((generator.summary(), tx_ids))
+}
+
+/**
+ * virtual chain sink recorded before the first transaction of the operation was signed
+ */
+pub async fn find_chain_start_hash(
+    tip_context: &Arc<TipContext>,
+    idempotency_key: &str,
+) -> Option<String> {
+    tip_context
+        .outbox_store
+        .find_by_idempotency_key(idempotency_key)
+        .await
+        .and_then(|entry| entry.chain_start_hash)
+}
+
+/**
+ * where the node knows the transaction from, none if it is neither in its mempool nor accepted
+ */
+pub async fn find_transaction_on_node(
+    tip_context: &Arc<TipContext>,
+    entry: &OutboxEntry,
+    tx_id: &str,
+) -> Result<Option<&'static str>, SpectreError> {
+    if is_transaction_in_mempool(tip_context.rpc_api(), tx_id).await? {
+        return Ok(Some("in mempool"));
+    }
+
+    if let Some(chain_start_hash) = &entry.chain_start_hash {
+        if find_accepting_block_hash(tip_context.rpc_api(), tx_id, chain_start_hash)
+            .await?
+            .is_some()
+        {
+            return Ok(Some("accepted"));
+        }
+    }
+
+    Ok(None)
+}
+
+/**
+ * re-post the receipts that were not delivered before the bot stopped
+ */
+pub async fn reconcile_outbox(
+    cache_http: impl CacheHttp,
+    tip_context: Arc<TipContext>,
+) -> Result<(), Error> {
+    let undelivered_entries = tip_context.outbox_store.find_undelivered().await;
+
+    info!(
+        "Reconciling {} undelivered outbox entries",
+        undelivered_entries.len()
+    );
+
+    for entry in undelivered_entries {
+        let embed = match entry.tx_ids.is_empty() {
+            // nothing has been signed, the operation can safely be retried by the user
+            true => create_warning_embed(
+                "Transaction Interrupted",
+                &format!(
+                    "The bot stopped before signing the following operation, no funds were sent:\n{}",
+                    entry.receipt
+                ),
+            ),
+            false => create_recovered_receipt_embed(&tip_context, &entry).await,
+        };
+
+        let message = CreateMessage::new().add_embeds(vec![embed]);
+
+        let delivery = match entry.public {
+            true => ChannelId::new(entry.channel_id)
+                .send_message(&cache_http, message)
+                .await
+                .map(|_| ()),
+            false => UserId::new(entry.owner_identifier.parse::<u64>()?)
+                .direct_message(&cache_http, message)
+                .await
+                .map(|_| ()),
+        };
+
+        if let Err(e) = delivery {
+            error!(
+                "Impossible to deliver the receipt of {}: {}",
+                entry.idempotency_key, e
+            );
+            continue;
+        }
+
+        match entry.tx_ids.is_empty() {
+            true => {
+                tip_context
+                    .outbox_store
+                    .remove(&entry.idempotency_key)
+                    .await?
+            }
+            false => {
+                tip_context
+                    .outbox_store
+                    .mark_delivered(&entry.idempotency_key)
+                    .await?
+            }
+        }
+    }
+
+    tip_context
+        .outbox_store
+        .prune_delivered(now_unix_secs().saturating_sub(DELIVERED_ENTRY_RETENTION_SECS))
+        .await?;
+
+    Ok(())
+}
+
+async fn create_recovered_receipt_embed(
+    tip_context: &Arc<TipContext>,
+    entry: &OutboxEntry,
+) -> CreateEmbed {
+    let mut embed = match (entry.status, &entry.error) {
+        // signed but the submission was not confirmed, the transactions may or may not have been broadcasted
+        (OutboxEntryStatus::Pending, _) => create_warning_embed(
+            "Transaction Status Unknown",
+            &format!(
+                "The bot stopped while broadcasting the following operation, check the transactions below before retrying:\n{}",
+                entry.receipt
+            ),
+        ),
+        // the transactions below were broadcasted before the operation failed, the rest was not sent
+        (_, Some(error)) => create_error_embed(
+            "Transaction Partially Sent",
+            &format!(
+                "The following operation failed after some of its transactions were broadcasted, check the transactions below before retrying:\n{}\nError: {}",
+                entry.receipt, error
+            ),
+        ),
+        (_, None) => create_success_embed("Transaction Receipt (recovered)", &entry.receipt),
+    };
+
+    for tx_id in &entry.tx_ids {
+        let state = match find_transaction_on_node(tip_context, entry, tx_id).await {
+            Ok(Some(state)) => state,
+            Ok(None) => "not found on the node",
+            Err(e) => {
+                error!("Error while checking transaction {}: {}", tx_id, e);
+                "status unknown"
+            }
+        };
+
+        embed = embed.field(
+            format!("Txid ({})", state),
+            get_tx_explorer_url(tx_id, tip_context.network_id().network_type()),
+            false,
+        );
+    }
+
+    embed
+}
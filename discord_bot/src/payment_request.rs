@@ -0,0 +1,350 @@
+use core::{
+    error::Error as SpectreError,
+    fee_policy::FeePriority,
+    payment_request::PaymentRequest,
+    tip_context::TipContext,
+    utils::{build_idempotency_key, get_tx_explorer_url, now_unix_secs},
+};
+use poise::{
+    serenity_prelude::{
+        self as serenity, ButtonStyle, ChannelId, ComponentInteraction, CreateActionRow,
+        CreateButton, CreateEmbed, CreateInteractionResponse, CreateInteractionResponseMessage,
+        CreateMessage, EditMessage, MessageId, UserId,
+    },
+    Modal,
+};
+use spectre_wallet_core::utils::sompi_to_spectre_string_with_suffix;
+use spectre_wallet_keys::secret::Secret;
+use std::{str::FromStr, sync::Arc, time::Duration};
+
+use crate::{models::Error, transfer::*, utils::*};
+
+// custom id of the buttons: payment-request-<pay|decline>-<request id>
+pub const PAYMENT_REQUEST_BUTTON_PREFIX: &str = "payment-request-";
+
+// time left to fill the password modal
+const PAY_MODAL_TIMEOUT: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Modal)]
+#[name = "Pay request"]
+struct PayModal {
+    #[name = "Password"]
+    #[min_length = 10]
+    password: String,
+    #[name = "Fee priority"]
+    #[placeholder = "low, normal, high or an amount in sompi"]
+    priority: Option<String>,
+}
+
+pub fn create_payment_request_buttons(request_id: u64) -> Vec<CreateActionRow> {
+    vec![CreateActionRow::Buttons(vec![
+        CreateButton::new(format!(
+            "{}pay-{}",
+            PAYMENT_REQUEST_BUTTON_PREFIX, request_id
+        ))
+        .label("Pay")
+        .style(ButtonStyle::Success),
+        CreateButton::new(format!(
+            "{}decline-{}",
+            PAYMENT_REQUEST_BUTTON_PREFIX, request_id
+        ))
+        .label("Decline")
+        .style(ButtonStyle::Secondary),
+    ])]
+}
+
+pub fn create_payment_request_embed(
+    tip_context: &Arc<TipContext>,
+    request: &PaymentRequest,
+) -> CreateEmbed {
+    let mut embed = create_warning_embed(
+        "Payment Request",
+        &format!(
+            "<@{}> requests {} from <@{}>",
+            request.requester_identifier,
+            sompi_to_spectre_string_with_suffix(request.amount_sompi, &tip_context.network_id()),
+            request.payer_identifier
+        ),
+    );
+
+    if let Some(memo) = &request.memo {
+        embed = embed.field("Memo", memo, false);
+    }
+
+    embed.field("Expires", format!("<t:{}:R>", request.expires_at), false)
+}
+
+/**
+ * replace the request message by its outcome and remove its buttons, best effort
+ */
+pub async fn close_payment_request_message(
+    http: impl serenity::CacheHttp,
+    request: &PaymentRequest,
+    embed: CreateEmbed,
+) {
+    let _ = ChannelId::new(request.channel_id)
+        .edit_message(
+            http,
+            MessageId::new(request.message_id),
+            EditMessage::new().embed(embed).components(vec![]),
+        )
+        .await;
+}
+
+/**
+ * drop the expired requests and mark their messages as expired
+ */
+pub async fn expire_payment_requests(
+    http: impl serenity::CacheHttp + Copy,
+    tip_context: &Arc<TipContext>,
+) -> Result<(), Error> {
+    let expired = tip_context
+        .payment_request_store
+        .prune_expired(now_unix_secs())
+        .await?;
+
+    for request in expired {
+        let embed =
+            create_payment_request_embed(tip_context, &request).title("Payment Request Expired");
+        close_payment_request_message(http, &request, embed).await;
+    }
+
+    Ok(())
+}
+
+/**
+ * Pay and Decline buttons of payment requests, they outlive the command so they are handled from events
+ */
+pub async fn handle_payment_request_interaction(
+    ctx: &serenity::Context,
+    tip_context: &Arc<TipContext>,
+    interaction: &ComponentInteraction,
+) -> Result<(), Error> {
+    let (action, request_id) = match interaction
+        .data
+        .custom_id
+        .strip_prefix(PAYMENT_REQUEST_BUTTON_PREFIX)
+        .and_then(|rest| rest.split_once('-'))
+        .and_then(|(action, id)| id.parse::<u64>().ok().map(|id| (action, id)))
+    {
+        Some(parsed) => parsed,
+        None => return Ok(()),
+    };
+
+    let request = match tip_context
+        .payment_request_store
+        .find_by_id(request_id)
+        .await
+    {
+        Some(request) if !request.is_expired(now_unix_secs()) => request,
+        _ => {
+            let embed = create_error_embed("Error", "This payment request is closed or expired");
+            return respond_ephemeral(ctx, interaction, embed).await;
+        }
+    };
+
+    let presser_identifier = interaction.user.id.to_string();
+
+    let is_presser_frozen = tip_context
+        .frozen_user_store
+        .is_frozen(&presser_identifier)
+        .await;
+
+    match action {
+        "decline" => decline_payment_request(ctx, tip_context, interaction, request).await,
+        "pay" if is_presser_frozen => {
+            let embed = create_error_embed(
+                "Error",
+                "Spending through the bot is frozen for your account, please contact the operators",
+            );
+            respond_ephemeral(ctx, interaction, embed).await
+        }
+        "pay" if presser_identifier == request.payer_identifier => {
+            pay_payment_request(ctx, tip_context, interaction, request).await
+        }
+        "pay" => {
+            let embed = create_error_embed(
+                "Error",
+                &format!("Only <@{}> can pay this request", request.payer_identifier),
+            );
+            respond_ephemeral(ctx, interaction, embed).await
+        }
+        _ => Ok(()),
+    }
+}
+
+async fn decline_payment_request(
+    ctx: &serenity::Context,
+    tip_context: &Arc<TipContext>,
+    interaction: &ComponentInteraction,
+    request: PaymentRequest,
+) -> Result<(), Error> {
+    let presser_identifier = interaction.user.id.to_string();
+
+    // the payer declines, the requester cancels
+    let title = if presser_identifier == request.payer_identifier {
+        "Payment Request Declined"
+    } else if presser_identifier == request.requester_identifier {
+        "Payment Request Cancelled"
+    } else {
+        let embed = create_error_embed("Error", "This payment request is not yours");
+        return respond_ephemeral(ctx, interaction, embed).await;
+    };
+
+    match tip_context.payment_request_store.remove(request.id).await {
+        Ok(_) => (),
+        Err(SpectreError::PaymentRequestNotFound()) => {
+            let embed = create_error_embed("Error", "This payment request is already closed");
+            return respond_ephemeral(ctx, interaction, embed).await;
+        }
+        Err(e) => return Err(e.into()),
+    }
+
+    let embed = create_payment_request_embed(tip_context, &request).title(title);
+
+    interaction
+        .create_response(
+            ctx,
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .embed(embed)
+                    .components(vec![]),
+            ),
+        )
+        .await?;
+
+    Ok(())
+}
+
+async fn pay_payment_request(
+    ctx: &serenity::Context,
+    tip_context: &Arc<TipContext>,
+    interaction: &ComponentInteraction,
+    request: PaymentRequest,
+) -> Result<(), Error> {
+    // the modal must be the first response, so the wallet is checked before asking the password
+    let tip_wallet = match tip_context.get_opened_owned_wallet(&request.payer_identifier) {
+        Some(tip_wallet) => tip_wallet,
+        None => {
+            let embed = create_error_embed("Error", "Wallet not opened");
+            return respond_ephemeral(ctx, interaction, embed).await;
+        }
+    };
+
+    if tip_wallet.is_watch_only() {
+        let embed = create_error_embed(
+            "Error",
+            "This is a watch-only wallet, it cannot sign transactions",
+        );
+        return respond_ephemeral(ctx, interaction, embed).await;
+    }
+
+    let modal =
+        match execute_component_modal::<PayModal>(ctx, interaction, PAY_MODAL_TIMEOUT).await? {
+            Some(modal) => modal,
+            None => return Ok(()),
+        };
+
+    let fee_priority = match modal
+        .priority
+        .map(|p| FeePriority::from_str(&p))
+        .transpose()
+    {
+        Ok(fee_priority) => fee_priority,
+        Err(e) => {
+            let embed = create_error_embed("Error", &e.to_string());
+            return follow_up_ephemeral(ctx, interaction, embed).await;
+        }
+    };
+
+    // the request may have been declined while the modal was opened
+    if tip_context
+        .payment_request_store
+        .find_by_id(request.id)
+        .await
+        .is_none()
+    {
+        let embed = create_error_embed("Error", "This payment request is already closed");
+        return follow_up_ephemeral(ctx, interaction, embed).await;
+    }
+
+    let network_id = tip_context.network_id();
+    let amount_formatted = sompi_to_spectre_string_with_suffix(request.amount_sompi, &network_id);
+
+    let transfer = BackgroundTransfer {
+        idempotency_key: build_idempotency_key("payment-request", request.id),
+        sender_identifier: request.payer_identifier.clone(),
+        recipient: TransferRecipient::User(request.requester_identifier.clone()),
+        amount_sompi: request.amount_sompi,
+        receiver_pays: false,
+        channel_id: request.channel_id,
+        receipt: format!(
+            "Payment of {} to <@{}> for their request",
+            amount_formatted, request.requester_identifier
+        ),
+    };
+
+    let tx_id = match send_background_transfer(
+        tip_context,
+        &tip_wallet.wallet().account()?,
+        Secret::from(modal.password),
+        fee_priority,
+        &transfer,
+    )
+    .await
+    {
+        Ok(tx_id) => tx_id,
+        Err(SpectreError::OutboxEntryAlreadyExists()) => {
+            let embed = create_error_embed("Error", "This payment request has already been paid");
+            return follow_up_ephemeral(ctx, interaction, embed).await;
+        }
+        Err(e) => {
+            let embed = create_error_embed("Error", &format!("Transaction failed: {}", e));
+            return follow_up_ephemeral(ctx, interaction, embed).await;
+        }
+    };
+
+    // already removed if the requester cancelled meanwhile, the payment went through anyway
+    let _ = tip_context.payment_request_store.remove(request.id).await;
+
+    let explorer_url = get_tx_explorer_url(&tx_id, network_id.network_type());
+
+    let public_embed = create_payment_request_embed(tip_context, &request)
+        .title("Payment Request Paid")
+        .colour(serenity::Colour::DARK_GREEN)
+        .field("Explorer", explorer_url.clone(), false);
+    close_payment_request_message(ctx, &request, public_embed).await;
+
+    tip_context
+        .outbox_store
+        .mark_delivered(&transfer.idempotency_key)
+        .await?;
+
+    let embed = create_success_embed(
+        "Payment Sent",
+        &format!(
+            "You paid {} to <@{}>",
+            amount_formatted, request.requester_identifier
+        ),
+    )
+    .field("Explorer", explorer_url.clone(), false);
+    follow_up_ephemeral(ctx, interaction, embed).await?;
+
+    let requester_embed = create_success_embed(
+        "Payment Request Paid",
+        &format!(
+            "<@{}> paid your request of {}",
+            request.payer_identifier, amount_formatted
+        ),
+    )
+    .field("Explorer", explorer_url, false);
+
+    // requesters may have closed their direct messages, the payment is done anyway
+    if let Ok(requester_id) = request.requester_identifier.parse::<u64>() {
+        let _ = UserId::new(requester_id)
+            .direct_message(ctx, CreateMessage::new().embed(requester_embed))
+            .await;
+    }
+
+    Ok(())
+}
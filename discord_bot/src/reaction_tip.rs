@@ -0,0 +1,189 @@
+use core::{
+    error::Error as SpectreError,
+    tip_context::TipContext,
+    utils::{build_idempotency_key, get_tx_explorer_url, now_unix_secs},
+};
+use poise::serenity_prelude::{self as serenity, CreateMessage, Reaction};
+use spectre_wallet_core::utils::sompi_to_spectre_string_with_suffix;
+use std::sync::Arc;
+
+use crate::{models::Error, transfer::*, utils::*};
+
+/**
+ * tip the author of a message when a configured emoji is added to it,
+ * from the opened wallet of the reactor if reaction tips are enabled
+ */
+pub async fn handle_reaction_add(
+    ctx: &serenity::Context,
+    tip_context: &Arc<TipContext>,
+    reaction: &Reaction,
+) -> Result<(), Error> {
+    let (guild_id, reactor_id) = match (reaction.guild_id, reaction.user_id) {
+        (Some(guild_id), Some(reactor_id)) => (guild_id, reactor_id),
+        _ => return Ok(()),
+    };
+
+    let config = tip_context
+        .guild_config_store
+        .find_by_guild_id(guild_id.get())
+        .await;
+
+    let emoji_key = reaction_emoji_key(&reaction.emoji);
+    let amount_sompi = match config.reaction_amounts_by_emoji.get(&emoji_key) {
+        Some(amount_sompi) => *amount_sompi,
+        None => return Ok(()),
+    };
+
+    let reactor_identifier = reactor_id.to_string();
+
+    // reactions of users who did not enable reaction tips are regular reactions
+    let wallet_secret = match tip_context.get_reaction_tip_secret(&reactor_identifier) {
+        Some(wallet_secret) => wallet_secret,
+        None => return Ok(()),
+    };
+
+    if tip_context
+        .frozen_user_store
+        .is_frozen(&reactor_identifier)
+        .await
+    {
+        return Ok(());
+    }
+
+    let tip_wallet = match tip_context.get_opened_owned_wallet(&reactor_identifier) {
+        Some(tip_wallet) => tip_wallet,
+        None => return Ok(()),
+    };
+
+    let recipient = match reaction.message_author_id {
+        Some(author_id) => author_id.to_user(ctx).await?,
+        None => reaction.message(ctx).await?.author,
+    };
+
+    if recipient.bot || recipient.system || recipient.id == reactor_id {
+        return Ok(());
+    }
+
+    if let Err(reason) = check_guild_tip_amount(tip_context, Some(guild_id), amount_sompi).await {
+        let embed = create_warning_embed("Reaction Tip Skipped", &reason);
+        reactor_id
+            .direct_message(ctx, CreateMessage::new().embed(embed))
+            .await?;
+        return Ok(());
+    }
+
+    let network_id = tip_context.network_id();
+    let amount_formatted = sompi_to_spectre_string_with_suffix(amount_sompi, &network_id);
+
+    let now = now_unix_secs();
+    if !tip_context.reaction_tip_limiter().try_spend(
+        &reactor_identifier,
+        amount_sompi,
+        config.reaction_daily_cap_sompi,
+        now,
+    ) {
+        let embed = create_warning_embed(
+            "Reaction Tip Skipped",
+            &format!(
+                "Tipping {} to <@{}> would exceed your daily reaction tip cap of {}",
+                amount_formatted,
+                recipient.id,
+                sompi_to_spectre_string_with_suffix(config.reaction_daily_cap_sompi, &network_id)
+            ),
+        );
+        reactor_id
+            .direct_message(ctx, CreateMessage::new().embed(embed))
+            .await?;
+        return Ok(());
+    }
+
+    let idempotency_key = build_idempotency_key(
+        &format!("reaction-{}-{}", reactor_id, emoji_key),
+        reaction.message_id.get(),
+    );
+
+    // reaction tip receipts are private, they are re-sent by direct message if the bot stops midway
+    let transfer = BackgroundTransfer {
+        idempotency_key: idempotency_key.clone(),
+        sender_identifier: reactor_identifier.clone(),
+        recipient: TransferRecipient::User(recipient.id.to_string()),
+        amount_sompi,
+        receiver_pays: false,
+        channel_id: reaction.channel_id.get(),
+        receipt: format!(
+            "Reaction tip of {} to <@{}>",
+            amount_formatted, recipient.id
+        ),
+    };
+
+    let result = send_background_transfer(
+        tip_context,
+        &tip_wallet.wallet().account()?,
+        wallet_secret,
+        None,
+        &transfer,
+    )
+    .await;
+
+    let tx_id = match result {
+        Ok(tx_id) => tx_id,
+        // re-delivered event, or the same reaction added again
+        Err(SpectreError::OutboxEntryAlreadyExists()) => {
+            tip_context
+                .reaction_tip_limiter()
+                .refund(&reactor_identifier, amount_sompi, now);
+            return Ok(());
+        }
+        Err(e) => {
+            tip_context
+                .reaction_tip_limiter()
+                .refund(&reactor_identifier, amount_sompi, now);
+
+            let embed = create_error_embed(
+                "Reaction Tip Failed",
+                &format!("Tipping <@{}> failed: {}", recipient.id, e),
+            );
+            reactor_id
+                .direct_message(ctx, CreateMessage::new().embed(embed))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let explorer_url = get_tx_explorer_url(&tx_id, network_id.network_type());
+
+    let reactor_embed = create_success_embed(
+        "Reaction Tip Sent",
+        &format!(
+            "You tipped {} to <@{}> by reacting with {}",
+            amount_formatted, recipient.id, reaction.emoji
+        ),
+    )
+    .field("Explorer", explorer_url.clone(), false);
+
+    reactor_id
+        .direct_message(ctx, CreateMessage::new().embed(reactor_embed))
+        .await?;
+
+    tip_context
+        .outbox_store
+        .mark_delivered(&idempotency_key)
+        .await?;
+
+    let recipient_embed = create_success_embed(
+        "Reaction Tip Received",
+        &format!(
+            "<@{}> tipped you {} by reacting to your message",
+            reactor_id, amount_formatted
+        ),
+    )
+    .field("Explorer", explorer_url, false);
+
+    // recipients may have closed their direct messages, the tip is sent anyway
+    let _ = recipient
+        .id
+        .direct_message(ctx, CreateMessage::new().embed(recipient_embed))
+        .await;
+
+    Ok(())
+}
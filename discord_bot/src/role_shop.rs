@@ -0,0 +1,263 @@
+use core::{
+    error::Error as SpectreError,
+    role_shop::{RolePurchase, ShopRole},
+    tip_context::TipContext,
+    transaction_tracker::{TrackedTransaction, TransactionState},
+    utils::now_unix_secs,
+};
+use poise::serenity_prelude::{CreateEmbed, CreateMessage, GuildId, Http, RoleId, UserId};
+use spectre_wallet_core::utils::sompi_to_spectre_string_with_suffix;
+use std::{sync::Arc, time::Duration};
+use tracing::{error, info};
+
+use crate::utils::*;
+
+// timed roles past their expiry are revoked on the next check
+const ROLE_EXPIRY_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+pub const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+pub fn format_shop_role_duration(duration_secs: Option<u64>) -> String {
+    match duration_secs {
+        Some(duration_secs) => format!("{} days", duration_secs / SECONDS_PER_DAY),
+        None => "permanent".to_string(),
+    }
+}
+
+pub fn format_shop_role(tip_context: &Arc<TipContext>, shop_role: &ShopRole) -> String {
+    format!(
+        "<@&{}>: {}, {}",
+        shop_role.role_id,
+        sompi_to_spectre_string_with_suffix(shop_role.price_sompi, &tip_context.network_id()),
+        format_shop_role_duration(shop_role.duration_secs)
+    )
+}
+
+async fn notify_buyer(http: &Http, purchase: &RolePurchase, embed: CreateEmbed) {
+    // buyers may have closed their direct messages
+    if let Ok(buyer_id) = purchase.buyer_identifier.parse::<u64>() {
+        let _ = UserId::new(buyer_id)
+            .direct_message(http, CreateMessage::new().embed(embed))
+            .await;
+    }
+}
+
+/**
+ * assign the role once the payment to the treasury matures
+ */
+async fn verify_role_purchase(
+    http: &Http,
+    tip_context: &Arc<TipContext>,
+    purchase: &RolePurchase,
+) -> Result<(), SpectreError> {
+    let treasury = tip_context
+        .treasury_store
+        .find_by_guild_id(purchase.guild_id)
+        .await
+        .ok_or(SpectreError::custom("Treasury not found"))?;
+
+    // without the virtual chain, only an output to the treasury confirms the payment:
+    // the acceptance of a transaction paying someone else must not assign the role
+    let tracked_transaction =
+        TrackedTransaction::new(purchase.tx_id.clone(), treasury.receive_address, None);
+
+    let state = tracked_transaction
+        .wait_for_final_state(tip_context.rpc_api())
+        .await?;
+
+    let paid_sompi = match state {
+        TransactionState::Confirmed => tracked_transaction
+            .find_recipient_output(tip_context.rpc_api())
+            .await?
+            .map(|entry| entry.utxo_entry.amount),
+        _ => None,
+    };
+
+    let failure = match paid_sompi {
+        Some(paid_sompi) if paid_sompi >= purchase.price_sompi => None,
+        Some(_) => Some("the amount paid to the treasury is below the price".to_string()),
+        None if state == TransactionState::Confirmed => {
+            Some("no payment to the treasury found".to_string())
+        }
+        None => Some(state.to_string()),
+    };
+
+    if let Some(failure) = failure {
+        let failed = tip_context
+            .role_purchase_store
+            .mark_failed(purchase.id, failure.clone())
+            .await?;
+
+        let embed = create_error_embed(
+            "Role Purchase Failed",
+            &format!(
+                "Your payment for <@&{}> was not confirmed: {}",
+                failed.role_id, failure
+            ),
+        );
+        notify_buyer(http, &failed, embed).await;
+
+        return Ok(());
+    }
+
+    let buyer_id = UserId::new(
+        purchase
+            .buyer_identifier
+            .parse::<u64>()
+            .map_err(|e| SpectreError::custom(e.to_string()))?,
+    );
+
+    if let Err(e) = http
+        .add_member_role(
+            GuildId::new(purchase.guild_id),
+            buyer_id,
+            RoleId::new(purchase.role_id),
+            Some("Role bought in the shop"),
+        )
+        .await
+    {
+        // the payment is done, an admin has to assign the role by hand
+        let failed = tip_context
+            .role_purchase_store
+            .mark_failed(purchase.id, format!("Role not assigned: {}", e))
+            .await?;
+
+        let embed = create_error_embed(
+            "Role Purchase Failed",
+            &format!(
+                "Your payment for <@&{}> is confirmed but the role could not be assigned, please contact an admin with purchase #{}",
+                failed.role_id, failed.id
+            ),
+        );
+        notify_buyer(http, &failed, embed).await;
+
+        return Ok(());
+    }
+
+    let activated = tip_context
+        .role_purchase_store
+        .activate(purchase.id, now_unix_secs())
+        .await?;
+
+    let until = match activated.expires_at {
+        Some(expires_at) => format!("until <t:{}:f>", expires_at),
+        None => "permanently".to_string(),
+    };
+
+    let embed = create_success_embed(
+        "Role Purchased",
+        &format!("You hold <@&{}> {}", activated.role_id, until),
+    );
+    notify_buyer(http, &activated, embed).await;
+
+    Ok(())
+}
+
+/**
+ * follow the payment in the background, the command does not wait for its maturity
+ */
+pub fn spawn_role_purchase_verification(
+    http: Arc<Http>,
+    tip_context: Arc<TipContext>,
+    purchase: RolePurchase,
+) {
+    tokio::spawn(async move {
+        if let Err(e) = verify_role_purchase(&http, &tip_context, &purchase).await {
+            error!(
+                "Error while verifying role purchase #{}: {}",
+                purchase.id, e
+            );
+        }
+    });
+}
+
+async fn revoke_expired_roles(http: &Http, tip_context: &Arc<TipContext>) {
+    for purchase in tip_context
+        .role_purchase_store
+        .find_expired(now_unix_secs())
+        .await
+    {
+        info!("Revoking role of purchase #{}", purchase.id);
+
+        let buyer_id = match purchase.buyer_identifier.parse::<u64>() {
+            Ok(buyer_id) => UserId::new(buyer_id),
+            Err(_) => continue,
+        };
+
+        // members who left the server or lost the role are simply expired
+        let _ = http
+            .remove_member_role(
+                GuildId::new(purchase.guild_id),
+                buyer_id,
+                RoleId::new(purchase.role_id),
+                Some("Role bought in the shop expired"),
+            )
+            .await;
+
+        match tip_context.role_purchase_store.expire(purchase.id).await {
+            Ok(expired) => {
+                let embed = create_warning_embed(
+                    "Role Expired",
+                    &format!(
+                        "Your <@&{}> role expired, buy it again with `/wallet shop buy`",
+                        expired.role_id
+                    ),
+                );
+                notify_buyer(http, &expired, embed).await;
+            }
+            Err(e) => error!("Error while expiring role purchase #{}: {}", purchase.id, e),
+        }
+    }
+}
+
+async fn send_renewal_reminders(http: &Http, tip_context: &Arc<TipContext>) {
+    for purchase in tip_context
+        .role_purchase_store
+        .find_due_reminders(now_unix_secs())
+        .await
+    {
+        let expires_at = purchase.expires_at.unwrap_or_default();
+
+        let embed = create_warning_embed(
+            "Role Expiring Soon",
+            &format!(
+                "Your <@&{}> role expires <t:{}:R>, renew it with `/wallet shop buy` to extend it",
+                purchase.role_id, expires_at
+            ),
+        );
+        notify_buyer(http, &purchase, embed).await;
+
+        if let Err(e) = tip_context
+            .role_purchase_store
+            .mark_reminded(purchase.id)
+            .await
+        {
+            error!(
+                "Error while recording the reminder of role purchase #{}: {}",
+                purchase.id, e
+            );
+        }
+    }
+}
+
+/**
+ * resume the verification of purchases interrupted by a restart, then revoke expired roles
+ * and remind subscribers in the background
+ */
+pub fn start_role_shop(http: Arc<Http>, tip_context: Arc<TipContext>) {
+    tokio::spawn(async move {
+        for purchase in tip_context.role_purchase_store.find_pending().await {
+            spawn_role_purchase_verification(http.clone(), tip_context.clone(), purchase);
+        }
+
+        let mut interval = tokio::time::interval(ROLE_EXPIRY_CHECK_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            revoke_expired_roles(&http, &tip_context).await;
+
+            send_renewal_reminders(&http, &tip_context).await;
+        }
+    });
+}
@@ -0,0 +1,250 @@
+use core::{
+    error::Error as SpectreError,
+    schedule::Schedule,
+    scheduler::{spawn_scheduler, ScheduleEvent},
+    tip_context::TipContext,
+    tip_custodial_wallet::TipCustodialWallet,
+    utils::{build_idempotency_key, get_tx_explorer_url},
+};
+use poise::serenity_prelude::{CreateMessage, Http, UserId};
+use spectre_wallet_core::utils::sompi_to_spectre_string_with_suffix;
+use spectre_wallet_keys::secret::Secret;
+use std::sync::Arc;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+use tracing::error;
+
+use crate::{transfer::*, utils::*};
+
+/**
+ * run the transfer from the custodial wallet of the schedule, which is stopped afterwards
+ */
+async fn send_from_allowance(
+    tip_context: &Arc<TipContext>,
+    schedule: &Schedule,
+    transfer: &BackgroundTransfer,
+) -> Result<String, SpectreError> {
+    let secret = Secret::from(schedule.secret.clone());
+
+    let allowance_wallet =
+        TipCustodialWallet::open(tip_context.clone(), &secret, &schedule.wallet_identifier).await?;
+
+    let result = send_background_transfer(
+        tip_context,
+        &allowance_wallet.wallet().account()?,
+        secret,
+        None,
+        transfer,
+    )
+    .await;
+
+    if let Err(e) = allowance_wallet.wallet().stop().await {
+        error!("Error while stopping allowance wallet: {}", e);
+    }
+
+    match result {
+        Ok(tx_id) => Ok(tx_id),
+        Err(SpectreError::OutboxEntryAlreadyExists()) => {
+            find_submitted_transfer(tip_context, &transfer.idempotency_key)
+                .await
+                .ok_or(SpectreError::OutboxEntryAlreadyExists())
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/**
+ * pay one run of the schedule, each run is keyed by its time so it is paid once
+ */
+pub async fn pay_schedule(
+    tip_context: Arc<TipContext>,
+    schedule: Schedule,
+) -> Result<String, SpectreError> {
+    // a failed run pauses the schedule, the owner is notified
+    if tip_context
+        .frozen_user_store
+        .is_frozen(&schedule.owner_identifier)
+        .await
+    {
+        return Err(SpectreError::UserFrozen());
+    }
+
+    let transfer = BackgroundTransfer {
+        idempotency_key: build_idempotency_key(
+            &format!("schedule-{}", schedule.id),
+            schedule.next_run_at,
+        ),
+        sender_identifier: schedule.owner_identifier.clone(),
+        recipient: TransferRecipient::User(schedule.recipient_identifier.clone()),
+        amount_sompi: schedule.amount_sompi,
+        receiver_pays: false,
+        // runs are not triggered from a channel, receipts go by direct message
+        channel_id: 0,
+        receipt: format!(
+            "Scheduled tip #{} of {} to <@{}>",
+            schedule.id,
+            sompi_to_spectre_string_with_suffix(schedule.amount_sompi, &tip_context.network_id()),
+            schedule.recipient_identifier
+        ),
+    };
+
+    let tx_id = send_from_allowance(&tip_context, &schedule, &transfer).await?;
+
+    tip_context
+        .outbox_store
+        .mark_delivered(&transfer.idempotency_key)
+        .await?;
+
+    Ok(tx_id)
+}
+
+/**
+ * send what is left of the allowance back to the owner, none if it was empty
+ */
+pub async fn refund_schedule_allowance(
+    tip_context: &Arc<TipContext>,
+    schedule: &Schedule,
+) -> Result<Option<String>, SpectreError> {
+    let secret = Secret::from(schedule.secret.clone());
+
+    let allowance_wallet =
+        TipCustodialWallet::open(tip_context.clone(), &secret, &schedule.wallet_identifier).await?;
+
+    let remaining_sompi = allowance_wallet
+        .wallet()
+        .account()?
+        .balance()
+        .map(|balance| balance.mature)
+        .unwrap_or(0);
+
+    if let Err(e) = allowance_wallet.wallet().stop().await {
+        error!("Error while stopping allowance wallet: {}", e);
+    }
+
+    if remaining_sompi == 0 {
+        return Ok(None);
+    }
+
+    let transfer = BackgroundTransfer {
+        idempotency_key: build_idempotency_key("schedule-refund", schedule.id),
+        sender_identifier: schedule.owner_identifier.clone(),
+        recipient: TransferRecipient::User(schedule.owner_identifier.clone()),
+        amount_sompi: remaining_sompi,
+        receiver_pays: true,
+        channel_id: 0,
+        receipt: format!(
+            "Refund of the allowance of scheduled tip #{}: {}",
+            schedule.id,
+            sompi_to_spectre_string_with_suffix(remaining_sompi, &tip_context.network_id())
+        ),
+    };
+
+    let tx_id = send_from_allowance(tip_context, schedule, &transfer).await?;
+
+    tip_context
+        .outbox_store
+        .mark_delivered(&transfer.idempotency_key)
+        .await?;
+
+    Ok(Some(tx_id))
+}
+
+async fn direct_message(http: &Arc<Http>, identifier: &str, message: CreateMessage) {
+    if let Ok(user_id) = identifier.parse::<u64>() {
+        // users may have closed their direct messages
+        let _ = UserId::new(user_id).direct_message(http, message).await;
+    }
+}
+
+/**
+ * tell owners and recipients about scheduled payments, and owners about failures
+ */
+async fn notify_schedule_events(
+    http: Arc<Http>,
+    tip_context: Arc<TipContext>,
+    mut events: UnboundedReceiver<ScheduleEvent>,
+) {
+    let network_id = tip_context.network_id();
+
+    while let Some(event) = events.recv().await {
+        match event {
+            ScheduleEvent::Paid { schedule, tx_id } => {
+                let amount_formatted =
+                    sompi_to_spectre_string_with_suffix(schedule.amount_sompi, &network_id);
+                let explorer_url = get_tx_explorer_url(&tx_id, network_id.network_type());
+
+                let owner_embed = create_success_embed(
+                    "Scheduled Tip Sent",
+                    &format!(
+                        "Scheduled tip #{} sent {} to <@{}>, next one <t:{}:R>",
+                        schedule.id,
+                        amount_formatted,
+                        schedule.recipient_identifier,
+                        schedule.next_run_at
+                    ),
+                )
+                .field("Explorer", explorer_url.clone(), false);
+                direct_message(
+                    &http,
+                    &schedule.owner_identifier,
+                    CreateMessage::new().embed(owner_embed),
+                )
+                .await;
+
+                let recipient_embed = create_success_embed(
+                    "Scheduled Tip Received",
+                    &format!(
+                        "<@{}> sent you {}",
+                        schedule.owner_identifier, amount_formatted
+                    ),
+                )
+                .field("Explorer", explorer_url, false);
+                direct_message(
+                    &http,
+                    &schedule.recipient_identifier,
+                    CreateMessage::new().embed(recipient_embed),
+                )
+                .await;
+            }
+            ScheduleEvent::Failed { schedule, error } => {
+                let embed = create_error_embed(
+                    "Scheduled Tip Failed",
+                    &format!(
+                        "Scheduled tip #{} to <@{}> failed and has been paused: {}",
+                        schedule.id, schedule.recipient_identifier, error
+                    ),
+                )
+                .field(
+                    "Top Up",
+                    format!(
+                        "Withdraw to {} to refill the allowance, then use `/wallet schedule resume {}`",
+                        schedule.receive_address.address_to_string(),
+                        schedule.id
+                    ),
+                    false,
+                );
+                direct_message(
+                    &http,
+                    &schedule.owner_identifier,
+                    CreateMessage::new().embed(embed),
+                )
+                .await;
+            }
+        }
+    }
+}
+
+/**
+ * start the core scheduler, paying from allowances and notifying by direct message
+ */
+pub fn start_schedules(http: Arc<Http>, tip_context: Arc<TipContext>) {
+    let (sender, receiver) = unbounded_channel();
+
+    let pay_tip_context = tip_context.clone();
+    spawn_scheduler(
+        tip_context.clone(),
+        move |schedule| pay_schedule(pay_tip_context.clone(), schedule),
+        sender,
+    );
+
+    tokio::spawn(notify_schedule_events(http, tip_context, receiver));
+}
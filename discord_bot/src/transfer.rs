@@ -0,0 +1,151 @@
+use core::{
+    error::Error as SpectreError,
+    fee_policy::FeePriority,
+    outbox::{OutboxEntry, OutboxEntryStatus},
+    tip_context::TipContext,
+    utils::{estimate_fees, now_unix_secs},
+};
+use spectre_wallet_core::{
+    prelude::{Account, Address},
+    tx::{Fees, PaymentOutputs},
+};
+use spectre_wallet_keys::secret::Secret;
+use std::sync::Arc;
+use workflow_core::abortable::Abortable;
+
+use crate::{outbox::send_with_outbox, utils::*};
+
+pub enum TransferRecipient {
+    // owned wallet of the user, or a transition wallet created if needed
+    User(String),
+    Address(Address),
+}
+
+/**
+ * transfer without preview nor public receipt, for reactions, buttons and bot-controlled wallets
+ */
+pub struct BackgroundTransfer {
+    pub idempotency_key: String,
+    pub sender_identifier: String,
+    pub recipient: TransferRecipient,
+    pub amount_sompi: u64,
+    // fees are deducted from the amount, used to empty bot-controlled wallets
+    pub receiver_pays: bool,
+    // channel the transfer was triggered from
+    pub channel_id: u64,
+    // re-sent to the sender by direct message if the bot stops midway
+    pub receipt: String,
+}
+
+/**
+ * send the transfer through the outbox, returns the id of the last transaction, the one paying
+ * the recipient (the previous ones compound utxos),
+ * `OutboxEntryAlreadyExists` means the transfer has already been processed
+ */
+pub async fn send_background_transfer(
+    tip_context: &Arc<TipContext>,
+    account: &Arc<dyn Account>,
+    wallet_secret: Secret,
+    fee_priority: Option<FeePriority>,
+    transfer: &BackgroundTransfer,
+) -> Result<String, SpectreError> {
+    let outbox_entry = OutboxEntry::new(
+        transfer.idempotency_key.clone(),
+        transfer.sender_identifier.clone(),
+        transfer.channel_id,
+        false,
+        transfer.receipt.clone(),
+        now_unix_secs(),
+    );
+
+    tip_context.outbox_store.add(&outbox_entry).await?;
+
+    let send_result = async {
+        let address = match &transfer.recipient {
+            TransferRecipient::User(recipient_identifier) => {
+                resolve_recipient_address(
+                    tip_context,
+                    &transfer.sender_identifier,
+                    recipient_identifier,
+                )
+                .await?
+                .0
+            }
+            TransferRecipient::Address(address) => address.clone(),
+        };
+
+        let outputs = PaymentOutputs::from((address, transfer.amount_sompi));
+
+        let base_fees_summary = estimate_fees(account, outputs.clone()).await?;
+        let priority_fee = tip_context
+            .fee_policy()
+            .resolve_priority_fee(
+                tip_context.rpc_api(),
+                fee_priority,
+                base_fees_summary.aggregated_fees,
+            )
+            .await?;
+
+        let fees = match transfer.receiver_pays {
+            true => Fees::ReceiverPays(priority_fee),
+            false => Fees::SenderPays(priority_fee),
+        };
+
+        let abortable = Abortable::default();
+
+        let (_, tx_ids) = send_with_outbox(
+            tip_context,
+            account,
+            outputs.into(),
+            fees,
+            wallet_secret,
+            &abortable,
+            &transfer.idempotency_key,
+        )
+        .await?;
+
+        Ok::<_, SpectreError>(tx_ids)
+    }
+    .await;
+
+    let tx_ids = match send_result {
+        Ok(tx_ids) => tx_ids,
+        Err(e) => {
+            forget_unsubmitted_outbox_entry(tip_context, &transfer.idempotency_key)
+                .await
+                .map_err(|e| SpectreError::custom(e.to_string()))?;
+            return Err(e);
+        }
+    };
+
+    Ok(tx_ids[tx_ids.len() - 1].clone())
+}
+
+/**
+ * a failed transfer whose outbox entry has been forgotten left nothing on the node
+ */
+pub async fn is_transfer_forgotten(tip_context: &Arc<TipContext>, idempotency_key: &str) -> bool {
+    tip_context
+        .outbox_store
+        .find_by_idempotency_key(idempotency_key)
+        .await
+        .is_none()
+}
+
+/**
+ * last transaction id of a transfer already broadcasted under this key,
+ * lets an interrupted operation be recorded without paying twice
+ */
+pub async fn find_submitted_transfer(
+    tip_context: &Arc<TipContext>,
+    idempotency_key: &str,
+) -> Option<String> {
+    match tip_context
+        .outbox_store
+        .find_by_idempotency_key(idempotency_key)
+        .await
+    {
+        Some(entry) if entry.status != OutboxEntryStatus::Pending => entry.tx_ids.last().cloned(),
+        _ => None,
+    }
+}
@@ -0,0 +1,565 @@
+use core::{
+    bounty::BountyStatus,
+    error::Error as SpectreError,
+    tip_context::TipContext,
+    tip_owned_wallet::TipOwnedWallet,
+    treasury::{Treasury, TreasuryPayout, TreasuryPayoutStatus},
+    utils::{build_idempotency_key, get_tx_explorer_url, now_unix_secs},
+};
+use poise::serenity_prelude::{
+    self as serenity, ButtonStyle, CacheHttp, ChannelId, ComponentInteraction, CreateActionRow,
+    CreateButton, CreateEmbed, CreateInteractionResponse, CreateMessage, EditMessage, GuildId,
+    MessageId, RoleId, UserId,
+};
+use spectre_wallet_core::utils::sompi_to_spectre_string_with_suffix;
+use spectre_wallet_keys::secret::Secret;
+use std::sync::Arc;
+
+use crate::{bounty::*, models::Error, transfer::*, utils::*};
+
+// custom id of the buttons: treasury-payout-<approve|reject>-<payout id>
+pub const TREASURY_PAYOUT_BUTTON_PREFIX: &str = "treasury-payout-";
+
+/**
+ * the treasury wallet stays opened once used, so its balance is kept in sync
+ */
+pub async fn open_treasury_wallet(
+    tip_context: &Arc<TipContext>,
+    treasury: &Treasury,
+) -> Result<TipOwnedWallet, SpectreError> {
+    if let Some(treasury_wallet) = tip_context.get_opened_owned_wallet(&treasury.wallet_identifier)
+    {
+        return Ok(treasury_wallet);
+    }
+
+    let secret = tip_context.treasury_wallet_secret(&treasury.wallet_identifier)?;
+
+    // switch treasuries created with a stored secret to the derived one
+    if let Some(stored_secret) = &treasury.secret {
+        let stored_secret = Secret::from(stored_secret.clone());
+
+        // a concurrent open may have switched it already
+        if let Ok(treasury_wallet) = TipOwnedWallet::open(
+            tip_context.clone(),
+            &stored_secret,
+            &treasury.wallet_identifier,
+        )
+        .await
+        {
+            treasury_wallet
+                .change_secret(&stored_secret, &secret)
+                .await?;
+
+            tip_context
+                .treasury_store
+                .forget_secret(treasury.guild_id)
+                .await?;
+
+            return Ok(treasury_wallet);
+        }
+    }
+
+    TipOwnedWallet::open(tip_context.clone(), &secret, &treasury.wallet_identifier).await
+}
+
+/**
+ * whether the member holds `role_id`, members are fetched since role lists are not cached
+ */
+pub async fn member_has_role(
+    cache_http: impl CacheHttp,
+    guild_id: u64,
+    user_id: UserId,
+    role_id: u64,
+) -> bool {
+    GuildId::new(guild_id)
+        .member(cache_http, user_id)
+        .await
+        .is_ok_and(|member| member.roles.contains(&RoleId::new(role_id)))
+}
+
+pub fn create_treasury_payout_buttons(payout_id: u64) -> Vec<CreateActionRow> {
+    vec![CreateActionRow::Buttons(vec![
+        CreateButton::new(format!(
+            "{}approve-{}",
+            TREASURY_PAYOUT_BUTTON_PREFIX, payout_id
+        ))
+        .label("Approve")
+        .style(ButtonStyle::Success),
+        CreateButton::new(format!(
+            "{}reject-{}",
+            TREASURY_PAYOUT_BUTTON_PREFIX, payout_id
+        ))
+        .label("Reject")
+        .style(ButtonStyle::Danger),
+    ])]
+}
+
+/**
+ * payouts go to a member, or fund a bounty
+ */
+pub fn format_treasury_payout_recipient(payout: &TreasuryPayout) -> String {
+    match payout.bounty_id {
+        Some(bounty_id) => format!("bounty #{}", bounty_id),
+        None => format!("<@{}>", payout.recipient_identifier),
+    }
+}
+
+pub fn create_treasury_payout_embed(
+    tip_context: &Arc<TipContext>,
+    payout: &TreasuryPayout,
+    threshold: u32,
+) -> CreateEmbed {
+    let approvers = payout
+        .approvals
+        .iter()
+        .map(|approval| format!("<@{}>", approval.approver_identifier))
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    let mut embed = create_warning_embed(
+        &format!("Treasury Payout #{}", payout.id),
+        &format!(
+            "<@{}> proposes to pay {} to {}",
+            payout.proposer_identifier,
+            sompi_to_spectre_string_with_suffix(payout.amount_sompi, &tip_context.network_id()),
+            format_treasury_payout_recipient(payout)
+        ),
+    )
+    .field("Reason", &payout.reason, false)
+    .field(
+        "Approvals",
+        format!("{}/{} {}", payout.approvals.len(), threshold, approvers),
+        false,
+    )
+    .field("Status", payout.status.to_string(), true);
+
+    match payout.status {
+        TreasuryPayoutStatus::Proposed => (),
+        TreasuryPayoutStatus::Executing => {
+            embed = embed.colour(serenity::Colour::ORANGE);
+        }
+        TreasuryPayoutStatus::Executed => {
+            embed = embed.colour(serenity::Colour::DARK_GREEN);
+        }
+        TreasuryPayoutStatus::Rejected | TreasuryPayoutStatus::Failed => {
+            embed = embed.colour(serenity::Colour::DARK_RED);
+        }
+    }
+
+    if let Some(rejected_by) = &payout.rejected_by {
+        embed = embed.field("Rejected By", format!("<@{}>", rejected_by), true);
+    }
+
+    if let Some(error) = &payout.error {
+        embed = embed.field("Error", error, false);
+    }
+
+    if let Some(tx_id) = &payout.tx_id {
+        embed = embed.field(
+            "Explorer",
+            get_tx_explorer_url(tx_id, tip_context.network_id().network_type()),
+            false,
+        );
+    }
+
+    embed
+}
+
+/**
+ * one line per approval and settlement, oldest first
+ */
+pub fn format_treasury_payout_trail(
+    tip_context: &Arc<TipContext>,
+    payout: &TreasuryPayout,
+) -> Vec<String> {
+    let mut lines = vec![format!(
+        "<t:{}:f> proposed by <@{}>",
+        payout.created_at, payout.proposer_identifier
+    )];
+
+    for approval in &payout.approvals {
+        lines.push(format!(
+            "<t:{}:f> approved by <@{}>",
+            approval.at, approval.approver_identifier
+        ));
+    }
+
+    if let Some(settled_at) = payout.settled_at {
+        let outcome = match (&payout.status, &payout.rejected_by, &payout.tx_id) {
+            (TreasuryPayoutStatus::Rejected, Some(rejected_by), _) => {
+                format!("rejected by <@{}>", rejected_by)
+            }
+            (TreasuryPayoutStatus::Executed, _, Some(tx_id)) => format!(
+                "executed ({})",
+                get_tx_explorer_url(tx_id, tip_context.network_id().network_type())
+            ),
+            (status, _, _) => status.to_string(),
+        };
+
+        lines.push(format!("<t:{}:f> {}", settled_at, outcome));
+    }
+
+    lines
+}
+
+/**
+ * refresh the proposal message, buttons are removed once the payout is settled
+ */
+async fn update_treasury_payout_message(
+    cache_http: impl CacheHttp,
+    tip_context: &Arc<TipContext>,
+    payout: &TreasuryPayout,
+    threshold: u32,
+) {
+    let components = match payout.status {
+        TreasuryPayoutStatus::Proposed => create_treasury_payout_buttons(payout.id),
+        _ => vec![],
+    };
+
+    let _ = ChannelId::new(payout.channel_id)
+        .edit_message(
+            cache_http,
+            MessageId::new(payout.message_id),
+            EditMessage::new()
+                .embed(create_treasury_payout_embed(tip_context, payout, threshold))
+                .components(components),
+        )
+        .await;
+}
+
+/**
+ * the bounty a payout funds is cancelled along with it
+ */
+async fn cancel_treasury_payout_bounty(
+    cache_http: impl CacheHttp,
+    tip_context: &Arc<TipContext>,
+    payout: &TreasuryPayout,
+) -> Result<(), SpectreError> {
+    let bounty_id = match payout.bounty_id {
+        Some(bounty_id) => bounty_id,
+        None => return Ok(()),
+    };
+
+    match tip_context
+        .bounty_store
+        .cancel(bounty_id, now_unix_secs())
+        .await
+    {
+        Ok(cancelled) => {
+            announce_bounty(
+                cache_http,
+                &cancelled,
+                create_bounty_embed(tip_context, &cancelled),
+            )
+            .await
+        }
+        // already expired
+        Err(SpectreError::BountyTransitionNotAllowed()) => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+async fn fail_treasury_payout(
+    cache_http: impl CacheHttp,
+    tip_context: &Arc<TipContext>,
+    payout: &TreasuryPayout,
+    error: &str,
+) -> Result<TreasuryPayout, SpectreError> {
+    let failed = tip_context
+        .treasury_payout_store
+        .mark_failed(payout.id, error.to_string(), now_unix_secs())
+        .await?;
+
+    cancel_treasury_payout_bounty(cache_http, tip_context, &failed).await?;
+
+    Ok(failed)
+}
+
+/**
+ * sign the approved payout with the treasury wallet, the approver who reached the threshold
+ * is the sender of record
+ */
+pub async fn execute_treasury_payout(
+    cache_http: impl CacheHttp + Copy,
+    tip_context: &Arc<TipContext>,
+    treasury: &Treasury,
+    payout: &TreasuryPayout,
+    executor_identifier: &str,
+) -> Result<TreasuryPayout, SpectreError> {
+    let network_id = tip_context.network_id();
+
+    let recipient = match payout.bounty_id {
+        Some(bounty_id) => match tip_context.bounty_store.find_by_id(bounty_id).await {
+            Some(bounty) if bounty.status == BountyStatus::Pending => {
+                TransferRecipient::Address(bounty.receive_address)
+            }
+            // the bounty expired while approvals were gathered
+            _ => {
+                return fail_treasury_payout(
+                    cache_http,
+                    tip_context,
+                    payout,
+                    "Bounty is no longer awaiting funds",
+                )
+                .await
+            }
+        },
+        None => {
+            // the role may have been removed while approvals were gathered
+            let is_payee = match payout.recipient_identifier.parse::<u64>() {
+                Ok(recipient_id) => {
+                    member_has_role(
+                        cache_http,
+                        treasury.guild_id,
+                        UserId::new(recipient_id),
+                        treasury.payee_role_id,
+                    )
+                    .await
+                }
+                Err(_) => false,
+            };
+
+            if !is_payee {
+                return fail_treasury_payout(
+                    cache_http,
+                    tip_context,
+                    payout,
+                    "Recipient no longer holds the payee role",
+                )
+                .await;
+            }
+
+            TransferRecipient::User(payout.recipient_identifier.clone())
+        }
+    };
+
+    let transfer = BackgroundTransfer {
+        idempotency_key: build_idempotency_key("treasury-payout", payout.id),
+        sender_identifier: executor_identifier.to_string(),
+        recipient,
+        amount_sompi: payout.amount_sompi,
+        receiver_pays: false,
+        channel_id: payout.channel_id,
+        receipt: format!(
+            "Treasury payout #{} of {} to {}",
+            payout.id,
+            sompi_to_spectre_string_with_suffix(payout.amount_sompi, &network_id),
+            format_treasury_payout_recipient(payout)
+        ),
+    };
+
+    let send_result = async {
+        let treasury_wallet = open_treasury_wallet(tip_context, treasury).await?;
+
+        send_background_transfer(
+            tip_context,
+            &treasury_wallet.wallet().account()?,
+            tip_context.treasury_wallet_secret(&treasury.wallet_identifier)?,
+            None,
+            &transfer,
+        )
+        .await
+    }
+    .await;
+
+    let tx_id = match send_result {
+        Ok(tx_id) => tx_id,
+        // executed by a concurrent approval
+        Err(SpectreError::OutboxEntryAlreadyExists()) => {
+            return Err(SpectreError::TreasuryPayoutNotProposed())
+        }
+        Err(e) => {
+            return fail_treasury_payout(cache_http, tip_context, payout, &e.to_string()).await
+        }
+    };
+
+    let executed = tip_context
+        .treasury_payout_store
+        .mark_executed(payout.id, tx_id.clone(), now_unix_secs())
+        .await?;
+
+    tip_context
+        .outbox_store
+        .mark_delivered(&transfer.idempotency_key)
+        .await?;
+
+    if let Some(bounty_id) = payout.bounty_id {
+        let funded = tip_context.bounty_store.fund(bounty_id, tx_id).await?;
+
+        announce_bounty(
+            cache_http,
+            &funded,
+            create_bounty_embed(tip_context, &funded),
+        )
+        .await?;
+
+        return Ok(executed);
+    }
+
+    let recipient_embed = create_success_embed(
+        "Treasury Payout Received",
+        &format!(
+            "You received {} from the server treasury: {}",
+            sompi_to_spectre_string_with_suffix(payout.amount_sompi, &network_id),
+            payout.reason
+        ),
+    )
+    .field(
+        "Explorer",
+        get_tx_explorer_url(&tx_id, network_id.network_type()),
+        false,
+    );
+
+    // recipients may have closed their direct messages, the payout is done anyway
+    if let Ok(recipient_id) = payout.recipient_identifier.parse::<u64>() {
+        let _ = UserId::new(recipient_id)
+            .direct_message(cache_http, CreateMessage::new().embed(recipient_embed))
+            .await;
+    }
+
+    Ok(executed)
+}
+
+/**
+ * Approve and Reject buttons of payout proposals, restricted to the approver role of the treasury
+ */
+pub async fn handle_treasury_payout_interaction(
+    ctx: &serenity::Context,
+    tip_context: &Arc<TipContext>,
+    interaction: &ComponentInteraction,
+) -> Result<(), Error> {
+    let (action, payout_id) = match interaction
+        .data
+        .custom_id
+        .strip_prefix(TREASURY_PAYOUT_BUTTON_PREFIX)
+        .and_then(|rest| rest.split_once('-'))
+        .and_then(|(action, id)| id.parse::<u64>().ok().map(|id| (action, id)))
+    {
+        Some(parsed) => parsed,
+        None => return Ok(()),
+    };
+
+    let payout = match tip_context
+        .treasury_payout_store
+        .find_by_id(payout_id)
+        .await
+    {
+        Some(payout) if payout.status == TreasuryPayoutStatus::Proposed => payout,
+        _ => {
+            let embed = create_error_embed("Error", "This payout is already settled");
+            return respond_ephemeral(ctx, interaction, embed).await;
+        }
+    };
+
+    let treasury = match tip_context
+        .treasury_store
+        .find_by_guild_id(payout.guild_id)
+        .await
+    {
+        Some(treasury) => treasury,
+        None => {
+            let embed = create_error_embed("Error", "Treasury not found");
+            return respond_ephemeral(ctx, interaction, embed).await;
+        }
+    };
+
+    let is_approver = interaction.guild_id.map(|guild_id| guild_id.get()) == Some(payout.guild_id)
+        && interaction.member.as_ref().is_some_and(|member| {
+            member
+                .roles
+                .contains(&RoleId::new(treasury.approver_role_id))
+        });
+
+    if !is_approver {
+        let embed = create_error_embed(
+            "Error",
+            &format!(
+                "Only <@&{}> can approve or reject payouts",
+                treasury.approver_role_id
+            ),
+        );
+        return respond_ephemeral(ctx, interaction, embed).await;
+    }
+
+    // approving may sign the payout, rejecting stays possible while frozen
+    if action == "approve"
+        && tip_context
+            .frozen_user_store
+            .is_frozen(&interaction.user.id.to_string())
+            .await
+    {
+        let embed = create_error_embed(
+            "Error",
+            "Spending through the bot is frozen for your account, please contact the operators",
+        );
+        return respond_ephemeral(ctx, interaction, embed).await;
+    }
+
+    // signing may take longer than the response deadline
+    interaction
+        .create_response(ctx, CreateInteractionResponse::Acknowledge)
+        .await?;
+
+    let approver_identifier = interaction.user.id.to_string();
+
+    let updated = match action {
+        "approve" => {
+            tip_context
+                .treasury_payout_store
+                .approve(
+                    payout.id,
+                    &approver_identifier,
+                    now_unix_secs(),
+                    treasury.threshold,
+                )
+                .await
+        }
+        "reject" => {
+            tip_context
+                .treasury_payout_store
+                .reject(payout.id, &approver_identifier, now_unix_secs())
+                .await
+        }
+        _ => return Ok(()),
+    };
+
+    let updated = match updated {
+        Ok(updated) => updated,
+        Err(SpectreError::TreasuryPayoutAlreadyApproved()) => {
+            let embed = create_error_embed("Error", "You already approved this payout");
+            return follow_up_ephemeral(ctx, interaction, embed).await;
+        }
+        Err(SpectreError::TreasuryPayoutNotProposed()) => {
+            let embed = create_error_embed("Error", "This payout is already settled");
+            return follow_up_ephemeral(ctx, interaction, embed).await;
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    if updated.status == TreasuryPayoutStatus::Rejected {
+        cancel_treasury_payout_bounty(ctx, tip_context, &updated).await?;
+    }
+
+    let updated = match updated.status == TreasuryPayoutStatus::Executing {
+        true => {
+            match execute_treasury_payout(
+                ctx,
+                tip_context,
+                &treasury,
+                &updated,
+                &approver_identifier,
+            )
+            .await
+            {
+                Ok(executed) => executed,
+                Err(SpectreError::TreasuryPayoutNotProposed()) => return Ok(()),
+                Err(e) => return Err(e.into()),
+            }
+        }
+        false => updated,
+    };
+
+    update_treasury_payout_message(ctx, tip_context, &updated, treasury.threshold).await;
+
+    Ok(())
+}
@@ -1,9 +1,30 @@
+use core::{
+    audit_log::AuditLogEntry, error::Error as SpectreError, outbox::OutboxEntryStatus,
+    tip_context::TipContext, tip_transition_wallet::TipTransitionWallet, utils::now_unix_secs,
+};
+use std::{io::Cursor, sync::Arc, time::Duration};
+
+use image::{ImageFormat, Luma};
 use poise::{
-    serenity_prelude::{Colour, CreateEmbed},
-    CreateReply,
+    serenity_prelude::{
+        self as serenity, ButtonStyle, Colour, ComponentInteraction, ComponentInteractionCollector,
+        CreateActionRow, CreateAttachment, CreateButton, CreateEmbed, CreateEmbedFooter,
+        CreateInteractionResponse, CreateInteractionResponseFollowup,
+        CreateInteractionResponseMessage, GuildId, ModalInteractionCollector, ReactionType, RoleId,
+    },
+    CreateReply, Modal,
 };
+use qrcode::QrCode;
+use spectre_wallet_core::{
+    prelude::Address, storage::TransactionRecord, utils::sompi_to_spectre_string_with_suffix,
+};
+
+use tracing::info;
 
-use crate::models::{Context, Error};
+use crate::{
+    models::{Context, Error},
+    outbox::find_transaction_on_node,
+};
 
 // embed creation
 pub fn create_embed(title: &str, description: &str, colour: Colour) -> CreateEmbed {
@@ -25,6 +46,54 @@ pub fn create_warning_embed(title: &str, description: &str) -> CreateEmbed {
     create_embed(title, description, Colour::ORANGE)
 }
 
+// embed field values are limited to 1024 characters
+const EMBED_FIELD_MAX_LENGTH: usize = 1000;
+
+/**
+ * one line per item, truncated to fit in an embed field
+ */
+pub fn format_field_lines(lines: Vec<String>) -> String {
+    let mut formatted = String::new();
+
+    for (index, line) in lines.iter().enumerate() {
+        let remaining = format!("\n... and {} more", lines.len() - index);
+
+        if formatted.len() + line.len() + remaining.len() + 1 > EMBED_FIELD_MAX_LENGTH {
+            formatted.push_str(remaining.trim_start());
+            break;
+        }
+
+        formatted.push_str(line);
+        formatted.push('\n');
+    }
+
+    formatted.trim_end().to_string()
+}
+
+// message contents are limited to 2000 characters
+const MESSAGE_CONTENT_MAX_LENGTH: usize = 1900;
+
+/**
+ * mentions separated by spaces, truncated to fit in a message content
+ */
+pub fn format_mentions(mentions: Vec<String>) -> String {
+    let mut formatted = String::new();
+
+    for (index, mention) in mentions.iter().enumerate() {
+        let remaining = format!("and {} more", mentions.len() - index);
+
+        if formatted.len() + mention.len() + remaining.len() + 1 > MESSAGE_CONTENT_MAX_LENGTH {
+            formatted.push_str(&remaining);
+            break;
+        }
+
+        formatted.push_str(mention);
+        formatted.push(' ');
+    }
+
+    formatted.trim_end().to_string()
+}
+
 pub async fn send_reply(
     ctx: Context<'_>,
     embed: CreateEmbed,
@@ -33,9 +102,491 @@ pub async fn send_reply(
     ctx.send(CreateReply {
         reply: false,
         embeds: vec![embed],
-        ephemeral: Some(ephemeral),
+        ephemeral: Some(ephemeral || is_ephemeral_by_default(ctx).await),
+        ..Default::default()
+    })
+    .await?;
+    Ok(())
+}
+
+pub async fn send_reply_with_attachment(
+    ctx: Context<'_>,
+    embed: CreateEmbed,
+    attachment: CreateAttachment,
+    ephemeral: bool,
+) -> Result<(), Error> {
+    ctx.send(CreateReply {
+        reply: false,
+        embeds: vec![embed],
+        attachments: vec![attachment],
+        ephemeral: Some(ephemeral || is_ephemeral_by_default(ctx).await),
         ..Default::default()
     })
     .await?;
     Ok(())
 }
+
+// operators
+
+/**
+ * check of the spending commands, frozen users are told why the command is refused
+ */
+pub async fn check_not_frozen(ctx: Context<'_>) -> Result<bool, Error> {
+    if !ctx
+        .data()
+        .frozen_user_store
+        .is_frozen(&ctx.author().id.to_string())
+        .await
+    {
+        return Ok(true);
+    }
+
+    let embed = create_error_embed(
+        "Error",
+        "Spending through the bot is frozen for your account, please contact the operators",
+    );
+    send_reply(ctx, embed, true).await?;
+
+    Ok(false)
+}
+
+/**
+ * record an action of the author in the audit log
+ */
+pub async fn record_audit_log(
+    ctx: Context<'_>,
+    action: &str,
+    target_identifier: Option<String>,
+    details: String,
+) -> Result<(), Error> {
+    let entry = ctx
+        .data()
+        .audit_log_store
+        .add(AuditLogEntry {
+            id: 0,
+            actor_identifier: ctx.author().id.to_string(),
+            action: action.to_string(),
+            target_identifier,
+            details,
+            at: now_unix_secs(),
+        })
+        .await?;
+
+    info!(
+        "Audit log #{}: {} by {} on {:?}: {}",
+        entry.id, entry.action, entry.actor_identifier, entry.target_identifier, entry.details
+    );
+
+    Ok(())
+}
+
+// guild config
+
+/**
+ * whether the guild wants replies to be ephemeral even when they could be public
+ */
+async fn is_ephemeral_by_default(ctx: Context<'_>) -> bool {
+    match ctx.guild_id() {
+        Some(guild_id) => {
+            ctx.data()
+                .guild_config_store
+                .find_by_guild_id(guild_id.get())
+                .await
+                .ephemeral_replies
+        }
+        None => false,
+    }
+}
+
+/**
+ * whether the author has the Manage Server permission or holds the admin role of the guild
+ */
+pub async fn is_guild_admin(ctx: Context<'_>) -> bool {
+    let (guild_id, member) = match (ctx.guild_id(), ctx.interaction.member.as_ref()) {
+        (Some(guild_id), Some(member)) => (guild_id, member),
+        _ => return false,
+    };
+
+    if member
+        .permissions
+        .is_some_and(|permissions| permissions.manage_guild())
+    {
+        return true;
+    }
+
+    ctx.data()
+        .guild_config_store
+        .find_by_guild_id(guild_id.get())
+        .await
+        .admin_role_id
+        .is_some_and(|admin_role_id| member.roles.contains(&RoleId::new(admin_role_id)))
+}
+
+/**
+ * check of the admin commands, the author is told why the command is refused
+ */
+pub async fn check_guild_admin(ctx: Context<'_>) -> Result<bool, Error> {
+    if is_guild_admin(ctx).await {
+        return Ok(true);
+    }
+
+    let embed = create_error_embed(
+        "Error",
+        "This command requires the Manage Server permission or the admin role of the server",
+    );
+    send_reply(ctx, embed, true).await?;
+
+    Ok(false)
+}
+
+/**
+ * channel the public receipt of the command is posted in, none when the guild keeps it private
+ */
+pub async fn find_receipt_channel(ctx: Context<'_>) -> Option<serenity::ChannelId> {
+    let channel_id = ctx.channel_id();
+
+    match ctx.guild_id() {
+        Some(guild_id) => ctx
+            .data()
+            .guild_config_store
+            .find_by_guild_id(guild_id.get())
+            .await
+            .allows_public_receipt(channel_id.get())
+            .then_some(channel_id),
+        None => Some(channel_id),
+    }
+}
+
+/**
+ * reason the amount is refused by the tip limits of the guild, if any
+ */
+pub async fn check_guild_tip_amount(
+    tip_context: &Arc<TipContext>,
+    guild_id: Option<GuildId>,
+    amount_sompi: u64,
+) -> Result<(), String> {
+    let guild_id = match guild_id {
+        Some(guild_id) => guild_id,
+        None => return Ok(()),
+    };
+
+    let config = tip_context
+        .guild_config_store
+        .find_by_guild_id(guild_id.get())
+        .await;
+    let network_id = tip_context.network_id();
+
+    match config.check_tip_amount(amount_sompi) {
+        Ok(()) => Ok(()),
+        Err(SpectreError::TipAmountBelowMinimum()) => Err(format!(
+            "The minimum tip in this server is {}",
+            sompi_to_spectre_string_with_suffix(
+                config.min_tip_sompi.unwrap_or_default(),
+                &network_id
+            )
+        )),
+        Err(SpectreError::TipAmountAboveMaximum()) => Err(format!(
+            "The maximum tip in this server is {}",
+            sompi_to_spectre_string_with_suffix(
+                config.max_tip_sompi.unwrap_or_default(),
+                &network_id
+            )
+        )),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+// qr codes
+pub const QR_CODE_FILENAME: &str = "qr_code.png";
+
+pub fn render_qr_code_png(data: &str) -> Result<Vec<u8>, Error> {
+    let image = QrCode::new(data.as_bytes())?
+        .render::<Luma<u8>>()
+        .min_dimensions(256, 256)
+        .build();
+
+    let mut png = Vec::new();
+    image.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+
+    Ok(png)
+}
+
+/**
+ * attachment to be displayed in an embed through `with_qr_code_image`
+ */
+pub fn create_qr_code_attachment(data: &str) -> Result<CreateAttachment, Error> {
+    Ok(CreateAttachment::bytes(
+        render_qr_code_png(data)?,
+        QR_CODE_FILENAME,
+    ))
+}
+
+pub fn with_qr_code_image(embed: CreateEmbed) -> CreateEmbed {
+    embed.image(format!("attachment://{}", QR_CODE_FILENAME))
+}
+
+// wallet status
+pub fn format_transaction_history(
+    tip_context: &TipContext,
+    records: &[Arc<TransactionRecord>],
+) -> String {
+    let history = records
+        .iter()
+        .map(|record| {
+            format!(
+                "{} {} `{}`",
+                record.kind(),
+                sompi_to_spectre_string_with_suffix(record.value(), &tip_context.network_id()),
+                record.id()
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    match history.is_empty() {
+        true => "No transaction yet".to_string(),
+        false => history,
+    }
+}
+
+// transaction previews
+pub const TRANSACTION_PREVIEW_TIMEOUT: Duration = Duration::from_secs(60);
+
+/**
+ * display the preview with Confirm/Cancel buttons and wait for the author's choice,
+ * an expired preview counts as cancelled
+ */
+pub async fn confirm_transaction_preview(
+    ctx: Context<'_>,
+    preview: CreateEmbed,
+) -> Result<bool, Error> {
+    let interaction_id = ctx.id().to_string();
+    let confirm_id = format!("{}-confirm", interaction_id);
+    let cancel_id = format!("{}-cancel", interaction_id);
+
+    let buttons = CreateActionRow::Buttons(vec![
+        CreateButton::new(&confirm_id)
+            .label("Confirm")
+            .style(ButtonStyle::Success),
+        CreateButton::new(&cancel_id)
+            .label("Cancel")
+            .style(ButtonStyle::Secondary),
+    ]);
+
+    let handle = ctx
+        .send(CreateReply {
+            reply: false,
+            embeds: vec![preview.clone()],
+            components: Some(vec![buttons]),
+            ephemeral: Some(true),
+            ..Default::default()
+        })
+        .await?;
+
+    let press = ComponentInteractionCollector::new(ctx)
+        .author_id(ctx.author().id)
+        .filter(move |press| press.data.custom_id.starts_with(&interaction_id))
+        .timeout(TRANSACTION_PREVIEW_TIMEOUT)
+        .await;
+
+    let press = match press {
+        Some(press) => press,
+        None => {
+            handle
+                .edit(
+                    ctx,
+                    CreateReply {
+                        embeds: vec![preview
+                            .footer(CreateEmbedFooter::new("Preview expired, nothing was sent"))],
+                        components: Some(vec![]),
+                        ..Default::default()
+                    },
+                )
+                .await?;
+            return Ok(false);
+        }
+    };
+
+    let confirmed = press.data.custom_id == confirm_id;
+    let footer = match confirmed {
+        true => "Confirmed, signing and broadcasting",
+        false => "Cancelled, nothing was sent",
+    };
+
+    press
+        .create_response(
+            ctx,
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .embed(preview.footer(CreateEmbedFooter::new(footer)))
+                    .components(vec![]),
+            ),
+        )
+        .await?;
+
+    Ok(confirmed)
+}
+
+// modals
+
+/**
+ * show a modal in response to a button press and wait for its submission,
+ * like `poise::execute_modal_on_component_interaction` but usable from event handlers
+ */
+pub async fn execute_component_modal<M: Modal>(
+    ctx: &serenity::Context,
+    interaction: &ComponentInteraction,
+    timeout: Duration,
+) -> Result<Option<M>, Error> {
+    let modal_custom_id = interaction.id.to_string();
+
+    interaction
+        .create_response(ctx, M::create(None, modal_custom_id.clone()))
+        .await?;
+
+    let response = ModalInteractionCollector::new(&ctx.shard)
+        .filter(move |submit| submit.data.custom_id == modal_custom_id)
+        .timeout(timeout)
+        .await;
+
+    let response = match response {
+        Some(response) => response,
+        None => return Ok(None),
+    };
+
+    // closes the pop-up
+    response
+        .create_response(ctx, CreateInteractionResponse::Acknowledge)
+        .await?;
+
+    Ok(Some(M::parse(response.data.clone())?))
+}
+
+// component interactions
+
+pub async fn respond_ephemeral(
+    ctx: &serenity::Context,
+    interaction: &ComponentInteraction,
+    embed: CreateEmbed,
+) -> Result<(), Error> {
+    interaction
+        .create_response(
+            ctx,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .embed(embed)
+                    .ephemeral(true),
+            ),
+        )
+        .await?;
+
+    Ok(())
+}
+
+pub async fn follow_up_ephemeral(
+    ctx: &serenity::Context,
+    interaction: &ComponentInteraction,
+    embed: CreateEmbed,
+) -> Result<(), Error> {
+    interaction
+        .create_followup(
+            ctx,
+            CreateInteractionResponseFollowup::new()
+                .embed(embed)
+                .ephemeral(true),
+        )
+        .await?;
+
+    Ok(())
+}
+
+// reactions
+
+/**
+ * custom emojis are identified by id (their name may change), unicode emojis by value
+ */
+pub fn reaction_emoji_key(emoji: &ReactionType) -> String {
+    match emoji {
+        ReactionType::Custom { id, .. } => id.to_string(),
+        ReactionType::Unicode(value) => value.clone(),
+        _ => emoji.to_string(),
+    }
+}
+
+// recipients
+
+/**
+ * receive address of the recipient owned wallet, or of a transition wallet (created if needed)
+ * when the recipient has no wallet yet, along with whether the recipient owns a wallet
+ */
+pub async fn resolve_recipient_address(
+    tip_context: &Arc<TipContext>,
+    initiator_identifier: &str,
+    recipient_identifier: &str,
+) -> Result<(Address, bool), SpectreError> {
+    let existing_owned_wallet = tip_context
+        .owned_wallet_metadata_store
+        .find_owned_wallet_metadata_by_owner_identifier(recipient_identifier)
+        .await;
+
+    match existing_owned_wallet {
+        Ok(wallet) => Ok((wallet.receive_address, true)),
+        Err(SpectreError::OwnedWalletNotFound()) => {
+            // find or create a temporary wallet
+            let transition_wallet_result = tip_context
+                .transition_wallet_metadata_store
+                .find_transition_wallet_metadata_by_identifier_couple(
+                    initiator_identifier,
+                    recipient_identifier,
+                )
+                .await?;
+
+            let address = match transition_wallet_result {
+                Some(wallet) => wallet.receive_address,
+                None => TipTransitionWallet::create(
+                    tip_context.clone(),
+                    initiator_identifier,
+                    recipient_identifier,
+                )
+                .await?
+                .receive_address(),
+            };
+
+            Ok((address, false))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+// outbox
+
+/**
+ * drop the outbox entry of a failed spend, unless one of its transactions reached the node
+ * or the node cannot tell
+ */
+pub async fn forget_unsubmitted_outbox_entry(
+    tip_context: &Arc<TipContext>,
+    idempotency_key: &str,
+) -> Result<(), Error> {
+    let entry = match tip_context
+        .outbox_store
+        .find_by_idempotency_key(idempotency_key)
+        .await
+    {
+        Some(entry) if entry.status == OutboxEntryStatus::Pending => entry,
+        _ => return Ok(()),
+    };
+
+    for tx_id in &entry.tx_ids {
+        if !matches!(
+            find_transaction_on_node(tip_context, &entry, tx_id).await,
+            Ok(None)
+        ) {
+            return Ok(());
+        }
+    }
+
+    tip_context.outbox_store.remove(idempotency_key).await?;
+
+    Ok(())
+}
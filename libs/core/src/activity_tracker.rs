@@ -0,0 +1,114 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::RwLock,
+};
+
+// activity older than this is forgotten, it bounds the window of a rain
+pub const ACTIVITY_RETENTION_SECS: u64 = 60 * 60;
+
+#[derive(Debug, Default)]
+struct ChannelActivity {
+    // message timestamps per author, oldest first
+    messages_by_author: HashMap<u64, VecDeque<u64>>,
+}
+
+/**
+ * in-memory record of who recently posted in which channel, fed by message events
+ */
+#[derive(Debug, Default)]
+pub struct ActivityTracker {
+    channels: RwLock<HashMap<u64, ChannelActivity>>,
+}
+
+impl ActivityTracker {
+    pub fn new() -> Self {
+        ActivityTracker::default()
+    }
+
+    pub fn record_message(&self, channel_id: u64, author_id: u64, posted_at: u64) {
+        let mut channels = self.channels.write().unwrap();
+        let channel = channels.entry(channel_id).or_default();
+
+        let messages = channel.messages_by_author.entry(author_id).or_default();
+        messages.push_back(posted_at);
+
+        // prune the whole channel on write, keeps memory bounded without a background task
+        let expired_before = posted_at.saturating_sub(ACTIVITY_RETENTION_SECS);
+        channel.messages_by_author.retain(|_, messages| {
+            while messages.front().is_some_and(|at| *at < expired_before) {
+                messages.pop_front();
+            }
+
+            !messages.is_empty()
+        });
+    }
+
+    /**
+     * authors with at least `min_messages` messages since `since`, most recently active first
+     */
+    pub fn find_active_authors(
+        &self,
+        channel_id: u64,
+        since: u64,
+        min_messages: usize,
+    ) -> Vec<u64> {
+        let channels = self.channels.read().unwrap();
+
+        let channel = match channels.get(&channel_id) {
+            Some(channel) => channel,
+            None => return vec![],
+        };
+
+        let mut authors: Vec<(u64, u64)> = channel
+            .messages_by_author
+            .iter()
+            .filter_map(|(author_id, messages)| {
+                let recent_messages: Vec<&u64> =
+                    messages.iter().filter(|at| **at >= since).collect();
+
+                match recent_messages.len() >= min_messages {
+                    true => recent_messages.last().map(|last| (*author_id, **last)),
+                    false => None,
+                }
+            })
+            .collect();
+
+        authors.sort_by(|a, b| b.1.cmp(&a.1));
+
+        authors
+            .into_iter()
+            .map(|(author_id, _)| author_id)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_active_authors() {
+        let tracker = ActivityTracker::new();
+
+        tracker.record_message(1, 10, 1_000);
+        tracker.record_message(1, 10, 1_010);
+        tracker.record_message(1, 20, 1_020);
+        tracker.record_message(1, 20, 1_030);
+        tracker.record_message(1, 30, 1_040);
+        tracker.record_message(2, 40, 1_050);
+
+        assert_eq!(tracker.find_active_authors(1, 1_000, 2), vec![20, 10]);
+        assert_eq!(tracker.find_active_authors(1, 1_015, 1), vec![30, 20]);
+        assert_eq!(tracker.find_active_authors(3, 0, 1), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn test_record_message_prunes_expired_activity() {
+        let tracker = ActivityTracker::new();
+
+        tracker.record_message(1, 10, 1_000);
+        tracker.record_message(1, 20, 1_000 + ACTIVITY_RETENTION_SECS + 1);
+
+        assert_eq!(tracker.find_active_authors(1, 0, 1), vec![20]);
+    }
+}
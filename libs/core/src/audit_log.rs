@@ -0,0 +1,134 @@
+use std::{
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use crate::result::Result;
+
+/**
+ * action taken by an operator, entries are never removed
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AuditLogEntry {
+    pub id: u64,
+    pub actor_identifier: String,
+    // e.g. the name of the admin command
+    pub action: String,
+    pub target_identifier: Option<String>,
+    pub details: String,
+    pub at: u64,
+}
+
+#[derive(Debug)]
+pub struct AuditLogStore {
+    entries: RwLock<Vec<AuditLogEntry>>,
+    path_buf: PathBuf,
+}
+
+impl AuditLogStore {
+    pub fn new(path_buf: &PathBuf) -> Result<Self> {
+        let path = Path::new(path_buf);
+
+        let file = match File::open(path) {
+            Ok(f) => f,
+            Err(_) => {
+                let mut created_file = File::create(path)?;
+
+                created_file.write_all(b"[]")?;
+
+                File::open(path)?
+            }
+        };
+
+        let entries: Vec<AuditLogEntry> = serde_json::from_reader(file)?;
+
+        Ok(AuditLogStore {
+            entries: RwLock::new(entries),
+            path_buf: path_buf.clone(),
+        })
+    }
+
+    /**
+     * assign the next id to the entry and append it
+     */
+    pub async fn add(&self, mut entry: AuditLogEntry) -> Result<AuditLogEntry> {
+        let mut entries = self.entries.write().await;
+
+        entry.id = entries.iter().map(|e| e.id).max().unwrap_or(0) + 1;
+
+        entries.push(entry.clone());
+
+        let file = File::create(Path::new(&self.path_buf))?;
+
+        serde_json::to_writer(file, &*entries)?;
+
+        Ok(entry)
+    }
+
+    /**
+     * most recent entries first, optionally only those targeting `target_identifier`
+     */
+    pub async fn find_recent(
+        &self,
+        target_identifier: Option<&str>,
+        limit: usize,
+    ) -> Vec<AuditLogEntry> {
+        let entries = self.entries.read().await;
+
+        entries
+            .iter()
+            .rev()
+            .filter(|e| {
+                target_identifier.is_none() || e.target_identifier.as_deref() == target_identifier
+            })
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_find_recent() {
+        let path_buf =
+            std::env::temp_dir().join(format!("audit_log_test_{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path_buf);
+
+        let store = AuditLogStore::new(&path_buf).unwrap();
+
+        for target_identifier in [Some("1"), None, Some("2"), Some("1")] {
+            store
+                .add(AuditLogEntry {
+                    id: 0,
+                    actor_identifier: "operator".to_string(),
+                    action: "lookup".to_string(),
+                    target_identifier: target_identifier.map(str::to_string),
+                    details: String::new(),
+                    at: 0,
+                })
+                .await
+                .unwrap();
+        }
+
+        let recent = store.find_recent(None, 2).await;
+        assert_eq!(
+            recent.iter().map(|e| e.id).collect::<Vec<u64>>(),
+            vec![4, 3]
+        );
+
+        let targeted = store.find_recent(Some("1"), 10).await;
+        assert_eq!(
+            targeted.iter().map(|e| e.id).collect::<Vec<u64>>(),
+            vec![4, 1]
+        );
+
+        std::fs::remove_file(&path_buf).unwrap();
+    }
+}
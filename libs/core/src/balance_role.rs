@@ -0,0 +1,277 @@
+use std::{
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use crate::{error::Error, result::Result};
+
+// a member balance is evaluated at most once per interval
+pub const BALANCE_ROLE_EVALUATION_INTERVAL_SECS: u64 = 60 * 60;
+
+// members evaluated per run of the periodic job, to spare the node
+pub const BALANCE_ROLE_EVALUATIONS_PER_RUN: usize = 25;
+
+/**
+ * members holding at least `min_balance_sompi` get the role
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BalanceRole {
+    pub guild_id: u64,
+    pub role_id: u64,
+    pub min_balance_sompi: u64,
+}
+
+impl BalanceRole {
+    pub fn is_qualified(&self, balance_sompi: u64) -> bool {
+        balance_sompi >= self.min_balance_sompi
+    }
+}
+
+#[derive(Debug)]
+pub struct BalanceRoleStore {
+    roles: RwLock<Vec<BalanceRole>>,
+    path_buf: PathBuf,
+}
+
+impl BalanceRoleStore {
+    pub fn new(path_buf: &PathBuf) -> Result<Self> {
+        let path = Path::new(path_buf);
+
+        let file = match File::open(path) {
+            Ok(f) => f,
+            Err(_) => {
+                let mut created_file = File::create(path)?;
+
+                created_file.write_all(b"[]")?;
+
+                File::open(path)?
+            }
+        };
+
+        let roles: Vec<BalanceRole> = serde_json::from_reader(file)?;
+
+        Ok(BalanceRoleStore {
+            roles: RwLock::new(roles),
+            path_buf: path_buf.clone(),
+        })
+    }
+
+    fn persist(&self, roles: &[BalanceRole]) -> Result<()> {
+        let file = File::create(Path::new(&self.path_buf))?;
+
+        serde_json::to_writer(file, roles)?;
+
+        Ok(())
+    }
+
+    /**
+     * add the rule, or replace the minimum balance of the role
+     */
+    pub async fn set(&self, balance_role: &BalanceRole) -> Result<()> {
+        let mut roles = self.roles.write().await;
+
+        roles.retain(|r| {
+            !(r.guild_id == balance_role.guild_id && r.role_id == balance_role.role_id)
+        });
+        roles.push(balance_role.clone());
+
+        self.persist(&roles)
+    }
+
+    pub async fn remove(&self, guild_id: u64, role_id: u64) -> Result<()> {
+        let mut roles = self.roles.write().await;
+
+        let count = roles.len();
+        roles.retain(|r| !(r.guild_id == guild_id && r.role_id == role_id));
+
+        if roles.len() == count {
+            return Err(Error::BalanceRoleNotFound());
+        }
+
+        self.persist(&roles)
+    }
+
+    pub async fn find_by_guild_id(&self, guild_id: u64) -> Vec<BalanceRole> {
+        let roles = self.roles.read().await;
+
+        roles
+            .iter()
+            .filter(|r| r.guild_id == guild_id)
+            .cloned()
+            .collect()
+    }
+}
+
+/**
+ * member who opted in to have their balance checked in a guild
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BalanceRoleMember {
+    pub guild_id: u64,
+    pub owner_identifier: String,
+    pub opted_in_at: u64,
+    pub last_evaluated_at: Option<u64>,
+    pub last_balance_sompi: Option<u64>,
+}
+
+impl BalanceRoleMember {
+    pub fn is_evaluation_due(&self, now: u64) -> bool {
+        self.last_evaluated_at.map_or(true, |last_evaluated_at| {
+            now >= last_evaluated_at + BALANCE_ROLE_EVALUATION_INTERVAL_SECS
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct BalanceRoleMemberStore {
+    members: RwLock<Vec<BalanceRoleMember>>,
+    path_buf: PathBuf,
+}
+
+impl BalanceRoleMemberStore {
+    pub fn new(path_buf: &PathBuf) -> Result<Self> {
+        let path = Path::new(path_buf);
+
+        let file = match File::open(path) {
+            Ok(f) => f,
+            Err(_) => {
+                let mut created_file = File::create(path)?;
+
+                created_file.write_all(b"[]")?;
+
+                File::open(path)?
+            }
+        };
+
+        let members: Vec<BalanceRoleMember> = serde_json::from_reader(file)?;
+
+        Ok(BalanceRoleMemberStore {
+            members: RwLock::new(members),
+            path_buf: path_buf.clone(),
+        })
+    }
+
+    fn persist(&self, members: &[BalanceRoleMember]) -> Result<()> {
+        let file = File::create(Path::new(&self.path_buf))?;
+
+        serde_json::to_writer(file, members)?;
+
+        Ok(())
+    }
+
+    /**
+     * opting in again resets the evaluation, the member is checked on the next run
+     */
+    pub async fn opt_in(&self, guild_id: u64, owner_identifier: &str, at: u64) -> Result<()> {
+        let mut members = self.members.write().await;
+
+        members.retain(|m| !(m.guild_id == guild_id && m.owner_identifier == owner_identifier));
+        members.push(BalanceRoleMember {
+            guild_id,
+            owner_identifier: owner_identifier.to_string(),
+            opted_in_at: at,
+            last_evaluated_at: None,
+            last_balance_sompi: None,
+        });
+
+        self.persist(&members)
+    }
+
+    pub async fn opt_out(&self, guild_id: u64, owner_identifier: &str) -> Result<()> {
+        let mut members = self.members.write().await;
+
+        let count = members.len();
+        members.retain(|m| !(m.guild_id == guild_id && m.owner_identifier == owner_identifier));
+
+        if members.len() == count {
+            return Err(Error::BalanceRoleMemberNotFound());
+        }
+
+        self.persist(&members)
+    }
+
+    pub async fn record_evaluation(
+        &self,
+        guild_id: u64,
+        owner_identifier: &str,
+        balance_sompi: u64,
+        at: u64,
+    ) -> Result<()> {
+        let mut members = self.members.write().await;
+
+        let member = members
+            .iter_mut()
+            .find(|m| m.guild_id == guild_id && m.owner_identifier == owner_identifier)
+            .ok_or(Error::BalanceRoleMemberNotFound())?;
+
+        member.last_evaluated_at = Some(at);
+        member.last_balance_sompi = Some(balance_sompi);
+
+        self.persist(&members)
+    }
+
+    pub async fn find(&self, guild_id: u64, owner_identifier: &str) -> Option<BalanceRoleMember> {
+        let members = self.members.read().await;
+
+        members
+            .iter()
+            .find(|m| m.guild_id == guild_id && m.owner_identifier == owner_identifier)
+            .cloned()
+    }
+
+    /**
+     * members due for an evaluation, least recently evaluated first
+     */
+    pub async fn find_due(&self, now: u64, limit: usize) -> Vec<BalanceRoleMember> {
+        let members = self.members.read().await;
+
+        let mut due: Vec<BalanceRoleMember> = members
+            .iter()
+            .filter(|m| m.is_evaluation_due(now))
+            .cloned()
+            .collect();
+
+        due.sort_by_key(|m| m.last_evaluated_at);
+        due.truncate(limit);
+
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_balance_role_qualification() {
+        let balance_role = BalanceRole {
+            guild_id: 1,
+            role_id: 1,
+            min_balance_sompi: 100,
+        };
+
+        assert!(!balance_role.is_qualified(99));
+        assert!(balance_role.is_qualified(100));
+    }
+
+    #[test]
+    fn test_evaluation_throttle() {
+        let mut member = BalanceRoleMember {
+            guild_id: 1,
+            owner_identifier: "member".to_string(),
+            opted_in_at: 0,
+            last_evaluated_at: None,
+            last_balance_sompi: None,
+        };
+
+        assert!(member.is_evaluation_due(0));
+
+        member.last_evaluated_at = Some(10);
+        assert!(!member.is_evaluation_due(10 + BALANCE_ROLE_EVALUATION_INTERVAL_SECS - 1));
+        assert!(member.is_evaluation_due(10 + BALANCE_ROLE_EVALUATION_INTERVAL_SECS));
+    }
+}
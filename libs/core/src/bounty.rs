@@ -0,0 +1,459 @@
+use std::{
+    fmt,
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use spectre_addresses::Address;
+use tokio::sync::RwLock;
+
+use crate::{error::Error, result::Result};
+
+pub const DEFAULT_BOUNTY_DURATION_SECS: u64 = 7 * 24 * 60 * 60;
+
+pub const MAX_BOUNTY_DURATION_SECS: u64 = 90 * 24 * 60 * 60;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum BountyStatus {
+    // bounty wallet created, the reward is not submitted yet
+    Pending,
+    Open,
+    // the reward is being paid to `winner_identifier`, or refunded without winner
+    Settling,
+    Awarded,
+    Refunded,
+    // never funded, nothing to refund
+    Cancelled,
+    // the funding failed after a transaction reached the node, the bounty wallet is kept
+    FundingFailed,
+}
+
+impl fmt::Display for BountyStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self {
+            BountyStatus::Pending => "awaiting funds",
+            BountyStatus::Open => "open",
+            BountyStatus::Settling => "being paid",
+            BountyStatus::Awarded => "awarded",
+            BountyStatus::Refunded => "refunded",
+            BountyStatus::Cancelled => "cancelled",
+            BountyStatus::FundingFailed => "funding failed",
+        };
+
+        write!(f, "{}", status)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BountyClaim {
+    pub claimant_identifier: String,
+    pub note: Option<String>,
+    pub at: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Bounty {
+    pub id: u64,
+    pub guild_id: u64,
+    pub channel_id: u64,
+    pub title: String,
+    pub description: Option<String>,
+    // picks the winner, along with the server admins
+    pub creator_identifier: String,
+    // refunded on expiry, a user or a treasury identifier
+    pub sponsor_identifier: String,
+    pub reward_sompi: u64,
+    pub wallet_identifier: String,
+    pub receive_address: Address,
+    pub secret: String,
+    pub status: BountyStatus,
+    pub claims: Vec<BountyClaim>,
+    pub winner_identifier: Option<String>,
+    pub fund_tx_id: Option<String>,
+    pub settle_tx_id: Option<String>,
+    pub expires_at: u64,
+    pub created_at: u64,
+    pub settled_at: Option<u64>,
+}
+
+impl Bounty {
+    pub fn is_expired(&self, now: u64) -> bool {
+        now >= self.expires_at
+    }
+
+    pub fn has_claimed(&self, owner_identifier: &str) -> bool {
+        self.claims
+            .iter()
+            .any(|claim| claim.claimant_identifier == owner_identifier)
+    }
+
+    /**
+     * members claim an open bounty once, its creator cannot
+     */
+    pub fn claim(
+        &mut self,
+        claimant_identifier: &str,
+        note: Option<String>,
+        at: u64,
+    ) -> Result<()> {
+        if self.status != BountyStatus::Open || self.is_expired(at) {
+            return Err(Error::BountyTransitionNotAllowed());
+        }
+
+        if claimant_identifier == self.creator_identifier || self.has_claimed(claimant_identifier) {
+            return Err(Error::BountyAlreadyClaimed());
+        }
+
+        self.claims.push(BountyClaim {
+            claimant_identifier: claimant_identifier.to_string(),
+            note,
+            at,
+        });
+
+        Ok(())
+    }
+
+    /**
+     * the winner is picked among the claimants of an open bounty
+     */
+    pub fn can_award(&self, winner_identifier: &str) -> bool {
+        self.can_settle(Some(winner_identifier))
+    }
+
+    /**
+     * award to a claimant or refund without winner, a settlement interrupted by a restart
+     * can only be resumed with the same winner
+     */
+    pub fn can_settle(&self, winner_identifier: Option<&str>) -> bool {
+        match self.status {
+            BountyStatus::Open => winner_identifier.map_or(true, |w| self.has_claimed(w)),
+            BountyStatus::Settling => self.winner_identifier.as_deref() == winner_identifier,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct BountyStore {
+    bounties: RwLock<Vec<Bounty>>,
+    path_buf: PathBuf,
+}
+
+impl BountyStore {
+    pub fn new(path_buf: &PathBuf) -> Result<Self> {
+        let path = Path::new(path_buf);
+
+        let file = match File::open(path) {
+            Ok(f) => f,
+            Err(_) => {
+                let mut created_file = File::create(path)?;
+
+                created_file.write_all(b"[]")?;
+
+                File::open(path)?
+            }
+        };
+
+        let bounties: Vec<Bounty> = serde_json::from_reader(file)?;
+
+        Ok(BountyStore {
+            bounties: RwLock::new(bounties),
+            path_buf: path_buf.clone(),
+        })
+    }
+
+    fn persist(&self, bounties: &[Bounty]) -> Result<()> {
+        let file = File::create(Path::new(&self.path_buf))?;
+
+        serde_json::to_writer(file, bounties)?;
+
+        Ok(())
+    }
+
+    async fn update<F: FnOnce(&mut Bounty) -> Result<()>>(
+        &self,
+        id: u64,
+        update: F,
+    ) -> Result<Bounty> {
+        let mut bounties = self.bounties.write().await;
+
+        let bounty = bounties
+            .iter_mut()
+            .find(|b| b.id == id)
+            .ok_or(Error::BountyNotFound())?;
+
+        update(bounty)?;
+
+        let updated = bounty.clone();
+
+        self.persist(&bounties)?;
+
+        Ok(updated)
+    }
+
+    /**
+     * assign the next id to the bounty and store it
+     */
+    pub async fn add(&self, mut bounty: Bounty) -> Result<Bounty> {
+        let mut bounties = self.bounties.write().await;
+
+        bounty.id = bounties.iter().map(|b| b.id).max().unwrap_or(0) + 1;
+
+        bounties.push(bounty.clone());
+
+        self.persist(&bounties)?;
+
+        Ok(bounty)
+    }
+
+    pub async fn remove(&self, id: u64) -> Result<()> {
+        let mut bounties = self.bounties.write().await;
+
+        let count = bounties.len();
+        bounties.retain(|b| b.id != id);
+
+        if bounties.len() == count {
+            return Err(Error::BountyNotFound());
+        }
+
+        self.persist(&bounties)
+    }
+
+    pub async fn fund(&self, id: u64, tx_id: String) -> Result<Bounty> {
+        self.update(id, |bounty| {
+            if bounty.status != BountyStatus::Pending {
+                return Err(Error::BountyTransitionNotAllowed());
+            }
+
+            bounty.status = BountyStatus::Open;
+            bounty.fund_tx_id = Some(tx_id);
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn claim(
+        &self,
+        id: u64,
+        claimant_identifier: &str,
+        note: Option<String>,
+        at: u64,
+    ) -> Result<Bounty> {
+        self.update(id, |bounty| bounty.claim(claimant_identifier, note, at))
+            .await
+    }
+
+    /**
+     * claim the bounty for an award or a refund before paying it
+     */
+    pub async fn begin_settlement(
+        &self,
+        id: u64,
+        winner_identifier: Option<&str>,
+    ) -> Result<Bounty> {
+        self.update(id, |bounty| {
+            if !bounty.can_settle(winner_identifier) {
+                return Err(Error::BountyTransitionNotAllowed());
+            }
+
+            bounty.status = BountyStatus::Settling;
+            bounty.winner_identifier = winner_identifier.map(|w| w.to_string());
+            Ok(())
+        })
+        .await
+    }
+
+    /**
+     * reopen the bounty once a settlement failed before reaching the node
+     */
+    pub async fn abort_settlement(&self, id: u64) -> Result<Bounty> {
+        self.update(id, |bounty| {
+            if bounty.status != BountyStatus::Settling {
+                return Err(Error::BountyTransitionNotAllowed());
+            }
+
+            bounty.status = BountyStatus::Open;
+            bounty.winner_identifier = None;
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn mark_funding_failed(&self, id: u64) -> Result<Bounty> {
+        self.update(id, |bounty| {
+            if bounty.status != BountyStatus::Pending {
+                return Err(Error::BountyTransitionNotAllowed());
+            }
+
+            bounty.status = BountyStatus::FundingFailed;
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn award(
+        &self,
+        id: u64,
+        winner_identifier: &str,
+        tx_id: String,
+        at: u64,
+    ) -> Result<Bounty> {
+        self.update(id, |bounty| {
+            if bounty.status != BountyStatus::Settling
+                || bounty.winner_identifier.as_deref() != Some(winner_identifier)
+            {
+                return Err(Error::BountyTransitionNotAllowed());
+            }
+
+            bounty.status = BountyStatus::Awarded;
+            bounty.settle_tx_id = Some(tx_id);
+            bounty.settled_at = Some(at);
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn refund(&self, id: u64, tx_id: String, at: u64) -> Result<Bounty> {
+        self.update(id, |bounty| {
+            if bounty.status != BountyStatus::Settling || bounty.winner_identifier.is_some() {
+                return Err(Error::BountyTransitionNotAllowed());
+            }
+
+            bounty.status = BountyStatus::Refunded;
+            bounty.settle_tx_id = Some(tx_id);
+            bounty.settled_at = Some(at);
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn cancel(&self, id: u64, at: u64) -> Result<Bounty> {
+        self.update(id, |bounty| {
+            if bounty.status != BountyStatus::Pending {
+                return Err(Error::BountyTransitionNotAllowed());
+            }
+
+            bounty.status = BountyStatus::Cancelled;
+            bounty.settled_at = Some(at);
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn find_by_id(&self, id: u64) -> Option<Bounty> {
+        let bounties = self.bounties.read().await;
+
+        bounties.iter().find(|b| b.id == id).cloned()
+    }
+
+    /**
+     * bounties of the guild, most recent first
+     */
+    pub async fn find_by_guild_id(&self, guild_id: u64) -> Vec<Bounty> {
+        let bounties = self.bounties.read().await;
+
+        bounties
+            .iter()
+            .rev()
+            .filter(|b| b.guild_id == guild_id)
+            .cloned()
+            .collect()
+    }
+
+    /**
+     * open bounties to refund and pending ones to cancel, refunds interrupted by a restart
+     * are resumed
+     */
+    pub async fn find_expired(&self, now: u64) -> Vec<Bounty> {
+        let bounties = self.bounties.read().await;
+
+        bounties
+            .iter()
+            .filter(|b| match b.status {
+                BountyStatus::Pending | BountyStatus::Open => true,
+                BountyStatus::Settling => b.winner_identifier.is_none(),
+                _ => false,
+            })
+            .filter(|b| b.is_expired(now))
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectre_addresses::{Prefix, Version};
+
+    fn build_bounty() -> Bounty {
+        Bounty {
+            id: 1,
+            guild_id: 1,
+            channel_id: 1,
+            title: "title".to_string(),
+            description: None,
+            creator_identifier: "creator".to_string(),
+            sponsor_identifier: "creator".to_string(),
+            reward_sompi: 100,
+            wallet_identifier: "bounty-1".to_string(),
+            receive_address: Address::new(Prefix::Mainnet, Version::PubKey, &[0u8; 32]),
+            secret: "secret".to_string(),
+            status: BountyStatus::Open,
+            claims: vec![],
+            winner_identifier: None,
+            fund_tx_id: None,
+            settle_tx_id: None,
+            expires_at: 100,
+            created_at: 0,
+            settled_at: None,
+        }
+    }
+
+    #[test]
+    fn test_bounty_claims() {
+        let mut bounty = build_bounty();
+
+        assert!(matches!(
+            bounty.claim("creator", None, 1),
+            Err(Error::BountyAlreadyClaimed())
+        ));
+
+        assert!(bounty.claim("member", None, 1).is_ok());
+        assert!(matches!(
+            bounty.claim("member", None, 2),
+            Err(Error::BountyAlreadyClaimed())
+        ));
+
+        assert!(matches!(
+            bounty.claim("late", None, 100),
+            Err(Error::BountyTransitionNotAllowed())
+        ));
+
+        assert!(bounty.can_award("member"));
+        assert!(!bounty.can_award("other"));
+
+        bounty.status = BountyStatus::Pending;
+        assert!(!bounty.can_award("member"));
+    }
+
+    #[test]
+    fn test_bounty_settlement_claim() {
+        let mut bounty = build_bounty();
+        bounty.claims.push(BountyClaim {
+            claimant_identifier: "member".to_string(),
+            note: None,
+            at: 1,
+        });
+
+        bounty.status = BountyStatus::Settling;
+        bounty.winner_identifier = Some("member".to_string());
+        assert!(bounty.can_settle(Some("member")));
+        assert!(!bounty.can_settle(None));
+
+        bounty.winner_identifier = None;
+        assert!(bounty.can_settle(None));
+        assert!(!bounty.can_award("member"));
+    }
+}
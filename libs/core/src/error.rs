@@ -28,6 +28,87 @@ pub enum Error {
 
     #[error("Owned Wallet Not Found")]
     OwnedWalletNotFound(),
+
+    #[error("Watch-Only Wallet Cannot Sign Transactions")]
+    WatchOnlyWallet(),
+
+    #[error("Linked Address Already Exists")]
+    LinkedAddressAlreadyExists(),
+
+    #[error("Linked Address Not Found")]
+    LinkedAddressNotFound(),
+
+    #[error("Outbox Entry Already Exists")]
+    OutboxEntryAlreadyExists(),
+
+    #[error("Outbox Entry Not Found")]
+    OutboxEntryNotFound(),
+
+    #[error("Payment Request Not Found")]
+    PaymentRequestNotFound(),
+
+    #[error("Escrow Not Found")]
+    EscrowNotFound(),
+
+    #[error("Escrow Transition Not Allowed")]
+    EscrowTransitionNotAllowed(),
+
+    #[error("Schedule Not Found")]
+    ScheduleNotFound(),
+
+    #[error("Treasury Payout Not Found")]
+    TreasuryPayoutNotFound(),
+
+    #[error("Treasury Payout Not Proposed")]
+    TreasuryPayoutNotProposed(),
+
+    #[error("Treasury Payout Already Approved")]
+    TreasuryPayoutAlreadyApproved(),
+
+    #[error("Treasury Payout Not Executing")]
+    TreasuryPayoutNotExecuting(),
+
+    #[error("Bounty Not Found")]
+    BountyNotFound(),
+
+    #[error("Bounty Transition Not Allowed")]
+    BountyTransitionNotAllowed(),
+
+    #[error("Bounty Already Claimed")]
+    BountyAlreadyClaimed(),
+
+    #[error("Shop Role Not Found")]
+    ShopRoleNotFound(),
+
+    #[error("Role Purchase Not Found")]
+    RolePurchaseNotFound(),
+
+    #[error("Balance Role Not Found")]
+    BalanceRoleNotFound(),
+
+    #[error("Balance Role Member Not Found")]
+    BalanceRoleMemberNotFound(),
+
+    #[error("Tip Amount Below Minimum")]
+    TipAmountBelowMinimum(),
+
+    #[error("Tip Amount Above Maximum")]
+    TipAmountAboveMaximum(),
+
+    #[error("User Frozen")]
+    UserFrozen(),
+
+    #[error("User Already Frozen")]
+    UserAlreadyFrozen(),
+
+    #[error("Frozen User Not Found")]
+    FrozenUserNotFound(),
+
+    #[error("Watch-Only Wallets Are Not Enabled")]
+    WatchOnlyWalletKeyMissing(),
+
+    #[error("Treasuries Are Not Enabled")]
+    TreasuryWalletKeyMissing(),
 }
 
 impl Error {
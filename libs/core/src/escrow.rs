@@ -0,0 +1,543 @@
+use std::{
+    fmt,
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use spectre_addresses::Address;
+use tokio::sync::RwLock;
+
+use crate::{error::Error, result::Result};
+
+pub const DEFAULT_ESCROW_DEADLINE_SECS: u64 = 3 * 24 * 60 * 60;
+
+pub const MAX_ESCROW_DEADLINE_SECS: u64 = 30 * 24 * 60 * 60;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum EscrowStatus {
+    // escrow wallet created, the buyer deposit is not submitted yet
+    Pending,
+    Funded,
+    // the deadline no longer refunds, only the arbiter can settle
+    Disputed,
+    // the release or refund is being paid, no other settlement can start meanwhile
+    Settling(EscrowAction),
+    Released,
+    Refunded,
+    // the deposit failed after a transaction reached the node, the escrow wallet is kept
+    FundingFailed,
+}
+
+impl EscrowStatus {
+    pub fn is_settled(&self) -> bool {
+        matches!(self, EscrowStatus::Released | EscrowStatus::Refunded)
+    }
+}
+
+impl fmt::Display for EscrowStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self {
+            EscrowStatus::Pending => "pending",
+            EscrowStatus::Funded => "funded",
+            EscrowStatus::Disputed => "disputed",
+            EscrowStatus::Settling(EscrowAction::Release) => "being released",
+            EscrowStatus::Settling(_) => "being refunded",
+            EscrowStatus::Released => "released",
+            EscrowStatus::Refunded => "refunded",
+            EscrowStatus::FundingFailed => "funding failed",
+        };
+
+        write!(f, "{}", status)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum EscrowAction {
+    Fund,
+    Release,
+    Refund,
+    Dispute,
+}
+
+impl fmt::Display for EscrowAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let action = match self {
+            EscrowAction::Fund => "funded",
+            EscrowAction::Release => "released",
+            EscrowAction::Refund => "refunded",
+            EscrowAction::Dispute => "disputed",
+        };
+
+        write!(f, "{}", action)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum EscrowActor {
+    Buyer,
+    Seller,
+    Arbiter,
+    // automatic refund once the deadline is reached
+    Deadline,
+}
+
+/**
+ * state change of an escrow, kept as its history
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EscrowEvent {
+    pub action: EscrowAction,
+    pub actor: EscrowActor,
+    pub actor_identifier: Option<String>,
+    pub tx_id: Option<String>,
+    pub at: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Escrow {
+    pub id: u64,
+    pub guild_id: u64,
+    pub channel_id: u64,
+    pub buyer_identifier: String,
+    pub seller_identifier: String,
+    pub amount_sompi: u64,
+    pub memo: Option<String>,
+    pub wallet_identifier: String,
+    pub receive_address: Address,
+    pub secret: String,
+    pub status: EscrowStatus,
+    pub deadline: u64,
+    pub events: Vec<EscrowEvent>,
+    pub created_at: u64,
+}
+
+impl Escrow {
+    /**
+     * status reached when `actor` performs `action`, none if the escrow does not allow it
+     */
+    pub fn next_status(&self, action: EscrowAction, actor: EscrowActor) -> Option<EscrowStatus> {
+        Self::transition(self.status, action, actor)
+    }
+
+    fn transition(
+        status: EscrowStatus,
+        action: EscrowAction,
+        actor: EscrowActor,
+    ) -> Option<EscrowStatus> {
+        use EscrowAction::*;
+        use EscrowActor::*;
+
+        match (status, action, actor) {
+            (EscrowStatus::Pending, Fund, Buyer) => Some(EscrowStatus::Funded),
+            (EscrowStatus::Funded, Release, Buyer | Arbiter) => Some(EscrowStatus::Released),
+            (EscrowStatus::Funded, Refund, Seller | Arbiter | Deadline) => {
+                Some(EscrowStatus::Refunded)
+            }
+            (EscrowStatus::Funded, Dispute, Buyer | Seller) => Some(EscrowStatus::Disputed),
+            (EscrowStatus::Disputed, Release, Arbiter) => Some(EscrowStatus::Released),
+            (EscrowStatus::Disputed, Refund, Arbiter) => Some(EscrowStatus::Refunded),
+            _ => None,
+        }
+    }
+
+    /**
+     * status before the settlement started, a dispute stays until the arbiter decides
+     */
+    fn unsettled_status(&self) -> EscrowStatus {
+        match self.status {
+            EscrowStatus::Settling(_) => match self
+                .events
+                .iter()
+                .any(|event| event.action == EscrowAction::Dispute)
+            {
+                true => EscrowStatus::Disputed,
+                false => EscrowStatus::Funded,
+            },
+            status => status,
+        }
+    }
+
+    /**
+     * whether `actor` can release or refund, a settlement of the same action interrupted
+     * by a restart can be resumed
+     */
+    pub fn can_settle(&self, action: EscrowAction, actor: EscrowActor) -> bool {
+        let resumable = match self.status {
+            EscrowStatus::Settling(settling) => settling == action,
+            _ => true,
+        };
+
+        resumable && Self::transition(self.unsettled_status(), action, actor).is_some()
+    }
+
+    /**
+     * role of a party of the escrow, arbiters are resolved from the guild roles by the caller
+     */
+    pub fn party_of(&self, owner_identifier: &str) -> Option<EscrowActor> {
+        if owner_identifier == self.buyer_identifier {
+            Some(EscrowActor::Buyer)
+        } else if owner_identifier == self.seller_identifier {
+            Some(EscrowActor::Seller)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_past_deadline(&self, now: u64) -> bool {
+        now >= self.deadline
+    }
+}
+
+#[derive(Debug)]
+pub struct EscrowStore {
+    escrows: RwLock<Vec<Escrow>>,
+    path_buf: PathBuf,
+}
+
+impl EscrowStore {
+    pub fn new(path_buf: &PathBuf) -> Result<Self> {
+        let path = Path::new(path_buf);
+
+        let file = match File::open(path) {
+            Ok(f) => f,
+            Err(_) => {
+                let mut created_file = File::create(path)?;
+
+                created_file.write_all(b"[]")?;
+
+                File::open(path)?
+            }
+        };
+
+        let escrows: Vec<Escrow> = serde_json::from_reader(file)?;
+
+        Ok(EscrowStore {
+            escrows: RwLock::new(escrows),
+            path_buf: path_buf.clone(),
+        })
+    }
+
+    fn persist(&self, escrows: &[Escrow]) -> Result<()> {
+        let file = File::create(Path::new(&self.path_buf))?;
+
+        serde_json::to_writer(file, escrows)?;
+
+        Ok(())
+    }
+
+    async fn update<F: FnOnce(&mut Escrow) -> Result<()>>(
+        &self,
+        id: u64,
+        update: F,
+    ) -> Result<Escrow> {
+        let mut escrows = self.escrows.write().await;
+
+        let escrow = escrows
+            .iter_mut()
+            .find(|e| e.id == id)
+            .ok_or(Error::EscrowNotFound())?;
+
+        update(escrow)?;
+
+        let updated = escrow.clone();
+
+        self.persist(&escrows)?;
+
+        Ok(updated)
+    }
+
+    /**
+     * assign the next id to the escrow and store it
+     */
+    pub async fn add(&self, mut escrow: Escrow) -> Result<Escrow> {
+        let mut escrows = self.escrows.write().await;
+
+        escrow.id = escrows.iter().map(|e| e.id).max().unwrap_or(0) + 1;
+
+        escrows.push(escrow.clone());
+
+        self.persist(&escrows)?;
+
+        Ok(escrow)
+    }
+
+    /**
+     * record the event and move the escrow to the resulting status
+     */
+    pub async fn apply(&self, id: u64, event: EscrowEvent) -> Result<Escrow> {
+        let mut escrows = self.escrows.write().await;
+
+        let escrow = escrows
+            .iter_mut()
+            .find(|e| e.id == id)
+            .ok_or(Error::EscrowNotFound())?;
+
+        let status = match escrow.status {
+            EscrowStatus::Settling(settling) if settling == event.action => {
+                Escrow::transition(escrow.unsettled_status(), event.action, event.actor)
+            }
+            _ => escrow.next_status(event.action, event.actor),
+        }
+        .ok_or(Error::EscrowTransitionNotAllowed())?;
+
+        escrow.status = status;
+        escrow.events.push(event);
+
+        let updated = escrow.clone();
+
+        self.persist(&escrows)?;
+
+        Ok(updated)
+    }
+
+    /**
+     * claim the escrow for a release or a refund before paying it
+     */
+    pub async fn begin_settlement(
+        &self,
+        id: u64,
+        action: EscrowAction,
+        actor: EscrowActor,
+    ) -> Result<Escrow> {
+        self.update(id, |escrow| {
+            if !escrow.can_settle(action, actor) {
+                return Err(Error::EscrowTransitionNotAllowed());
+            }
+
+            escrow.status = EscrowStatus::Settling(action);
+            Ok(())
+        })
+        .await
+    }
+
+    /**
+     * give the escrow back to its parties once a settlement failed before reaching the node
+     */
+    pub async fn abort_settlement(&self, id: u64) -> Result<Escrow> {
+        self.update(id, |escrow| {
+            escrow.status = escrow.unsettled_status();
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn mark_funding_failed(&self, id: u64) -> Result<Escrow> {
+        self.update(id, |escrow| {
+            if escrow.status != EscrowStatus::Pending {
+                return Err(Error::EscrowTransitionNotAllowed());
+            }
+
+            escrow.status = EscrowStatus::FundingFailed;
+            Ok(())
+        })
+        .await
+    }
+
+    /**
+     * forget an escrow whose deposit failed before reaching the node
+     */
+    pub async fn remove(&self, id: u64) -> Result<()> {
+        let mut escrows = self.escrows.write().await;
+
+        let index = escrows
+            .iter()
+            .position(|e| e.id == id)
+            .ok_or(Error::EscrowNotFound())?;
+
+        escrows.remove(index);
+
+        self.persist(&escrows)
+    }
+
+    pub async fn find_by_id(&self, id: u64) -> Option<Escrow> {
+        let escrows = self.escrows.read().await;
+
+        escrows.iter().find(|e| e.id == id).cloned()
+    }
+
+    pub async fn find_by_owner(&self, owner_identifier: &str) -> Vec<Escrow> {
+        let escrows = self.escrows.read().await;
+
+        escrows
+            .iter()
+            .filter(|e| e.party_of(owner_identifier).is_some())
+            .cloned()
+            .collect()
+    }
+
+    /**
+     * funded escrows to refund automatically
+     */
+    pub async fn find_past_deadline(&self, now: u64) -> Vec<Escrow> {
+        let escrows = self.escrows.read().await;
+
+        escrows
+            .iter()
+            .filter(|e| e.status == EscrowStatus::Funded && e.is_past_deadline(now))
+            .cloned()
+            .collect()
+    }
+}
+
+/**
+ * role allowed to settle the escrows of a guild
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EscrowArbiter {
+    pub guild_id: u64,
+    pub role_id: u64,
+}
+
+#[derive(Debug)]
+pub struct EscrowArbiterStore {
+    arbiters: RwLock<Vec<EscrowArbiter>>,
+    path_buf: PathBuf,
+}
+
+impl EscrowArbiterStore {
+    pub fn new(path_buf: &PathBuf) -> Result<Self> {
+        let path = Path::new(path_buf);
+
+        let file = match File::open(path) {
+            Ok(f) => f,
+            Err(_) => {
+                let mut created_file = File::create(path)?;
+
+                created_file.write_all(b"[]")?;
+
+                File::open(path)?
+            }
+        };
+
+        let arbiters: Vec<EscrowArbiter> = serde_json::from_reader(file)?;
+
+        Ok(EscrowArbiterStore {
+            arbiters: RwLock::new(arbiters),
+            path_buf: path_buf.clone(),
+        })
+    }
+
+    pub async fn set_arbiter_role(&self, guild_id: u64, role_id: u64) -> Result<()> {
+        let mut arbiters = self.arbiters.write().await;
+
+        arbiters.retain(|arbiter| arbiter.guild_id != guild_id);
+        arbiters.push(EscrowArbiter { guild_id, role_id });
+
+        let file = File::create(Path::new(&self.path_buf))?;
+
+        let copied = arbiters.clone();
+
+        serde_json::to_writer(file, &copied)?;
+
+        Ok(())
+    }
+
+    pub async fn find_arbiter_role(&self, guild_id: u64) -> Option<u64> {
+        let arbiters = self.arbiters.read().await;
+
+        arbiters
+            .iter()
+            .find(|arbiter| arbiter.guild_id == guild_id)
+            .map(|arbiter| arbiter.role_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectre_addresses::{Prefix, Version};
+
+    fn build_escrow(status: EscrowStatus) -> Escrow {
+        Escrow {
+            id: 1,
+            guild_id: 1,
+            channel_id: 1,
+            buyer_identifier: "buyer".to_string(),
+            seller_identifier: "seller".to_string(),
+            amount_sompi: 100,
+            memo: None,
+            wallet_identifier: "escrow-1".to_string(),
+            receive_address: Address::new(Prefix::Mainnet, Version::PubKey, &[0u8; 32]),
+            secret: "secret".to_string(),
+            status,
+            deadline: 1_000,
+            events: vec![],
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_escrow_transitions() {
+        use EscrowAction::*;
+        use EscrowActor::*;
+
+        let funded = build_escrow(EscrowStatus::Funded);
+        assert_eq!(
+            funded.next_status(Release, Buyer),
+            Some(EscrowStatus::Released)
+        );
+        assert_eq!(funded.next_status(Release, Seller), None);
+        assert_eq!(
+            funded.next_status(Refund, Seller),
+            Some(EscrowStatus::Refunded)
+        );
+        assert_eq!(funded.next_status(Refund, Buyer), None);
+        assert_eq!(
+            funded.next_status(Refund, Deadline),
+            Some(EscrowStatus::Refunded)
+        );
+        assert_eq!(
+            funded.next_status(Dispute, Seller),
+            Some(EscrowStatus::Disputed)
+        );
+
+        let disputed = build_escrow(EscrowStatus::Disputed);
+        assert_eq!(disputed.next_status(Release, Buyer), None);
+        assert_eq!(disputed.next_status(Refund, Deadline), None);
+        assert_eq!(
+            disputed.next_status(Release, Arbiter),
+            Some(EscrowStatus::Released)
+        );
+
+        let released = build_escrow(EscrowStatus::Released);
+        assert_eq!(released.next_status(Refund, Arbiter), None);
+    }
+
+    #[test]
+    fn test_escrow_settlement_claim() {
+        use EscrowAction::*;
+        use EscrowActor::*;
+
+        let releasing = build_escrow(EscrowStatus::Settling(Release));
+        assert!(releasing.can_settle(Release, Buyer));
+        assert!(!releasing.can_settle(Refund, Deadline));
+        assert_eq!(releasing.next_status(Dispute, Seller), None);
+        assert_eq!(releasing.unsettled_status(), EscrowStatus::Funded);
+
+        let mut refunding = build_escrow(EscrowStatus::Settling(Refund));
+        refunding.events.push(EscrowEvent {
+            action: Dispute,
+            actor: Seller,
+            actor_identifier: Some("seller".to_string()),
+            tx_id: None,
+            at: 0,
+        });
+        assert!(refunding.can_settle(Refund, Arbiter));
+        assert!(!refunding.can_settle(Refund, Seller));
+        assert_eq!(refunding.unsettled_status(), EscrowStatus::Disputed);
+    }
+
+    #[test]
+    fn test_escrow_parties() {
+        let escrow = build_escrow(EscrowStatus::Funded);
+
+        assert_eq!(escrow.party_of("buyer"), Some(EscrowActor::Buyer));
+        assert_eq!(escrow.party_of("seller"), Some(EscrowActor::Seller));
+        assert_eq!(escrow.party_of("other"), None);
+
+        assert!(!escrow.is_past_deadline(999));
+        assert!(escrow.is_past_deadline(1_000));
+    }
+}
@@ -0,0 +1,160 @@
+use std::{fmt::Display, str::FromStr, sync::Arc};
+
+use spectre_wallet_core::rpc::RpcApi;
+
+use crate::{error::Error, result::Result};
+
+// minimum relay feerate (sompi per gram of mass), already covered by the wallet base fees
+const MINIMUM_FEERATE: f64 = 1.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FeePriority {
+    Low,
+    Normal,
+    High,
+    // explicit priority fee in sompi
+    Custom(u64),
+}
+
+impl FromStr for FeePriority {
+    type Err = Error;
+
+    /**
+     * `low`, `normal`, `high` or an explicit amount in sompi
+     */
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "low" => Ok(FeePriority::Low),
+            "normal" => Ok(FeePriority::Normal),
+            "high" => Ok(FeePriority::High),
+            amount => match amount.parse::<u64>() {
+                Ok(amount_sompi) if amount_sompi > 0 => Ok(FeePriority::Custom(amount_sompi)),
+                _ => Err(Error::custom(format!(
+                    "Fee priority must be low, normal, high or an amount in sompi: '{value}'"
+                ))),
+            },
+        }
+    }
+}
+
+impl Display for FeePriority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FeePriority::Low => write!(f, "low"),
+            FeePriority::Normal => write!(f, "normal"),
+            FeePriority::High => write!(f, "high"),
+            FeePriority::Custom(_) => write!(f, "custom"),
+        }
+    }
+}
+
+/**
+ * operator defaults applied to every spend
+ */
+#[derive(Debug, Clone)]
+pub struct FeePolicy {
+    default_priority: FeePriority,
+    max_priority_fee_sompi: u64,
+}
+
+impl FeePolicy {
+    pub fn new(default_priority: FeePriority, max_priority_fee_sompi: u64) -> Self {
+        FeePolicy {
+            default_priority,
+            max_priority_fee_sompi,
+        }
+    }
+
+    pub fn default_priority(&self) -> FeePriority {
+        self.default_priority
+    }
+
+    pub fn max_priority_fee_sompi(&self) -> u64 {
+        self.max_priority_fee_sompi
+    }
+
+    /**
+     * priority fee (in sompi) to add on top of `base_fees`, the minimum fees of the transaction
+     * estimated by a dry-run of the generator (see `estimate_fees`)
+     */
+    pub async fn resolve_priority_fee(
+        &self,
+        rpc_api: Arc<dyn RpcApi>,
+        priority: Option<FeePriority>,
+        base_fees: u64,
+    ) -> Result<u64> {
+        let priority = priority.unwrap_or(self.default_priority);
+
+        let feerate = match priority {
+            FeePriority::Custom(priority_fee_sompi) => {
+                return self.ensure_below_max(priority_fee_sompi)
+            }
+            priority => {
+                let estimate = rpc_api.get_fee_estimate().await?;
+
+                match priority {
+                    FeePriority::Low => estimate.low_buckets.first(),
+                    FeePriority::Normal => estimate.normal_buckets.first(),
+                    _ => Some(&estimate.priority_bucket),
+                }
+                .map(|bucket| bucket.feerate)
+                .unwrap_or(MINIMUM_FEERATE)
+            }
+        };
+
+        // estimated feerates are capped by the operator maximum instead of failing
+        Ok(compute_priority_fee(feerate, base_fees).min(self.max_priority_fee_sompi))
+    }
+
+    fn ensure_below_max(&self, priority_fee_sompi: u64) -> Result<u64> {
+        if priority_fee_sompi > self.max_priority_fee_sompi {
+            return Err(Error::custom(format!(
+                "Priority fee exceeds the maximum of {} sompi allowed by the operator",
+                self.max_priority_fee_sompi
+            )));
+        }
+
+        Ok(priority_fee_sompi)
+    }
+}
+
+/**
+ * base fees are paid at the minimum feerate, so they are proportional to the transaction mass
+ */
+pub fn compute_priority_fee(feerate: f64, base_fees: u64) -> u64 {
+    if feerate <= MINIMUM_FEERATE {
+        return 0;
+    }
+
+    ((feerate - MINIMUM_FEERATE) * base_fees as f64).ceil() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fee_priority() {
+        assert_eq!(FeePriority::from_str("low").unwrap(), FeePriority::Low);
+        assert_eq!(
+            FeePriority::from_str(" Normal ").unwrap(),
+            FeePriority::Normal
+        );
+        assert_eq!(FeePriority::from_str("HIGH").unwrap(), FeePriority::High);
+        assert_eq!(
+            FeePriority::from_str("5000").unwrap(),
+            FeePriority::Custom(5_000)
+        );
+        assert!(FeePriority::from_str("0.5").is_err());
+        assert!(FeePriority::from_str("fast").is_err());
+        assert!(FeePriority::from_str("0").is_err());
+    }
+
+    #[test]
+    fn test_compute_priority_fee() {
+        assert_eq!(compute_priority_fee(0.5, 2_000), 0);
+        assert_eq!(compute_priority_fee(1.0, 2_000), 0);
+        assert_eq!(compute_priority_fee(3.0, 2_000), 4_000);
+        assert_eq!(compute_priority_fee(1.25, 2_001), 501);
+    }
+}
@@ -0,0 +1,102 @@
+use std::{
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use crate::{error::Error, result::Result};
+
+/**
+ * user who cannot spend through the bot until unfrozen by an operator
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FrozenUser {
+    pub owner_identifier: String,
+    pub reason: String,
+    pub frozen_by: String,
+    pub frozen_at: u64,
+}
+
+#[derive(Debug)]
+pub struct FrozenUserStore {
+    users: RwLock<Vec<FrozenUser>>,
+    path_buf: PathBuf,
+}
+
+impl FrozenUserStore {
+    pub fn new(path_buf: &PathBuf) -> Result<Self> {
+        let path = Path::new(path_buf);
+
+        let file = match File::open(path) {
+            Ok(f) => f,
+            Err(_) => {
+                let mut created_file = File::create(path)?;
+
+                created_file.write_all(b"[]")?;
+
+                File::open(path)?
+            }
+        };
+
+        let users: Vec<FrozenUser> = serde_json::from_reader(file)?;
+
+        Ok(FrozenUserStore {
+            users: RwLock::new(users),
+            path_buf: path_buf.clone(),
+        })
+    }
+
+    fn persist(&self, users: &[FrozenUser]) -> Result<()> {
+        let file = File::create(Path::new(&self.path_buf))?;
+
+        serde_json::to_writer(file, users)?;
+
+        Ok(())
+    }
+
+    pub async fn freeze(&self, frozen_user: &FrozenUser) -> Result<()> {
+        let mut users = self.users.write().await;
+
+        if users
+            .iter()
+            .any(|u| u.owner_identifier == frozen_user.owner_identifier)
+        {
+            return Err(Error::UserAlreadyFrozen());
+        }
+
+        users.push(frozen_user.clone());
+
+        self.persist(&users)
+    }
+
+    pub async fn unfreeze(&self, owner_identifier: &str) -> Result<FrozenUser> {
+        let mut users = self.users.write().await;
+
+        let index = users
+            .iter()
+            .position(|u| u.owner_identifier == owner_identifier)
+            .ok_or(Error::FrozenUserNotFound())?;
+
+        let unfrozen = users.remove(index);
+
+        self.persist(&users)?;
+
+        Ok(unfrozen)
+    }
+
+    pub async fn find(&self, owner_identifier: &str) -> Option<FrozenUser> {
+        let users = self.users.read().await;
+
+        users
+            .iter()
+            .find(|u| u.owner_identifier == owner_identifier)
+            .cloned()
+    }
+
+    pub async fn is_frozen(&self, owner_identifier: &str) -> bool {
+        self.find(owner_identifier).await.is_some()
+    }
+}
@@ -0,0 +1,355 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use spectre_consensus_core::constants::SOMPI_PER_SPECTRE;
+use tokio::sync::RwLock;
+
+use crate::{error::Error, result::Result};
+
+// until an admin sets another reaction tip cap for the guild
+pub const DEFAULT_REACTION_DAILY_CAP_SOMPI: u64 = 10 * SOMPI_PER_SPECTRE;
+
+fn default_reaction_daily_cap_sompi() -> u64 {
+    DEFAULT_REACTION_DAILY_CAP_SOMPI
+}
+
+/**
+ * settings of a guild, every field defaults to the behaviour of the bot before it was configured
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GuildConfig {
+    pub guild_id: u64,
+    // receipts are posted privately when disabled
+    pub public_receipts: bool,
+    // channels where public receipts may be posted, any channel when empty
+    pub receipt_channel_ids: Vec<u64>,
+    pub min_tip_sompi: Option<u64>,
+    pub max_tip_sompi: Option<u64>,
+    // replies that would be public are ephemeral instead
+    pub ephemeral_replies: bool,
+    // role allowed to use admin commands in addition to the Manage Server permission
+    pub admin_role_id: Option<u64>,
+    // reaction tip amount of each emoji, keyed by custom emoji id or unicode value
+    #[serde(default)]
+    pub reaction_amounts_by_emoji: HashMap<String, u64>,
+    // maximum spent by a reactor per day, 0 disables reaction tips
+    #[serde(default = "default_reaction_daily_cap_sompi")]
+    pub reaction_daily_cap_sompi: u64,
+}
+
+/**
+ * reaction tip settings, kept in `reaction_tips.json` before they were part of the guild config
+ */
+#[derive(Deserialize)]
+struct LegacyReactionTipConfig {
+    guild_id: u64,
+    amounts_by_emoji: HashMap<String, u64>,
+    daily_cap_sompi: u64,
+}
+
+impl GuildConfig {
+    pub fn new(guild_id: u64) -> Self {
+        GuildConfig {
+            guild_id,
+            public_receipts: true,
+            receipt_channel_ids: vec![],
+            min_tip_sompi: None,
+            max_tip_sompi: None,
+            ephemeral_replies: false,
+            admin_role_id: None,
+            reaction_amounts_by_emoji: HashMap::new(),
+            reaction_daily_cap_sompi: DEFAULT_REACTION_DAILY_CAP_SOMPI,
+        }
+    }
+
+    pub fn allows_public_receipt(&self, channel_id: u64) -> bool {
+        self.public_receipts
+            && (self.receipt_channel_ids.is_empty()
+                || self.receipt_channel_ids.contains(&channel_id))
+    }
+
+    pub fn check_tip_amount(&self, amount_sompi: u64) -> Result<()> {
+        if self
+            .min_tip_sompi
+            .is_some_and(|min_tip_sompi| amount_sompi < min_tip_sompi)
+        {
+            return Err(Error::TipAmountBelowMinimum());
+        }
+
+        if self
+            .max_tip_sompi
+            .is_some_and(|max_tip_sompi| amount_sompi > max_tip_sompi)
+        {
+            return Err(Error::TipAmountAboveMaximum());
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct GuildConfigStore {
+    configs: RwLock<Vec<GuildConfig>>,
+    path_buf: PathBuf,
+}
+
+impl GuildConfigStore {
+    /**
+     * reaction tip settings found at `legacy_reaction_tip_path_buf` are merged once into the configs
+     */
+    pub fn new(path_buf: &PathBuf, legacy_reaction_tip_path_buf: &PathBuf) -> Result<Self> {
+        let path = Path::new(path_buf);
+
+        let file = match File::open(path) {
+            Ok(f) => f,
+            Err(_) => {
+                let mut created_file = File::create(path)?;
+
+                created_file.write_all(b"[]")?;
+
+                File::open(path)?
+            }
+        };
+
+        let mut configs: Vec<GuildConfig> = serde_json::from_reader(file)?;
+
+        if let Ok(legacy_file) = File::open(Path::new(legacy_reaction_tip_path_buf)) {
+            let legacy_configs: Vec<LegacyReactionTipConfig> =
+                serde_json::from_reader(legacy_file)?;
+
+            for legacy_config in legacy_configs {
+                let index = match configs
+                    .iter()
+                    .position(|config| config.guild_id == legacy_config.guild_id)
+                {
+                    Some(index) => index,
+                    None => {
+                        configs.push(GuildConfig::new(legacy_config.guild_id));
+                        configs.len() - 1
+                    }
+                };
+
+                configs[index].reaction_amounts_by_emoji = legacy_config.amounts_by_emoji;
+                configs[index].reaction_daily_cap_sompi = legacy_config.daily_cap_sompi;
+            }
+
+            let file = File::create(path)?;
+
+            serde_json::to_writer(file, &configs)?;
+
+            fs::remove_file(Path::new(legacy_reaction_tip_path_buf))?;
+        }
+
+        Ok(GuildConfigStore {
+            configs: RwLock::new(configs),
+            path_buf: path_buf.clone(),
+        })
+    }
+
+    /**
+     * apply `update` to the config of the guild, created if missing
+     */
+    async fn update<F: FnOnce(&mut GuildConfig)>(
+        &self,
+        guild_id: u64,
+        update: F,
+    ) -> Result<GuildConfig> {
+        let mut configs = self.configs.write().await;
+
+        let index = match configs
+            .iter()
+            .position(|config| config.guild_id == guild_id)
+        {
+            Some(index) => index,
+            None => {
+                configs.push(GuildConfig::new(guild_id));
+                configs.len() - 1
+            }
+        };
+
+        update(&mut configs[index]);
+
+        let updated = configs[index].clone();
+
+        let file = File::create(Path::new(&self.path_buf))?;
+
+        serde_json::to_writer(file, &*configs)?;
+
+        Ok(updated)
+    }
+
+    pub async fn set_public_receipts(
+        &self,
+        guild_id: u64,
+        public_receipts: bool,
+    ) -> Result<GuildConfig> {
+        self.update(guild_id, |config| config.public_receipts = public_receipts)
+            .await
+    }
+
+    pub async fn add_receipt_channel(&self, guild_id: u64, channel_id: u64) -> Result<GuildConfig> {
+        self.update(guild_id, |config| {
+            if !config.receipt_channel_ids.contains(&channel_id) {
+                config.receipt_channel_ids.push(channel_id);
+            }
+        })
+        .await
+    }
+
+    pub async fn remove_receipt_channel(
+        &self,
+        guild_id: u64,
+        channel_id: u64,
+    ) -> Result<GuildConfig> {
+        self.update(guild_id, |config| {
+            config.receipt_channel_ids.retain(|id| *id != channel_id)
+        })
+        .await
+    }
+
+    pub async fn set_tip_limits(
+        &self,
+        guild_id: u64,
+        min_tip_sompi: Option<u64>,
+        max_tip_sompi: Option<u64>,
+    ) -> Result<GuildConfig> {
+        self.update(guild_id, |config| {
+            config.min_tip_sompi = min_tip_sompi;
+            config.max_tip_sompi = max_tip_sompi;
+        })
+        .await
+    }
+
+    pub async fn set_ephemeral_replies(
+        &self,
+        guild_id: u64,
+        ephemeral_replies: bool,
+    ) -> Result<GuildConfig> {
+        self.update(guild_id, |config| {
+            config.ephemeral_replies = ephemeral_replies
+        })
+        .await
+    }
+
+    pub async fn set_admin_role(
+        &self,
+        guild_id: u64,
+        admin_role_id: Option<u64>,
+    ) -> Result<GuildConfig> {
+        self.update(guild_id, |config| config.admin_role_id = admin_role_id)
+            .await
+    }
+
+    pub async fn set_reaction_emoji_amount(
+        &self,
+        guild_id: u64,
+        emoji: &str,
+        amount_sompi: u64,
+    ) -> Result<GuildConfig> {
+        self.update(guild_id, |config| {
+            config
+                .reaction_amounts_by_emoji
+                .insert(emoji.to_string(), amount_sompi);
+        })
+        .await
+    }
+
+    pub async fn remove_reaction_emoji(&self, guild_id: u64, emoji: &str) -> Result<GuildConfig> {
+        self.update(guild_id, |config| {
+            config.reaction_amounts_by_emoji.remove(emoji);
+        })
+        .await
+    }
+
+    pub async fn set_reaction_daily_cap(
+        &self,
+        guild_id: u64,
+        daily_cap_sompi: u64,
+    ) -> Result<GuildConfig> {
+        self.update(guild_id, |config| {
+            config.reaction_daily_cap_sompi = daily_cap_sompi
+        })
+        .await
+    }
+
+    pub async fn find_by_guild_id(&self, guild_id: u64) -> GuildConfig {
+        let configs = self.configs.read().await;
+
+        configs
+            .iter()
+            .find(|config| config.guild_id == guild_id)
+            .cloned()
+            .unwrap_or(GuildConfig::new(guild_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tip_limits() {
+        let mut config = GuildConfig::new(1);
+
+        assert!(config.check_tip_amount(1).is_ok());
+
+        config.min_tip_sompi = Some(10);
+        config.max_tip_sompi = Some(100);
+
+        assert!(matches!(
+            config.check_tip_amount(9),
+            Err(Error::TipAmountBelowMinimum())
+        ));
+        assert!(config.check_tip_amount(10).is_ok());
+        assert!(config.check_tip_amount(100).is_ok());
+        assert!(matches!(
+            config.check_tip_amount(101),
+            Err(Error::TipAmountAboveMaximum())
+        ));
+    }
+
+    #[test]
+    fn test_legacy_reaction_tip_migration() {
+        let dir = std::env::temp_dir();
+        let path_buf = dir.join(format!("guild_configs_test_{}.json", std::process::id()));
+        let legacy_path_buf = dir.join(format!("reaction_tips_test_{}.json", std::process::id()));
+
+        std::fs::write(&path_buf, r#"[{"guild_id":1,"public_receipts":false,"receipt_channel_ids":[],"min_tip_sompi":null,"max_tip_sompi":null,"ephemeral_replies":false,"admin_role_id":null}]"#).unwrap();
+        std::fs::write(
+            &legacy_path_buf,
+            r#"[{"guild_id":1,"amounts_by_emoji":{"a":5},"daily_cap_sompi":50},{"guild_id":2,"amounts_by_emoji":{},"daily_cap_sompi":0}]"#,
+        )
+        .unwrap();
+
+        let store = GuildConfigStore::new(&path_buf, &legacy_path_buf).unwrap();
+        let configs = store.configs.try_read().unwrap();
+
+        assert!(!configs[0].public_receipts);
+        assert_eq!(configs[0].reaction_amounts_by_emoji.get("a"), Some(&5));
+        assert_eq!(configs[0].reaction_daily_cap_sompi, 50);
+        assert_eq!(configs[1].guild_id, 2);
+        assert_eq!(configs[1].reaction_daily_cap_sompi, 0);
+        assert!(!legacy_path_buf.exists());
+
+        let _ = std::fs::remove_file(&path_buf);
+    }
+
+    #[test]
+    fn test_public_receipt_channels() {
+        let mut config = GuildConfig::new(1);
+
+        assert!(config.allows_public_receipt(1));
+
+        config.receipt_channel_ids = vec![2];
+        assert!(!config.allows_public_receipt(1));
+        assert!(config.allows_public_receipt(2));
+
+        config.public_receipts = false;
+        assert!(!config.allows_public_receipt(2));
+    }
+}
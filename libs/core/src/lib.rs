@@ -1,8 +1,28 @@
+pub mod activity_tracker;
+pub mod audit_log;
+pub mod balance_role;
+pub mod bounty;
 pub mod error;
+pub mod escrow;
+pub mod fee_policy;
+pub mod frozen_user;
+pub mod guild_config;
+pub mod linked_address_metadata;
+pub mod opt_out;
+pub mod outbox;
 pub mod owned_wallet_metadata;
+pub mod payment_request;
+pub mod payment_uri;
+pub mod reaction_tip_config;
 pub mod result;
+pub mod role_shop;
+pub mod schedule;
+pub mod scheduler;
 pub mod tip_context;
+pub mod tip_custodial_wallet;
 pub mod tip_owned_wallet;
 pub mod tip_transition_wallet;
+pub mod transaction_tracker;
 pub mod transition_wallet_metadata;
+pub mod treasury;
 pub mod utils;
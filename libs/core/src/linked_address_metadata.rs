@@ -0,0 +1,144 @@
+use std::{
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use spectre_addresses::Address;
+use tokio::sync::RwLock;
+
+use crate::{error::Error, result::Result};
+
+/**
+ * external address whose ownership has been proven by signing a challenge
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LinkedAddressMetadata {
+    pub owner_identifier: String,
+    pub address: Address,
+    pub linked_at: u64,
+}
+
+impl LinkedAddressMetadata {
+    pub fn new(owner_identifier: String, address: Address, linked_at: u64) -> Self {
+        LinkedAddressMetadata {
+            owner_identifier,
+            address,
+            linked_at,
+        }
+    }
+}
+
+/**
+ * challenge issued to an owner, to be signed with the key of `address`
+ */
+#[derive(Debug, Clone)]
+pub struct LinkChallenge {
+    pub address: Address,
+    pub message: String,
+    pub expires_at: u64,
+}
+
+#[derive(Debug)]
+pub struct LinkedAddressMetadataStore {
+    metadata: RwLock<Vec<LinkedAddressMetadata>>,
+    path_buf: PathBuf,
+}
+
+impl LinkedAddressMetadataStore {
+    pub fn new(path_buf: &PathBuf) -> Result<Self> {
+        let path = Path::new(path_buf);
+
+        let file = match File::open(path) {
+            Ok(f) => f,
+            Err(_) => {
+                let mut created_file = File::create(path)?;
+
+                created_file.write_all(b"[]")?;
+
+                File::open(path)?
+            }
+        };
+
+        let metadata: Vec<LinkedAddressMetadata> = serde_json::from_reader(file)?;
+
+        Ok(LinkedAddressMetadataStore {
+            metadata: RwLock::new(metadata),
+            path_buf: path_buf.clone(),
+        })
+    }
+
+    pub async fn add(&self, linked_address_metadata: &LinkedAddressMetadata) -> Result<()> {
+        let mut metadata = self.metadata.write().await;
+
+        if metadata
+            .iter()
+            .any(|metadata| metadata.address == linked_address_metadata.address)
+        {
+            return Err(Error::LinkedAddressAlreadyExists());
+        }
+
+        let file = File::create(Path::new(&self.path_buf))?;
+
+        metadata.push(linked_address_metadata.clone());
+
+        let copied = metadata.clone();
+
+        serde_json::to_writer(file, &copied)?;
+
+        Ok(())
+    }
+
+    pub async fn remove(&self, owner_identifier: &str, address: &Address) -> Result<()> {
+        let mut metadata = self.metadata.write().await;
+
+        let metadata_to_delete = metadata.iter().position(|metadata| {
+            metadata.owner_identifier == owner_identifier && metadata.address == *address
+        });
+
+        if metadata_to_delete.is_none() {
+            return Err(Error::LinkedAddressNotFound());
+        }
+
+        metadata.remove(metadata_to_delete.unwrap());
+
+        let file = File::create(Path::new(&self.path_buf))?;
+
+        let copied = metadata.clone();
+
+        serde_json::to_writer(file, &copied)?;
+
+        Ok(())
+    }
+
+    /**
+     * linked addresses of an owner, most recently linked first
+     */
+    pub async fn find_linked_addresses_by_owner_identifier(
+        &self,
+        owner_identifier: &str,
+    ) -> Result<Vec<LinkedAddressMetadata>> {
+        let all_metadata = self.metadata.read().await;
+        let mut metadata: Vec<LinkedAddressMetadata> = all_metadata
+            .iter()
+            .filter(|metadata| metadata.owner_identifier == owner_identifier)
+            .cloned()
+            .collect();
+
+        metadata.sort_by(|a, b| b.linked_at.cmp(&a.linked_at));
+
+        Ok(metadata)
+    }
+
+    pub async fn find_default_linked_address_by_owner_identifier(
+        &self,
+        owner_identifier: &str,
+    ) -> Result<LinkedAddressMetadata> {
+        self.find_linked_addresses_by_owner_identifier(owner_identifier)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or(Error::LinkedAddressNotFound())
+    }
+}
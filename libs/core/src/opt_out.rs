@@ -0,0 +1,68 @@
+use std::{
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use tokio::sync::RwLock;
+
+use crate::result::Result;
+
+/**
+ * owners excluded from mass tips (role tips, rains)
+ */
+#[derive(Debug)]
+pub struct OptOutStore {
+    owner_identifiers: RwLock<Vec<String>>,
+    path_buf: PathBuf,
+}
+
+impl OptOutStore {
+    pub fn new(path_buf: &PathBuf) -> Result<Self> {
+        let path = Path::new(path_buf);
+
+        let file = match File::open(path) {
+            Ok(f) => f,
+            Err(_) => {
+                let mut created_file = File::create(path)?;
+
+                created_file.write_all(b"[]")?;
+
+                File::open(path)?
+            }
+        };
+
+        let owner_identifiers: Vec<String> = serde_json::from_reader(file)?;
+
+        Ok(OptOutStore {
+            owner_identifiers: RwLock::new(owner_identifiers),
+            path_buf: path_buf.clone(),
+        })
+    }
+
+    pub async fn set_opted_out(&self, owner_identifier: &str, opted_out: bool) -> Result<()> {
+        let mut owner_identifiers = self.owner_identifiers.write().await;
+
+        owner_identifiers.retain(|identifier| identifier != owner_identifier);
+
+        if opted_out {
+            owner_identifiers.push(owner_identifier.to_string());
+        }
+
+        let file = File::create(Path::new(&self.path_buf))?;
+
+        let copied = owner_identifiers.clone();
+
+        serde_json::to_writer(file, &copied)?;
+
+        Ok(())
+    }
+
+    pub async fn is_opted_out(&self, owner_identifier: &str) -> bool {
+        let owner_identifiers = self.owner_identifiers.read().await;
+
+        owner_identifiers
+            .iter()
+            .any(|identifier| identifier == owner_identifier)
+    }
+}
@@ -0,0 +1,244 @@
+use std::{
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use crate::{error::Error, result::Result};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum OutboxEntryStatus {
+    // recorded before signing, transactions may have been signed but none is known as submitted
+    Pending,
+    // at least one transaction has been submitted to the node
+    Submitted,
+    // the receipt has been posted on discord
+    Delivered,
+}
+
+/**
+ * spend operation keyed by the idempotency key of the discord interaction,
+ * kept until its receipt is delivered so it survives a crash of the bot
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OutboxEntry {
+    pub idempotency_key: String,
+    pub owner_identifier: String,
+    pub channel_id: u64,
+    // public receipts are re-posted in the channel, private ones are sent by direct message
+    pub public: bool,
+    pub receipt: String,
+    pub tx_ids: Vec<String>,
+    // sink of the virtual chain when the first transaction was signed, acceptance is searched from there
+    #[serde(default)]
+    pub chain_start_hash: Option<String>,
+    pub status: OutboxEntryStatus,
+    // reason the operation stopped, submitted transactions of a failed operation are a partial send
+    #[serde(default)]
+    pub error: Option<String>,
+    pub created_at: u64,
+}
+
+impl OutboxEntry {
+    pub fn new(
+        idempotency_key: String,
+        owner_identifier: String,
+        channel_id: u64,
+        public: bool,
+        receipt: String,
+        created_at: u64,
+    ) -> Self {
+        OutboxEntry {
+            idempotency_key,
+            owner_identifier,
+            channel_id,
+            public,
+            receipt,
+            tx_ids: vec![],
+            chain_start_hash: None,
+            status: OutboxEntryStatus::Pending,
+            error: None,
+            created_at,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct OutboxStore {
+    entries: RwLock<Vec<OutboxEntry>>,
+    path_buf: PathBuf,
+}
+
+impl OutboxStore {
+    pub fn new(path_buf: &PathBuf) -> Result<Self> {
+        let path = Path::new(path_buf);
+
+        let file = match File::open(path) {
+            Ok(f) => f,
+            Err(_) => {
+                let mut created_file = File::create(path)?;
+
+                created_file.write_all(b"[]")?;
+
+                File::open(path)?
+            }
+        };
+
+        let entries: Vec<OutboxEntry> = serde_json::from_reader(file)?;
+
+        Ok(OutboxStore {
+            entries: RwLock::new(entries),
+            path_buf: path_buf.clone(),
+        })
+    }
+
+    fn persist(&self, entries: &[OutboxEntry]) -> Result<()> {
+        let file = File::create(Path::new(&self.path_buf))?;
+
+        serde_json::to_writer(file, entries)?;
+
+        Ok(())
+    }
+
+    /**
+     * fails if the idempotency key is already known, the operation must not be executed twice
+     */
+    pub async fn add(&self, entry: &OutboxEntry) -> Result<()> {
+        let mut entries = self.entries.write().await;
+
+        if entries
+            .iter()
+            .any(|e| e.idempotency_key == entry.idempotency_key)
+        {
+            return Err(Error::OutboxEntryAlreadyExists());
+        }
+
+        entries.push(entry.clone());
+
+        self.persist(&entries)
+    }
+
+    /**
+     * recorded before the submission, so a transaction broadcasted right before a crash is not lost
+     */
+    pub async fn record_signed_tx_id(
+        &self,
+        idempotency_key: &str,
+        tx_id: &str,
+        chain_start_hash: &str,
+    ) -> Result<()> {
+        let mut entries = self.entries.write().await;
+
+        let entry = entries
+            .iter_mut()
+            .find(|e| e.idempotency_key == idempotency_key)
+            .ok_or(Error::OutboxEntryNotFound())?;
+
+        if !entry.tx_ids.iter().any(|id| id == tx_id) {
+            entry.tx_ids.push(tx_id.to_string());
+        }
+
+        if entry.chain_start_hash.is_none() {
+            entry.chain_start_hash = Some(chain_start_hash.to_string());
+        }
+
+        self.persist(&entries)
+    }
+
+    pub async fn record_tx_ids(&self, idempotency_key: &str, tx_ids: &[String]) -> Result<()> {
+        let mut entries = self.entries.write().await;
+
+        let entry = entries
+            .iter_mut()
+            .find(|e| e.idempotency_key == idempotency_key)
+            .ok_or(Error::OutboxEntryNotFound())?;
+
+        for tx_id in tx_ids {
+            if !entry.tx_ids.contains(tx_id) {
+                entry.tx_ids.push(tx_id.clone());
+            }
+        }
+
+        if entry.status == OutboxEntryStatus::Pending && !entry.tx_ids.is_empty() {
+            entry.status = OutboxEntryStatus::Submitted;
+        }
+
+        self.persist(&entries)
+    }
+
+    pub async fn record_error(&self, idempotency_key: &str, error: &str) -> Result<()> {
+        let mut entries = self.entries.write().await;
+
+        let entry = entries
+            .iter_mut()
+            .find(|e| e.idempotency_key == idempotency_key)
+            .ok_or(Error::OutboxEntryNotFound())?;
+
+        entry.error = Some(error.to_string());
+
+        self.persist(&entries)
+    }
+
+    pub async fn mark_delivered(&self, idempotency_key: &str) -> Result<()> {
+        let mut entries = self.entries.write().await;
+
+        let entry = entries
+            .iter_mut()
+            .find(|e| e.idempotency_key == idempotency_key)
+            .ok_or(Error::OutboxEntryNotFound())?;
+
+        entry.status = OutboxEntryStatus::Delivered;
+
+        self.persist(&entries)
+    }
+
+    /**
+     * forget an operation that failed before anything was broadcasted
+     */
+    pub async fn remove(&self, idempotency_key: &str) -> Result<()> {
+        let mut entries = self.entries.write().await;
+
+        let entry_to_delete = entries
+            .iter()
+            .position(|e| e.idempotency_key == idempotency_key)
+            .ok_or(Error::OutboxEntryNotFound())?;
+
+        entries.remove(entry_to_delete);
+
+        self.persist(&entries)
+    }
+
+    /**
+     * delivered entries are kept for deduplication until they are older than `created_before`
+     */
+    pub async fn prune_delivered(&self, created_before: u64) -> Result<()> {
+        let mut entries = self.entries.write().await;
+
+        entries
+            .retain(|e| e.status != OutboxEntryStatus::Delivered || e.created_at >= created_before);
+
+        self.persist(&entries)
+    }
+
+    pub async fn find_by_idempotency_key(&self, idempotency_key: &str) -> Option<OutboxEntry> {
+        let entries = self.entries.read().await;
+
+        entries
+            .iter()
+            .find(|e| e.idempotency_key == idempotency_key)
+            .cloned()
+    }
+
+    pub async fn find_undelivered(&self) -> Vec<OutboxEntry> {
+        let entries = self.entries.read().await;
+
+        entries
+            .iter()
+            .filter(|e| e.status != OutboxEntryStatus::Delivered)
+            .cloned()
+            .collect()
+    }
+}
@@ -14,6 +14,9 @@ use crate::{error::Error, result::Result};
 pub struct OwnedWalletMetadata {
     pub owner_identifier: String,
     pub receive_address: Address,
+    // the local file of a watch-only wallet is protected by a secret derived from the operator key
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub watch_only: bool,
 }
 
 impl OwnedWalletMetadata {
@@ -21,8 +24,21 @@ impl OwnedWalletMetadata {
         OwnedWalletMetadata {
             owner_identifier,
             receive_address,
+            watch_only: false,
         }
     }
+
+    pub fn new_watch_only(owner_identifier: String, receive_address: Address) -> Self {
+        OwnedWalletMetadata {
+            owner_identifier,
+            receive_address,
+            watch_only: true,
+        }
+    }
+
+    pub fn is_watch_only(&self) -> bool {
+        self.watch_only
+    }
 }
 
 #[derive(Debug)]
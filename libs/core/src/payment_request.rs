@@ -0,0 +1,172 @@
+use std::{
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use crate::{error::Error, result::Result};
+
+// open requests are dropped after this delay
+pub const PAYMENT_REQUEST_TTL_SECS: u64 = 24 * 60 * 60;
+
+/**
+ * amount asked by a requester to a payer, keyed by the id of the interaction that created it
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PaymentRequest {
+    pub id: u64,
+    pub requester_identifier: String,
+    pub payer_identifier: String,
+    pub amount_sompi: u64,
+    pub memo: Option<String>,
+    // public message holding the pay and decline buttons
+    pub channel_id: u64,
+    pub message_id: u64,
+    pub created_at: u64,
+    pub expires_at: u64,
+}
+
+impl PaymentRequest {
+    pub fn is_expired(&self, now: u64) -> bool {
+        now >= self.expires_at
+    }
+
+    pub fn involves(&self, owner_identifier: &str) -> bool {
+        self.requester_identifier == owner_identifier || self.payer_identifier == owner_identifier
+    }
+}
+
+#[derive(Debug)]
+pub struct PaymentRequestStore {
+    requests: RwLock<Vec<PaymentRequest>>,
+    path_buf: PathBuf,
+}
+
+impl PaymentRequestStore {
+    pub fn new(path_buf: &PathBuf) -> Result<Self> {
+        let path = Path::new(path_buf);
+
+        let file = match File::open(path) {
+            Ok(f) => f,
+            Err(_) => {
+                let mut created_file = File::create(path)?;
+
+                created_file.write_all(b"[]")?;
+
+                File::open(path)?
+            }
+        };
+
+        let requests: Vec<PaymentRequest> = serde_json::from_reader(file)?;
+
+        Ok(PaymentRequestStore {
+            requests: RwLock::new(requests),
+            path_buf: path_buf.clone(),
+        })
+    }
+
+    fn persist(&self, requests: &[PaymentRequest]) -> Result<()> {
+        let file = File::create(Path::new(&self.path_buf))?;
+
+        serde_json::to_writer(file, requests)?;
+
+        Ok(())
+    }
+
+    pub async fn add(&self, request: &PaymentRequest) -> Result<()> {
+        let mut requests = self.requests.write().await;
+
+        requests.push(request.clone());
+
+        self.persist(&requests)
+    }
+
+    /**
+     * close a request (paid, declined or cancelled), only one caller can close it
+     */
+    pub async fn remove(&self, id: u64) -> Result<PaymentRequest> {
+        let mut requests = self.requests.write().await;
+
+        let index = requests
+            .iter()
+            .position(|r| r.id == id)
+            .ok_or(Error::PaymentRequestNotFound())?;
+
+        let request = requests.remove(index);
+
+        self.persist(&requests)?;
+
+        Ok(request)
+    }
+
+    pub async fn find_by_id(&self, id: u64) -> Option<PaymentRequest> {
+        let requests = self.requests.read().await;
+
+        requests.iter().find(|r| r.id == id).cloned()
+    }
+
+    /**
+     * requests sent or received by the owner which are not expired yet, oldest first
+     */
+    pub async fn find_open_by_owner(
+        &self,
+        owner_identifier: &str,
+        now: u64,
+    ) -> Vec<PaymentRequest> {
+        let requests = self.requests.read().await;
+
+        requests
+            .iter()
+            .filter(|r| r.involves(owner_identifier) && !r.is_expired(now))
+            .cloned()
+            .collect()
+    }
+
+    /**
+     * drop expired requests and return them, so their messages can be updated
+     */
+    pub async fn prune_expired(&self, now: u64) -> Result<Vec<PaymentRequest>> {
+        let mut requests = self.requests.write().await;
+
+        let (expired, open): (Vec<PaymentRequest>, Vec<PaymentRequest>) =
+            requests.drain(..).partition(|r| r.is_expired(now));
+
+        *requests = open;
+
+        if !expired.is_empty() {
+            self.persist(&requests)?;
+        }
+
+        Ok(expired)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payment_request_expiry() {
+        let request = PaymentRequest {
+            id: 1,
+            requester_identifier: "requester".to_string(),
+            payer_identifier: "payer".to_string(),
+            amount_sompi: 100,
+            memo: None,
+            channel_id: 10,
+            message_id: 20,
+            created_at: 1_000,
+            expires_at: 1_000 + PAYMENT_REQUEST_TTL_SECS,
+        };
+
+        assert!(!request.is_expired(1_000 + PAYMENT_REQUEST_TTL_SECS - 1));
+        assert!(request.is_expired(1_000 + PAYMENT_REQUEST_TTL_SECS));
+
+        assert!(request.involves("requester"));
+        assert!(request.involves("payer"));
+        assert!(!request.involves("other"));
+    }
+}
@@ -0,0 +1,219 @@
+use std::fmt::Display;
+
+use spectre_addresses::Address;
+use spectre_consensus_core::constants::SOMPI_PER_SPECTRE;
+
+use crate::{error::Error, result::Result, utils::try_parse_required_nonzero_spectre_as_sompi_u64};
+
+/**
+ * BIP21-style payment request: `spectre:<address>?amount=<spr>&label=<label>&message=<message>`
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaymentUri {
+    pub address: Address,
+    pub amount_sompi: Option<u64>,
+    pub label: Option<String>,
+    pub message: Option<String>,
+}
+
+impl PaymentUri {
+    pub fn new(
+        address: Address,
+        amount_sompi: Option<u64>,
+        label: Option<String>,
+        message: Option<String>,
+    ) -> Self {
+        PaymentUri {
+            address,
+            amount_sompi,
+            label,
+            message,
+        }
+    }
+
+    /**
+     * parse a payment uri, a plain address is accepted as a uri without parameters
+     */
+    pub fn try_parse(uri: &str) -> Result<Self> {
+        let uri = uri.trim();
+
+        let (address_part, query) = match uri.split_once('?') {
+            Some((address_part, query)) => (address_part, Some(query)),
+            None => (uri, None),
+        };
+
+        let address = Address::try_from(address_part)
+            .map_err(|_| Error::custom(format!("Invalid Spectre address: '{address_part}'")))?;
+
+        let mut payment_uri = PaymentUri::new(address, None, None, None);
+
+        for pair in query.unwrap_or_default().split('&') {
+            if pair.is_empty() {
+                continue;
+            }
+
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = percent_decode(value)?;
+
+            match key {
+                "amount" => {
+                    payment_uri.amount_sompi = Some(
+                        try_parse_required_nonzero_spectre_as_sompi_u64(Some(value))?,
+                    )
+                }
+                "label" => payment_uri.label = Some(value),
+                "message" => payment_uri.message = Some(value),
+                // BIP21: unknown required parameters must make the uri invalid
+                key if key.starts_with("req-") => {
+                    return Err(Error::custom(format!(
+                        "Unsupported payment uri parameter: '{key}'"
+                    )))
+                }
+                _ => (),
+            }
+        }
+
+        Ok(payment_uri)
+    }
+}
+
+impl Display for PaymentUri {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parameters = vec![];
+
+        if let Some(amount_sompi) = self.amount_sompi {
+            parameters.push(format!(
+                "amount={}",
+                format_sompi_as_spectre_decimal(amount_sompi)
+            ));
+        }
+
+        if let Some(label) = &self.label {
+            parameters.push(format!("label={}", percent_encode(label)));
+        }
+
+        if let Some(message) = &self.message {
+            parameters.push(format!("message={}", percent_encode(message)));
+        }
+
+        match parameters.is_empty() {
+            true => write!(f, "{}", self.address),
+            false => write!(f, "{}?{}", self.address, parameters.join("&")),
+        }
+    }
+}
+
+/**
+ * plain decimal representation (no suffix, no thousands separator) as expected in uris
+ */
+pub fn format_sompi_as_spectre_decimal(sompi: u64) -> String {
+    let whole = sompi / SOMPI_PER_SPECTRE;
+    let fraction = sompi % SOMPI_PER_SPECTRE;
+
+    if fraction == 0 {
+        return whole.to_string();
+    }
+
+    let fraction = format!("{:08}", fraction);
+    format!("{}.{}", whole, fraction.trim_end_matches('0'))
+}
+
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+fn percent_decode(value: &str) -> Result<String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+
+    while index < bytes.len() {
+        match bytes[index] {
+            b'%' => {
+                let hex = value
+                    .get(index + 1..index + 3)
+                    .ok_or(Error::custom("Invalid percent encoding in payment uri"))?;
+                let byte = u8::from_str_radix(hex, 16)
+                    .map_err(|_| Error::custom("Invalid percent encoding in payment uri"))?;
+                decoded.push(byte);
+                index += 3;
+            }
+            b'+' => {
+                decoded.push(b' ');
+                index += 1;
+            }
+            byte => {
+                decoded.push(byte);
+                index += 1;
+            }
+        }
+    }
+
+    String::from_utf8(decoded).map_err(|_| Error::custom("Payment uri is not valid utf-8"))
+}
+
+#[cfg(test)]
+mod tests {
+    use spectre_addresses::{Prefix, Version};
+
+    use super::*;
+
+    fn get_address() -> Address {
+        Address::new(Prefix::Mainnet, Version::PubKey, &[7u8; 32])
+    }
+
+    #[test]
+    fn test_parse_plain_address() {
+        let address = get_address();
+        let payment_uri = PaymentUri::try_parse(&address.to_string()).unwrap();
+
+        assert_eq!(payment_uri, PaymentUri::new(address, None, None, None));
+    }
+
+    #[test]
+    fn test_parse_full_uri() {
+        let address = get_address();
+        let payment_uri = PaymentUri::try_parse(&format!(
+            "{}?amount=1.5&label=Spectre%20Tipper&message=thanks+a+lot",
+            address
+        ))
+        .unwrap();
+
+        assert_eq!(payment_uri.address, address);
+        assert_eq!(payment_uri.amount_sompi, Some(150_000_000));
+        assert_eq!(payment_uri.label.as_deref(), Some("Spectre Tipper"));
+        assert_eq!(payment_uri.message.as_deref(), Some("thanks a lot"));
+    }
+
+    #[test]
+    fn test_display_roundtrip() {
+        let payment_uri = PaymentUri::new(
+            get_address(),
+            Some(1_000_000),
+            Some("coffee & cake".into()),
+            None,
+        );
+
+        let formatted = payment_uri.to_string();
+        assert!(formatted.ends_with("?amount=0.01&label=coffee%20%26%20cake"));
+        assert_eq!(PaymentUri::try_parse(&formatted).unwrap(), payment_uri);
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_required_parameter() {
+        assert!(PaymentUri::try_parse(&format!("{}?req-unknown=1", get_address())).is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_zero_amount() {
+        assert!(PaymentUri::try_parse(&format!("{}?amount=0", get_address())).is_err());
+    }
+}
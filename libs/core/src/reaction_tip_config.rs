@@ -0,0 +1,82 @@
+use std::{collections::HashMap, sync::RwLock as StdRwLock};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/**
+ * in-memory amounts spent through reactions by each reactor during the current day
+ */
+#[derive(Debug, Default)]
+pub struct ReactionTipLimiter {
+    // owner identifier -> (day, spent sompi)
+    spent_by_owner: StdRwLock<HashMap<String, (u64, u64)>>,
+}
+
+impl ReactionTipLimiter {
+    pub fn new() -> Self {
+        ReactionTipLimiter::default()
+    }
+
+    /**
+     * reserve `amount_sompi` of the daily cap, returns false if it would be exceeded
+     */
+    pub fn try_spend(
+        &self,
+        owner_identifier: &str,
+        amount_sompi: u64,
+        daily_cap_sompi: u64,
+        now: u64,
+    ) -> bool {
+        let day = now / SECONDS_PER_DAY;
+        let mut spent_by_owner = self.spent_by_owner.write().unwrap();
+
+        let spent = spent_by_owner
+            .entry(owner_identifier.to_string())
+            .or_insert((day, 0));
+
+        if spent.0 != day {
+            *spent = (day, 0);
+        }
+
+        if spent.1 + amount_sompi > daily_cap_sompi {
+            return false;
+        }
+
+        spent.1 += amount_sompi;
+        true
+    }
+
+    /**
+     * give back a reservation whose tip failed
+     */
+    pub fn refund(&self, owner_identifier: &str, amount_sompi: u64, now: u64) {
+        let day = now / SECONDS_PER_DAY;
+        let mut spent_by_owner = self.spent_by_owner.write().unwrap();
+
+        if let Some(spent) = spent_by_owner.get_mut(owner_identifier) {
+            if spent.0 == day {
+                spent.1 = spent.1.saturating_sub(amount_sompi);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limiter_daily_cap() {
+        let limiter = ReactionTipLimiter::new();
+        let now = 10 * SECONDS_PER_DAY;
+
+        assert!(limiter.try_spend("owner", 60, 100, now));
+        assert!(!limiter.try_spend("owner", 60, 100, now));
+        assert!(limiter.try_spend("other", 60, 100, now));
+
+        limiter.refund("owner", 60, now);
+        assert!(limiter.try_spend("owner", 100, 100, now));
+
+        // the cap is reset the next day
+        assert!(limiter.try_spend("owner", 100, 100, now + SECONDS_PER_DAY));
+    }
+}
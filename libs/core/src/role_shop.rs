@@ -0,0 +1,403 @@
+use std::{
+    fmt,
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use crate::{error::Error, result::Result};
+
+// subscribers are reminded this long before their role expires
+pub const ROLE_RENEWAL_REMINDER_SECS: u64 = 3 * 24 * 60 * 60;
+
+/**
+ * role sold by a guild, paid to its treasury
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ShopRole {
+    pub guild_id: u64,
+    pub role_id: u64,
+    pub price_sompi: u64,
+    // permanent role when none
+    pub duration_secs: Option<u64>,
+}
+
+#[derive(Debug)]
+pub struct ShopRoleStore {
+    roles: RwLock<Vec<ShopRole>>,
+    path_buf: PathBuf,
+}
+
+impl ShopRoleStore {
+    pub fn new(path_buf: &PathBuf) -> Result<Self> {
+        let path = Path::new(path_buf);
+
+        let file = match File::open(path) {
+            Ok(f) => f,
+            Err(_) => {
+                let mut created_file = File::create(path)?;
+
+                created_file.write_all(b"[]")?;
+
+                File::open(path)?
+            }
+        };
+
+        let roles: Vec<ShopRole> = serde_json::from_reader(file)?;
+
+        Ok(ShopRoleStore {
+            roles: RwLock::new(roles),
+            path_buf: path_buf.clone(),
+        })
+    }
+
+    fn persist(&self, roles: &[ShopRole]) -> Result<()> {
+        let file = File::create(Path::new(&self.path_buf))?;
+
+        serde_json::to_writer(file, roles)?;
+
+        Ok(())
+    }
+
+    /**
+     * add the role to the catalog, or replace its price and duration
+     */
+    pub async fn set(&self, shop_role: &ShopRole) -> Result<()> {
+        let mut roles = self.roles.write().await;
+
+        roles.retain(|r| !(r.guild_id == shop_role.guild_id && r.role_id == shop_role.role_id));
+        roles.push(shop_role.clone());
+
+        self.persist(&roles)
+    }
+
+    pub async fn remove(&self, guild_id: u64, role_id: u64) -> Result<()> {
+        let mut roles = self.roles.write().await;
+
+        let count = roles.len();
+        roles.retain(|r| !(r.guild_id == guild_id && r.role_id == role_id));
+
+        if roles.len() == count {
+            return Err(Error::ShopRoleNotFound());
+        }
+
+        self.persist(&roles)
+    }
+
+    pub async fn find(&self, guild_id: u64, role_id: u64) -> Option<ShopRole> {
+        let roles = self.roles.read().await;
+
+        roles
+            .iter()
+            .find(|r| r.guild_id == guild_id && r.role_id == role_id)
+            .cloned()
+    }
+
+    pub async fn find_by_guild_id(&self, guild_id: u64) -> Vec<ShopRole> {
+        let roles = self.roles.read().await;
+
+        roles
+            .iter()
+            .filter(|r| r.guild_id == guild_id)
+            .cloned()
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum RolePurchaseStatus {
+    // payment submitted, the role is assigned once it is confirmed
+    Pending,
+    Active,
+    // extended by a later purchase of the same role
+    Renewed,
+    Expired,
+    Failed,
+}
+
+impl fmt::Display for RolePurchaseStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self {
+            RolePurchaseStatus::Pending => "awaiting confirmation",
+            RolePurchaseStatus::Active => "active",
+            RolePurchaseStatus::Renewed => "renewed",
+            RolePurchaseStatus::Expired => "expired",
+            RolePurchaseStatus::Failed => "failed",
+        };
+
+        write!(f, "{}", status)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RolePurchase {
+    pub id: u64,
+    pub guild_id: u64,
+    pub role_id: u64,
+    pub buyer_identifier: String,
+    pub price_sompi: u64,
+    pub duration_secs: Option<u64>,
+    // last transaction of the payment, the one with the output to the treasury
+    pub tx_id: String,
+    pub status: RolePurchaseStatus,
+    pub error: Option<String>,
+    pub created_at: u64,
+    pub expires_at: Option<u64>,
+    // renewal reminder sent
+    pub reminded: bool,
+}
+
+impl RolePurchase {
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires_at.is_some_and(|expires_at| now >= expires_at)
+    }
+
+    pub fn is_reminder_due(&self, now: u64) -> bool {
+        !self.reminded
+            && self.expires_at.is_some_and(|expires_at| {
+                now + ROLE_RENEWAL_REMINDER_SECS >= expires_at && now < expires_at
+            })
+    }
+}
+
+/**
+ * a renewal extends the subscription still running instead of starting from now
+ */
+pub fn next_role_expiry(
+    active_expires_at: Option<u64>,
+    now: u64,
+    duration_secs: Option<u64>,
+) -> Option<u64> {
+    duration_secs.map(|duration_secs| active_expires_at.unwrap_or(now).max(now) + duration_secs)
+}
+
+#[derive(Debug)]
+pub struct RolePurchaseStore {
+    purchases: RwLock<Vec<RolePurchase>>,
+    path_buf: PathBuf,
+}
+
+impl RolePurchaseStore {
+    pub fn new(path_buf: &PathBuf) -> Result<Self> {
+        let path = Path::new(path_buf);
+
+        let file = match File::open(path) {
+            Ok(f) => f,
+            Err(_) => {
+                let mut created_file = File::create(path)?;
+
+                created_file.write_all(b"[]")?;
+
+                File::open(path)?
+            }
+        };
+
+        let purchases: Vec<RolePurchase> = serde_json::from_reader(file)?;
+
+        Ok(RolePurchaseStore {
+            purchases: RwLock::new(purchases),
+            path_buf: path_buf.clone(),
+        })
+    }
+
+    fn persist(&self, purchases: &[RolePurchase]) -> Result<()> {
+        let file = File::create(Path::new(&self.path_buf))?;
+
+        serde_json::to_writer(file, purchases)?;
+
+        Ok(())
+    }
+
+    async fn update<F: FnOnce(&mut RolePurchase)>(
+        &self,
+        id: u64,
+        update: F,
+    ) -> Result<RolePurchase> {
+        let mut purchases = self.purchases.write().await;
+
+        let purchase = purchases
+            .iter_mut()
+            .find(|p| p.id == id)
+            .ok_or(Error::RolePurchaseNotFound())?;
+
+        update(purchase);
+
+        let updated = purchase.clone();
+
+        self.persist(&purchases)?;
+
+        Ok(updated)
+    }
+
+    /**
+     * assign the next id to the purchase and store it
+     */
+    pub async fn add(&self, mut purchase: RolePurchase) -> Result<RolePurchase> {
+        let mut purchases = self.purchases.write().await;
+
+        purchase.id = purchases.iter().map(|p| p.id).max().unwrap_or(0) + 1;
+
+        purchases.push(purchase.clone());
+
+        self.persist(&purchases)?;
+
+        Ok(purchase)
+    }
+
+    /**
+     * the purchase becomes the active subscription of the buyer for its role,
+     * a subscription still running is extended
+     */
+    pub async fn activate(&self, id: u64, now: u64) -> Result<RolePurchase> {
+        let mut purchases = self.purchases.write().await;
+
+        let purchase = purchases
+            .iter()
+            .find(|p| p.id == id)
+            .cloned()
+            .ok_or(Error::RolePurchaseNotFound())?;
+
+        let mut active_expires_at = None;
+
+        for active in purchases.iter_mut().filter(|p| {
+            p.id != id
+                && p.status == RolePurchaseStatus::Active
+                && p.guild_id == purchase.guild_id
+                && p.role_id == purchase.role_id
+                && p.buyer_identifier == purchase.buyer_identifier
+        }) {
+            active.status = RolePurchaseStatus::Renewed;
+            active_expires_at = active_expires_at.max(active.expires_at);
+        }
+
+        let activated = purchases
+            .iter_mut()
+            .find(|p| p.id == id)
+            .ok_or(Error::RolePurchaseNotFound())?;
+
+        activated.status = RolePurchaseStatus::Active;
+        activated.expires_at = next_role_expiry(active_expires_at, now, activated.duration_secs);
+
+        let activated = activated.clone();
+
+        self.persist(&purchases)?;
+
+        Ok(activated)
+    }
+
+    pub async fn mark_failed(&self, id: u64, error: String) -> Result<RolePurchase> {
+        self.update(id, |purchase| {
+            purchase.status = RolePurchaseStatus::Failed;
+            purchase.error = Some(error);
+        })
+        .await
+    }
+
+    pub async fn expire(&self, id: u64) -> Result<RolePurchase> {
+        self.update(id, |purchase| {
+            purchase.status = RolePurchaseStatus::Expired;
+        })
+        .await
+    }
+
+    pub async fn mark_reminded(&self, id: u64) -> Result<RolePurchase> {
+        self.update(id, |purchase| {
+            purchase.reminded = true;
+        })
+        .await
+    }
+
+    /**
+     * purchases whose payment is still to be verified, e.g. after a restart
+     */
+    pub async fn find_pending(&self) -> Vec<RolePurchase> {
+        let purchases = self.purchases.read().await;
+
+        purchases
+            .iter()
+            .filter(|p| p.status == RolePurchaseStatus::Pending)
+            .cloned()
+            .collect()
+    }
+
+    pub async fn find_expired(&self, now: u64) -> Vec<RolePurchase> {
+        let purchases = self.purchases.read().await;
+
+        purchases
+            .iter()
+            .filter(|p| p.status == RolePurchaseStatus::Active && p.is_expired(now))
+            .cloned()
+            .collect()
+    }
+
+    pub async fn find_due_reminders(&self, now: u64) -> Vec<RolePurchase> {
+        let purchases = self.purchases.read().await;
+
+        purchases
+            .iter()
+            .filter(|p| p.status == RolePurchaseStatus::Active && p.is_reminder_due(now))
+            .cloned()
+            .collect()
+    }
+
+    /**
+     * purchases of the buyer in the guild, most recent first
+     */
+    pub async fn find_by_buyer(&self, guild_id: u64, buyer_identifier: &str) -> Vec<RolePurchase> {
+        let purchases = self.purchases.read().await;
+
+        purchases
+            .iter()
+            .rev()
+            .filter(|p| p.guild_id == guild_id && p.buyer_identifier == buyer_identifier)
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_role_expiry() {
+        // permanent roles never expire
+        assert_eq!(next_role_expiry(None, 100, None), None);
+
+        assert_eq!(next_role_expiry(None, 100, Some(10)), Some(110));
+
+        // renewal of a running subscription
+        assert_eq!(next_role_expiry(Some(150), 100, Some(10)), Some(160));
+
+        // a subscription already past its expiry restarts from now
+        assert_eq!(next_role_expiry(Some(50), 100, Some(10)), Some(110));
+    }
+
+    #[test]
+    fn test_reminder_due() {
+        let mut purchase = RolePurchase {
+            id: 1,
+            guild_id: 1,
+            role_id: 1,
+            buyer_identifier: "buyer".to_string(),
+            price_sompi: 100,
+            duration_secs: Some(ROLE_RENEWAL_REMINDER_SECS * 2),
+            tx_id: "tx".to_string(),
+            status: RolePurchaseStatus::Active,
+            error: None,
+            created_at: 0,
+            expires_at: Some(ROLE_RENEWAL_REMINDER_SECS * 2),
+            reminded: false,
+        };
+
+        assert!(!purchase.is_reminder_due(0));
+        assert!(purchase.is_reminder_due(ROLE_RENEWAL_REMINDER_SECS));
+        assert!(!purchase.is_reminder_due(ROLE_RENEWAL_REMINDER_SECS * 2));
+
+        purchase.reminded = true;
+        assert!(!purchase.is_reminder_due(ROLE_RENEWAL_REMINDER_SECS));
+    }
+}
@@ -0,0 +1,269 @@
+use std::{
+    fmt,
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use spectre_addresses::Address;
+use tokio::sync::RwLock;
+
+use crate::{error::Error, result::Result};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum ScheduleStatus {
+    Active,
+    // paused by the owner, or after a failed payment
+    Paused,
+    Cancelled,
+}
+
+impl fmt::Display for ScheduleStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self {
+            ScheduleStatus::Active => "active",
+            ScheduleStatus::Paused => "paused",
+            ScheduleStatus::Cancelled => "cancelled",
+        };
+
+        write!(f, "{}", status)
+    }
+}
+
+/**
+ * recurring tip paid from a pre-funded allowance wallet, the owner password is never stored
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Schedule {
+    pub id: u64,
+    pub owner_identifier: String,
+    pub recipient_identifier: String,
+    pub amount_sompi: u64,
+    pub interval_secs: u64,
+    pub next_run_at: u64,
+    // custodial wallet holding the allowance
+    pub wallet_identifier: String,
+    pub receive_address: Address,
+    pub secret: String,
+    pub status: ScheduleStatus,
+    pub runs: u64,
+    pub last_error: Option<String>,
+    pub created_at: u64,
+}
+
+impl Schedule {
+    /**
+     * first run time after `now`, runs missed while the bot was stopped are skipped
+     */
+    pub fn next_run_after(&self, now: u64) -> u64 {
+        if self.next_run_at > now {
+            return self.next_run_at;
+        }
+
+        let missed_intervals = (now - self.next_run_at) / self.interval_secs + 1;
+
+        self.next_run_at + missed_intervals * self.interval_secs
+    }
+
+    pub fn is_due(&self, now: u64) -> bool {
+        self.status == ScheduleStatus::Active && self.next_run_at <= now
+    }
+}
+
+#[derive(Debug)]
+pub struct ScheduleStore {
+    schedules: RwLock<Vec<Schedule>>,
+    path_buf: PathBuf,
+}
+
+impl ScheduleStore {
+    pub fn new(path_buf: &PathBuf) -> Result<Self> {
+        let path = Path::new(path_buf);
+
+        let file = match File::open(path) {
+            Ok(f) => f,
+            Err(_) => {
+                let mut created_file = File::create(path)?;
+
+                created_file.write_all(b"[]")?;
+
+                File::open(path)?
+            }
+        };
+
+        let schedules: Vec<Schedule> = serde_json::from_reader(file)?;
+
+        Ok(ScheduleStore {
+            schedules: RwLock::new(schedules),
+            path_buf: path_buf.clone(),
+        })
+    }
+
+    fn persist(&self, schedules: &[Schedule]) -> Result<()> {
+        let file = File::create(Path::new(&self.path_buf))?;
+
+        serde_json::to_writer(file, schedules)?;
+
+        Ok(())
+    }
+
+    /**
+     * apply `update` to the schedule and return its updated copy
+     */
+    async fn update<F: FnOnce(&mut Schedule)>(&self, id: u64, update: F) -> Result<Schedule> {
+        let mut schedules = self.schedules.write().await;
+
+        let schedule = schedules
+            .iter_mut()
+            .find(|s| s.id == id)
+            .ok_or(Error::ScheduleNotFound())?;
+
+        update(schedule);
+
+        let updated = schedule.clone();
+
+        self.persist(&schedules)?;
+
+        Ok(updated)
+    }
+
+    /**
+     * assign the next id to the schedule and store it
+     */
+    pub async fn add(&self, mut schedule: Schedule) -> Result<Schedule> {
+        let mut schedules = self.schedules.write().await;
+
+        schedule.id = schedules.iter().map(|s| s.id).max().unwrap_or(0) + 1;
+
+        schedules.push(schedule.clone());
+
+        self.persist(&schedules)?;
+
+        Ok(schedule)
+    }
+
+    /**
+     * forget a schedule whose allowance could not be funded
+     */
+    pub async fn remove(&self, id: u64) -> Result<()> {
+        let mut schedules = self.schedules.write().await;
+
+        let index = schedules
+            .iter()
+            .position(|s| s.id == id)
+            .ok_or(Error::ScheduleNotFound())?;
+
+        schedules.remove(index);
+
+        self.persist(&schedules)
+    }
+
+    pub async fn set_status(&self, id: u64, status: ScheduleStatus) -> Result<Schedule> {
+        self.update(id, |schedule| schedule.status = status).await
+    }
+
+    /**
+     * reactivate a paused schedule, a run missed while paused happens right away
+     */
+    pub async fn resume(&self, id: u64, now: u64) -> Result<Schedule> {
+        self.update(id, |schedule| {
+            schedule.status = ScheduleStatus::Active;
+            schedule.next_run_at = schedule.next_run_at.max(now);
+            schedule.last_error = None;
+        })
+        .await
+    }
+
+    pub async fn record_run(&self, id: u64, now: u64) -> Result<Schedule> {
+        self.update(id, |schedule| {
+            schedule.runs += 1;
+            schedule.next_run_at = schedule.next_run_after(now);
+            schedule.last_error = None;
+        })
+        .await
+    }
+
+    /**
+     * failed schedules are paused until their owner resumes them
+     */
+    pub async fn record_failure(&self, id: u64, error: String) -> Result<Schedule> {
+        self.update(id, |schedule| {
+            schedule.status = ScheduleStatus::Paused;
+            schedule.last_error = Some(error);
+        })
+        .await
+    }
+
+    pub async fn find_by_id(&self, id: u64) -> Option<Schedule> {
+        let schedules = self.schedules.read().await;
+
+        schedules.iter().find(|s| s.id == id).cloned()
+    }
+
+    pub async fn find_by_owner(&self, owner_identifier: &str) -> Vec<Schedule> {
+        let schedules = self.schedules.read().await;
+
+        schedules
+            .iter()
+            .filter(|s| s.owner_identifier == owner_identifier)
+            .cloned()
+            .collect()
+    }
+
+    pub async fn find_due(&self, now: u64) -> Vec<Schedule> {
+        let schedules = self.schedules.read().await;
+
+        schedules
+            .iter()
+            .filter(|s| s.is_due(now))
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectre_addresses::{Prefix, Version};
+
+    fn build_schedule(next_run_at: u64) -> Schedule {
+        Schedule {
+            id: 1,
+            owner_identifier: "owner".to_string(),
+            recipient_identifier: "recipient".to_string(),
+            amount_sompi: 100,
+            interval_secs: 100,
+            next_run_at,
+            wallet_identifier: "schedule-1".to_string(),
+            receive_address: Address::new(Prefix::Mainnet, Version::PubKey, &[0u8; 32]),
+            secret: "secret".to_string(),
+            status: ScheduleStatus::Active,
+            runs: 0,
+            last_error: None,
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_next_run_after() {
+        let schedule = build_schedule(1_000);
+
+        assert_eq!(schedule.next_run_after(900), 1_000);
+        assert_eq!(schedule.next_run_after(1_000), 1_100);
+        assert_eq!(schedule.next_run_after(1_050), 1_100);
+        // missed runs are not caught up
+        assert_eq!(schedule.next_run_after(1_350), 1_400);
+    }
+
+    #[test]
+    fn test_is_due() {
+        let mut schedule = build_schedule(1_000);
+
+        assert!(!schedule.is_due(999));
+        assert!(schedule.is_due(1_000));
+
+        schedule.status = ScheduleStatus::Paused;
+        assert!(!schedule.is_due(1_000));
+    }
+}
@@ -0,0 +1,78 @@
+use std::{future::Future, sync::Arc, time::Duration};
+
+use tokio::sync::mpsc::UnboundedSender;
+use tracing::{error, info};
+
+use crate::{result::Result, schedule::Schedule, tip_context::TipContext, utils::now_unix_secs};
+
+// due schedules are paid on the next tick
+const SCHEDULER_TICK_INTERVAL: Duration = Duration::from_secs(30);
+
+/**
+ * outcome of a scheduled payment, forwarded to the owner by the bot
+ */
+#[derive(Debug, Clone)]
+pub enum ScheduleEvent {
+    Paid { schedule: Schedule, tx_id: String },
+    Failed { schedule: Schedule, error: String },
+}
+
+/**
+ * pay the due schedules with `pay`, which returns the transaction id of the payment,
+ * a failed schedule is paused so a broken allowance is not retried forever
+ */
+pub async fn run_due_schedules<F, Fut>(
+    tip_context: &Arc<TipContext>,
+    pay: &F,
+    events: &UnboundedSender<ScheduleEvent>,
+) where
+    F: Fn(Schedule) -> Fut,
+    Fut: Future<Output = Result<String>>,
+{
+    for schedule in tip_context.schedule_store.find_due(now_unix_secs()).await {
+        info!("Running schedule #{}", schedule.id);
+
+        let event = match pay(schedule.clone()).await {
+            Ok(tx_id) => tip_context
+                .schedule_store
+                .record_run(schedule.id, now_unix_secs())
+                .await
+                .map(|schedule| ScheduleEvent::Paid { schedule, tx_id }),
+            Err(e) => tip_context
+                .schedule_store
+                .record_failure(schedule.id, e.to_string())
+                .await
+                .map(|schedule| ScheduleEvent::Failed {
+                    schedule,
+                    error: e.to_string(),
+                }),
+        };
+
+        match event {
+            Ok(event) => {
+                // nobody listening only means nobody is notified
+                let _ = events.send(event);
+            }
+            Err(e) => error!("Error while recording schedule #{}: {}", schedule.id, e),
+        }
+    }
+}
+
+pub fn spawn_scheduler<F, Fut>(
+    tip_context: Arc<TipContext>,
+    pay: F,
+    events: UnboundedSender<ScheduleEvent>,
+) where
+    F: Fn(Schedule) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<String>> + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(SCHEDULER_TICK_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            run_due_schedules(&tip_context, &pay, &events).await;
+        }
+    });
+}
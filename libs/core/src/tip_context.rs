@@ -5,22 +5,70 @@ use std::{
 };
 
 use spectre_wallet_core::{rpc::RpcApi, storage::Interface, wallet::Wallet};
+use spectre_wallet_keys::secret::Secret;
 use spectre_wrpc_client::{prelude::NetworkId, Resolver, SpectreRpcClient};
 use tracing::info;
 
 use crate::{
-    owned_wallet_metadata::OwnedWalletMetadataStore, result::Result,
-    tip_owned_wallet::TipOwnedWallet, transition_wallet_metadata::TransitionWalletMetadataStore,
+    activity_tracker::ActivityTracker,
+    audit_log::AuditLogStore,
+    balance_role::{BalanceRoleMemberStore, BalanceRoleStore},
+    bounty::BountyStore,
+    error::Error,
+    escrow::{EscrowArbiterStore, EscrowStore},
+    fee_policy::FeePolicy,
+    frozen_user::FrozenUserStore,
+    guild_config::GuildConfigStore,
+    linked_address_metadata::{LinkChallenge, LinkedAddressMetadataStore},
+    opt_out::OptOutStore,
+    outbox::OutboxStore,
+    owned_wallet_metadata::OwnedWalletMetadataStore,
+    payment_request::PaymentRequestStore,
+    reaction_tip_config::ReactionTipLimiter,
+    result::Result,
+    role_shop::{RolePurchaseStore, ShopRoleStore},
+    schedule::ScheduleStore,
+    tip_owned_wallet::TipOwnedWallet,
+    transition_wallet_metadata::TransitionWalletMetadataStore,
+    treasury::{TreasuryPayoutStore, TreasuryStore},
+    utils::{derive_treasury_wallet_secret, derive_watch_only_wallet_secret},
 };
 
 pub struct TipContext {
     resolver: Resolver,
     network_id: NetworkId,
     opened_owned_wallets: RwLock<HashMap<String, TipOwnedWallet>>,
+    link_challenges: RwLock<HashMap<String, LinkChallenge>>,
+    // wallet secrets kept in memory by owners who enabled reaction tips
+    reaction_tip_secrets: RwLock<HashMap<String, Secret>>,
     pub transition_wallet_metadata_store: TransitionWalletMetadataStore,
     pub owned_wallet_metadata_store: OwnedWalletMetadataStore,
+    pub linked_address_metadata_store: LinkedAddressMetadataStore,
+    pub outbox_store: OutboxStore,
+    pub opt_out_store: OptOutStore,
+    pub payment_request_store: PaymentRequestStore,
+    pub escrow_store: EscrowStore,
+    pub escrow_arbiter_store: EscrowArbiterStore,
+    pub schedule_store: ScheduleStore,
+    pub treasury_store: TreasuryStore,
+    pub treasury_payout_store: TreasuryPayoutStore,
+    pub bounty_store: BountyStore,
+    pub shop_role_store: ShopRoleStore,
+    pub role_purchase_store: RolePurchaseStore,
+    pub balance_role_store: BalanceRoleStore,
+    pub balance_role_member_store: BalanceRoleMemberStore,
+    pub guild_config_store: GuildConfigStore,
+    pub frozen_user_store: FrozenUserStore,
+    pub audit_log_store: AuditLogStore,
     forced_node_url: Option<String>,
     wrpc_client: Arc<SpectreRpcClient>,
+    fee_policy: FeePolicy,
+    // operator key watch-only wallet secrets are derived from, watch-only wallets are disabled without it
+    watch_only_wallet_key: Option<String>,
+    // operator key treasury wallet secrets are derived from, treasuries are disabled without it
+    treasury_wallet_key: Option<String>,
+    activity_tracker: ActivityTracker,
+    reaction_tip_limiter: ReactionTipLimiter,
 }
 
 impl TipContext {
@@ -30,10 +78,33 @@ impl TipContext {
         forced_node_url: Option<String>,
         wrpc_client: Arc<SpectreRpcClient>,
         wallet_data_path_buf: PathBuf,
+        fee_policy: FeePolicy,
+        watch_only_wallet_key: Option<String>,
+        treasury_wallet_key: Option<String>,
     ) -> Result<Arc<Self>> {
         let transition_wallet_metadata_path_buf =
             wallet_data_path_buf.clone().join("transitions.json");
         let owned_wallet_metadata_path_buf = wallet_data_path_buf.clone().join("owned.json");
+        let linked_address_metadata_path_buf = wallet_data_path_buf.clone().join("linked.json");
+        let outbox_path_buf = wallet_data_path_buf.clone().join("outbox.json");
+        let opt_out_path_buf = wallet_data_path_buf.clone().join("opt_out.json");
+        let reaction_tip_config_path_buf = wallet_data_path_buf.clone().join("reaction_tips.json");
+        let payment_request_path_buf = wallet_data_path_buf.clone().join("payment_requests.json");
+        let escrow_path_buf = wallet_data_path_buf.clone().join("escrows.json");
+        let escrow_arbiter_path_buf = wallet_data_path_buf.clone().join("escrow_arbiters.json");
+        let schedule_path_buf = wallet_data_path_buf.clone().join("schedules.json");
+        let treasury_path_buf = wallet_data_path_buf.clone().join("treasuries.json");
+        let treasury_payout_path_buf = wallet_data_path_buf.clone().join("treasury_payouts.json");
+        let bounty_path_buf = wallet_data_path_buf.clone().join("bounties.json");
+        let shop_role_path_buf = wallet_data_path_buf.clone().join("shop_roles.json");
+        let role_purchase_path_buf = wallet_data_path_buf.clone().join("role_purchases.json");
+        let balance_role_path_buf = wallet_data_path_buf.clone().join("balance_roles.json");
+        let balance_role_member_path_buf = wallet_data_path_buf
+            .clone()
+            .join("balance_role_members.json");
+        let guild_config_path_buf = wallet_data_path_buf.clone().join("guild_configs.json");
+        let frozen_user_path_buf = wallet_data_path_buf.clone().join("frozen_users.json");
+        let audit_log_path_buf = wallet_data_path_buf.clone().join("audit_log.json");
 
         info!(
             "Using {} as owned wallet metadata store",
@@ -45,20 +116,166 @@ impl TipContext {
             transition_wallet_metadata_path_buf.to_str().unwrap()
         );
 
+        info!(
+            "Using {} as linked address metadata store",
+            linked_address_metadata_path_buf.to_str().unwrap()
+        );
+
+        info!(
+            "Using {} as outbox store",
+            outbox_path_buf.to_str().unwrap()
+        );
+
+        info!(
+            "Using {} as opt-out store",
+            opt_out_path_buf.to_str().unwrap()
+        );
+
+        info!(
+            "Using {} as payment request store",
+            payment_request_path_buf.to_str().unwrap()
+        );
+
+        info!(
+            "Using {} as escrow store",
+            escrow_path_buf.to_str().unwrap()
+        );
+
+        info!(
+            "Using {} as escrow arbiter store",
+            escrow_arbiter_path_buf.to_str().unwrap()
+        );
+
+        info!(
+            "Using {} as schedule store",
+            schedule_path_buf.to_str().unwrap()
+        );
+
+        info!(
+            "Using {} as treasury store",
+            treasury_path_buf.to_str().unwrap()
+        );
+
+        info!(
+            "Using {} as treasury payout store",
+            treasury_payout_path_buf.to_str().unwrap()
+        );
+
+        info!(
+            "Using {} as bounty store",
+            bounty_path_buf.to_str().unwrap()
+        );
+
+        info!(
+            "Using {} as shop role store",
+            shop_role_path_buf.to_str().unwrap()
+        );
+
+        info!(
+            "Using {} as role purchase store",
+            role_purchase_path_buf.to_str().unwrap()
+        );
+
+        info!(
+            "Using {} as balance role store",
+            balance_role_path_buf.to_str().unwrap()
+        );
+
+        info!(
+            "Using {} as balance role member store",
+            balance_role_member_path_buf.to_str().unwrap()
+        );
+
+        info!(
+            "Using {} as guild config store",
+            guild_config_path_buf.to_str().unwrap()
+        );
+
+        info!(
+            "Using {} as frozen user store",
+            frozen_user_path_buf.to_str().unwrap()
+        );
+
+        info!(
+            "Using {} as audit log store",
+            audit_log_path_buf.to_str().unwrap()
+        );
+
         let transition_wallet_metadata_store =
             TransitionWalletMetadataStore::new(&transition_wallet_metadata_path_buf)?;
 
         let owned_wallet_metadata_store =
             OwnedWalletMetadataStore::new(&owned_wallet_metadata_path_buf)?;
 
+        let linked_address_metadata_store =
+            LinkedAddressMetadataStore::new(&linked_address_metadata_path_buf)?;
+
+        let outbox_store = OutboxStore::new(&outbox_path_buf)?;
+
+        let opt_out_store = OptOutStore::new(&opt_out_path_buf)?;
+
+        let payment_request_store = PaymentRequestStore::new(&payment_request_path_buf)?;
+
+        let escrow_store = EscrowStore::new(&escrow_path_buf)?;
+
+        let escrow_arbiter_store = EscrowArbiterStore::new(&escrow_arbiter_path_buf)?;
+
+        let schedule_store = ScheduleStore::new(&schedule_path_buf)?;
+
+        let treasury_store = TreasuryStore::new(&treasury_path_buf)?;
+
+        let treasury_payout_store = TreasuryPayoutStore::new(&treasury_payout_path_buf)?;
+
+        let bounty_store = BountyStore::new(&bounty_path_buf)?;
+
+        let shop_role_store = ShopRoleStore::new(&shop_role_path_buf)?;
+
+        let role_purchase_store = RolePurchaseStore::new(&role_purchase_path_buf)?;
+
+        let balance_role_store = BalanceRoleStore::new(&balance_role_path_buf)?;
+
+        let balance_role_member_store = BalanceRoleMemberStore::new(&balance_role_member_path_buf)?;
+
+        // reaction tips were configured in their own file before the guild config existed
+        let guild_config_store =
+            GuildConfigStore::new(&guild_config_path_buf, &reaction_tip_config_path_buf)?;
+
+        let frozen_user_store = FrozenUserStore::new(&frozen_user_path_buf)?;
+
+        let audit_log_store = AuditLogStore::new(&audit_log_path_buf)?;
+
         Ok(Arc::new(TipContext {
             network_id,
             resolver,
             forced_node_url,
             wrpc_client,
+            fee_policy,
+            watch_only_wallet_key,
+            treasury_wallet_key,
+            activity_tracker: ActivityTracker::new(),
+            reaction_tip_limiter: ReactionTipLimiter::new(),
             opened_owned_wallets: RwLock::new(HashMap::new()),
+            link_challenges: RwLock::new(HashMap::new()),
+            reaction_tip_secrets: RwLock::new(HashMap::new()),
             transition_wallet_metadata_store,
             owned_wallet_metadata_store,
+            linked_address_metadata_store,
+            outbox_store,
+            opt_out_store,
+            payment_request_store,
+            escrow_store,
+            escrow_arbiter_store,
+            schedule_store,
+            treasury_store,
+            treasury_payout_store,
+            bounty_store,
+            shop_role_store,
+            role_purchase_store,
+            balance_role_store,
+            balance_role_member_store,
+            guild_config_store,
+            frozen_user_store,
+            audit_log_store,
         }))
     }
 
@@ -105,6 +322,40 @@ impl TipContext {
         lock.remove(identifier)
     }
 
+    /*
+     * a new challenge replaces any pending challenge of the same owner
+     */
+    pub fn add_link_challenge(&self, owner_identifier: String, challenge: LinkChallenge) {
+        let mut lock = self.link_challenges.write().unwrap();
+        lock.insert(owner_identifier, challenge);
+    }
+
+    /*
+     * challenges can only be answered once
+     */
+    pub fn take_link_challenge(&self, owner_identifier: &str) -> Option<LinkChallenge> {
+        let mut lock = self.link_challenges.write().unwrap();
+        lock.remove(owner_identifier)
+    }
+
+    /*
+     * reaction tips sign with the secret given when they were enabled, until disabled or closed
+     */
+    pub fn enable_reaction_tips(&self, owner_identifier: String, wallet_secret: Secret) {
+        let mut lock = self.reaction_tip_secrets.write().unwrap();
+        lock.insert(owner_identifier, wallet_secret);
+    }
+
+    pub fn disable_reaction_tips(&self, owner_identifier: &str) {
+        let mut lock = self.reaction_tip_secrets.write().unwrap();
+        lock.remove(owner_identifier);
+    }
+
+    pub fn get_reaction_tip_secret(&self, owner_identifier: &str) -> Option<Secret> {
+        let read_lock = self.reaction_tip_secrets.read().unwrap();
+        read_lock.get(owner_identifier).cloned()
+    }
+
     /*
      * get a new store
      */
@@ -119,4 +370,36 @@ impl TipContext {
     pub fn rpc_api(&self) -> Arc<dyn RpcApi> {
         self.wrpc_client.clone()
     }
+
+    pub fn fee_policy(&self) -> &FeePolicy {
+        &self.fee_policy
+    }
+
+    pub fn watch_only_wallet_secret(&self, owned_identifier: &str) -> Result<Secret> {
+        match &self.watch_only_wallet_key {
+            Some(watch_only_wallet_key) => Ok(derive_watch_only_wallet_secret(
+                watch_only_wallet_key,
+                owned_identifier,
+            )),
+            None => Err(Error::WatchOnlyWalletKeyMissing()),
+        }
+    }
+
+    pub fn treasury_wallet_secret(&self, owned_identifier: &str) -> Result<Secret> {
+        match &self.treasury_wallet_key {
+            Some(treasury_wallet_key) => Ok(derive_treasury_wallet_secret(
+                treasury_wallet_key,
+                owned_identifier,
+            )),
+            None => Err(Error::TreasuryWalletKeyMissing()),
+        }
+    }
+
+    pub fn activity_tracker(&self) -> &ActivityTracker {
+        &self.activity_tracker
+    }
+
+    pub fn reaction_tip_limiter(&self) -> &ReactionTipLimiter {
+        &self.reaction_tip_limiter
+    }
 }
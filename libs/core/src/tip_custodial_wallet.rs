@@ -0,0 +1,165 @@
+use std::sync::Arc;
+
+use crate::result::Result;
+use crate::tip_context::TipContext;
+use crate::utils::{connect_wallet_to_rpc, generate_random_transition_wallet_secret};
+use spectre_addresses::Address;
+use spectre_wallet_core::{
+    prelude::{EncryptionKind, Language, Mnemonic, WordCount},
+    storage::PrvKeyData,
+    wallet::{AccountCreateArgsBip32, Wallet, WalletCreateArgs, WalletOpenArgs},
+};
+use spectre_wallet_keys::secret::Secret;
+
+/**
+ * bot-controlled wallet holding funds on behalf of users (escrows, allowances of scheduled tips)
+ */
+#[derive(Clone)]
+pub struct TipCustodialWallet {
+    wallet_identifier: String,
+    wallet: Arc<Wallet>,
+    receive_address: Address,
+}
+
+impl TipCustodialWallet {
+    pub fn new(wallet_identifier: String, wallet: Arc<Wallet>, receive_address: Address) -> Self {
+        TipCustodialWallet {
+            wallet_identifier,
+            wallet,
+            receive_address,
+        }
+    }
+
+    /**
+     * returns the wallet along with its generated secret, to be kept in the escrow record
+     * Note: created custodial wallet aren't connected to RPC, they only receive until spent
+     */
+    pub async fn create(
+        tip_context: Arc<TipContext>,
+        wallet_identifier: &str,
+    ) -> Result<(TipCustodialWallet, String)> {
+        let secret_str: String = generate_random_transition_wallet_secret();
+
+        let wallet_secret = Secret::from(secret_str.clone());
+
+        let mnemonic = Mnemonic::random(WordCount::Words12, Language::default())?;
+        let localstore = Wallet::local_store()?;
+
+        let wallet = Wallet::try_new(
+            localstore,
+            Some(tip_context.resolver()),
+            Some(tip_context.network_id()),
+        )?;
+
+        let wallet_arc = Arc::new(wallet.clone());
+
+        let wallet_args: WalletCreateArgs = WalletCreateArgs::new(
+            Some(wallet_identifier.to_string()),
+            None,
+            EncryptionKind::XChaCha20Poly1305,
+            None,
+            true,
+        );
+
+        wallet_arc.store().batch().await?;
+
+        wallet_arc
+            .create_wallet(&wallet_secret, wallet_args)
+            .await?;
+
+        let prv_key_data = PrvKeyData::try_from_mnemonic(
+            mnemonic.clone(),
+            None,
+            // unused since payment_secret is None
+            EncryptionKind::XChaCha20Poly1305,
+        )?;
+        let prv_key_data_id = prv_key_data.id;
+
+        let prv_key_data_store = wallet_arc.store().as_prv_key_data_store()?;
+        prv_key_data_store
+            .store(&wallet_secret, prv_key_data)
+            .await?;
+        wallet_arc.store().commit(&wallet_secret).await?;
+
+        let account_args = AccountCreateArgsBip32::new(None, None);
+        let account = wallet_arc
+            .create_account_bip32(&wallet_secret, prv_key_data_id, None, account_args)
+            .await?;
+
+        let receive_address = account.receive_address()?;
+
+        wallet_arc.store().flush(&wallet_secret).await?;
+
+        let tip_wallet =
+            TipCustodialWallet::new(wallet_identifier.to_string(), wallet_arc, receive_address);
+
+        Ok((tip_wallet, secret_str))
+    }
+
+    /**
+     * opened custodial wallets are connected to RPC so they can settle, stop them once done
+     */
+    pub async fn open(
+        tip_context: Arc<TipContext>,
+        wallet_secret: &Secret,
+        wallet_identifier: &str,
+    ) -> Result<TipCustodialWallet> {
+        let localstore = Wallet::local_store()?;
+
+        let wallet = Wallet::try_new(
+            localstore,
+            Some(tip_context.resolver()),
+            Some(tip_context.network_id()),
+        )?;
+        let wallet_arc = Arc::new(wallet.clone());
+
+        connect_wallet_to_rpc(&wallet_arc, tip_context.rpc_api()).await?;
+
+        let args = WalletOpenArgs::default_with_legacy_accounts();
+
+        {
+            let guard = wallet_arc.guard();
+            let guard = guard.lock().await;
+
+            wallet_arc
+                .open(
+                    wallet_secret,
+                    Some(wallet_identifier.to_string()),
+                    args,
+                    &guard,
+                )
+                .await?;
+
+            wallet_arc.start().await?;
+
+            wallet_arc.activate_accounts(None, &guard).await?;
+            wallet_arc.autoselect_default_account_if_single().await?;
+        }
+
+        let receive_address = wallet_arc.account()?.receive_address()?;
+
+        wallet_arc
+            .account()?
+            .utxo_context()
+            .register_addresses(&[receive_address.clone()])
+            .await?;
+
+        Ok(TipCustodialWallet::new(
+            wallet_identifier.to_string(),
+            wallet_arc,
+            receive_address,
+        ))
+    }
+
+    pub fn wallet_identifier(&self) -> &str {
+        &self.wallet_identifier
+    }
+
+    pub fn wallet(&self) -> Arc<Wallet> {
+        self.wallet.clone()
+    }
+
+    pub fn receive_address(&self) -> Address {
+        self.receive_address.clone()
+    }
+}
@@ -1,24 +1,33 @@
-use std::sync::Arc;
+use std::{str::FromStr, sync::Arc};
 
 use crate::error::Error;
 use crate::tip_context::TipContext;
-use crate::utils::connect_wallet_to_rpc;
+use crate::utils::{connect_wallet_to_rpc, get_wallet_file_path, try_build_prv_key_data_from_xprv};
 use crate::{owned_wallet_metadata::OwnedWalletMetadata, result::Result};
 use spectre_addresses::Address;
+use spectre_bip32::secp256k1::{SecretKey, SECP256K1};
 use spectre_wallet_core::{
+    account::{keypair::Keypair, DerivationCapableAccount},
     api::WalletApi,
+    derivation::AddressDerivationManagerTrait,
     deterministic::bip32::BIP32_ACCOUNT_KIND,
-    prelude::{EncryptionKind, Language, Mnemonic, WordCount},
-    storage::PrvKeyData,
-    wallet::{AccountCreateArgsBip32, Wallet, WalletCreateArgs, WalletOpenArgs},
+    message::{sign_message, PersonalMessage, SignMessageOptions},
+    prelude::{Account, EncryptionKind, Language, Mnemonic, WordCount},
+    storage::{interface::OpenArgs, Binding, PrvKeyData, TransactionRecord},
+    wallet::{
+        AccountCreateArgsBip32, AccountCreateArgsBip32Watch, Wallet, WalletCreateArgs,
+        WalletOpenArgs,
+    },
 };
 use spectre_wallet_keys::secret::Secret;
+use tokio::fs;
 
 #[derive(Clone)]
 pub struct TipOwnedWallet {
     owned_identifier: String,
     wallet: Arc<Wallet>,
     receive_address: Address,
+    watch_only: bool,
 }
 
 impl TipOwnedWallet {
@@ -27,6 +36,20 @@ impl TipOwnedWallet {
             owned_identifier,
             receive_address,
             wallet,
+            watch_only: false,
+        }
+    }
+
+    pub fn new_watch_only(
+        owned_identifier: String,
+        wallet: Arc<Wallet>,
+        receive_address: Address,
+    ) -> Self {
+        TipOwnedWallet {
+            owned_identifier,
+            receive_address,
+            wallet,
+            watch_only: true,
         }
     }
 
@@ -173,6 +196,8 @@ impl TipOwnedWallet {
         mnemonic: Mnemonic,
         owned_identifier: &str,
     ) -> Result<TipOwnedWallet> {
+        Self::close_replaced_wallet(&tip_context, owned_identifier).await?;
+
         let localstore = Wallet::local_store()?;
 
         let wallet = Wallet::try_new(
@@ -258,6 +283,364 @@ impl TipOwnedWallet {
         Ok(tip_owned_wallet)
     }
 
+    /**
+     * import a wallet from a single secp256k1 private key (hex encoded)
+     * creates a keypair account, override any already existing wallet owned by `owned_identifier`
+     */
+    pub async fn import_private_key(
+        tip_context: Arc<TipContext>,
+        wallet_secret: &Secret,
+        private_key_hex: &str,
+        owned_identifier: &str,
+    ) -> Result<TipOwnedWallet> {
+        let secret_key = SecretKey::from_str(private_key_hex.trim())
+            .map_err(|_| Error::custom("Invalid private key"))?;
+
+        let wallet_arc =
+            Self::create_empty_wallet(tip_context.clone(), wallet_secret, owned_identifier).await?;
+
+        let prv_key_data = PrvKeyData::try_new_from_secret_key(
+            secret_key,
+            None,
+            // unused since payment_secret is None
+            EncryptionKind::XChaCha20Poly1305,
+        )?;
+        let prv_key_data_id = prv_key_data.id;
+
+        let prv_key_data_store = wallet_arc.store().as_prv_key_data_store()?;
+        prv_key_data_store
+            .store(wallet_secret, prv_key_data)
+            .await?;
+        wallet_arc.store().commit(wallet_secret).await?;
+
+        let public_key = secret_key.public_key(SECP256K1);
+        let account: Arc<dyn Account> = Arc::new(
+            Keypair::try_new(&wallet_arc, None, public_key, prv_key_data_id, false).await?,
+        );
+
+        wallet_arc
+            .store()
+            .as_account_store()?
+            .store_single(&account.to_storage()?, None)
+            .await?;
+        wallet_arc.store().commit(wallet_secret).await?;
+
+        Self::finalize_imported_wallet(
+            tip_context,
+            wallet_arc,
+            wallet_secret,
+            account,
+            owned_identifier,
+            false,
+        )
+        .await
+    }
+
+    /**
+     * import a wallet from a master extended private key (xprv)
+     * creates a bip32 account, override any already existing wallet owned by `owned_identifier`
+     */
+    pub async fn import_xprv(
+        tip_context: Arc<TipContext>,
+        wallet_secret: &Secret,
+        xprv: &str,
+        owned_identifier: &str,
+    ) -> Result<TipOwnedWallet> {
+        let prv_key_data = try_build_prv_key_data_from_xprv(xprv.trim())?;
+        let prv_key_data_id = prv_key_data.id;
+
+        let wallet_arc =
+            Self::create_empty_wallet(tip_context.clone(), wallet_secret, owned_identifier).await?;
+
+        let prv_key_data_store = wallet_arc.store().as_prv_key_data_store()?;
+        prv_key_data_store
+            .store(wallet_secret, prv_key_data)
+            .await?;
+        wallet_arc.store().commit(wallet_secret).await?;
+
+        let account_args = AccountCreateArgsBip32::new(None, None);
+        let account = wallet_arc
+            .create_account_bip32(wallet_secret, prv_key_data_id, None, account_args)
+            .await?;
+
+        Self::finalize_imported_wallet(
+            tip_context,
+            wallet_arc,
+            wallet_secret,
+            account,
+            owned_identifier,
+            false,
+        )
+        .await
+    }
+
+    /**
+     * stop and close the opened wallet of `owned_identifier` before its file is replaced,
+     * the in-memory reaction tip secret belongs to the replaced wallet
+     */
+    async fn close_replaced_wallet(tip_context: &TipContext, owned_identifier: &str) -> Result<()> {
+        tip_context.disable_reaction_tips(owned_identifier);
+
+        if let Some(tip_wallet) = tip_context.remove_opened_owned_wallet(owned_identifier) {
+            tip_wallet.wallet().stop().await?;
+            tip_wallet.wallet().close().await?;
+        }
+
+        Ok(())
+    }
+
+    async fn create_empty_wallet(
+        tip_context: Arc<TipContext>,
+        wallet_secret: &Secret,
+        owned_identifier: &str,
+    ) -> Result<Arc<Wallet>> {
+        Self::close_replaced_wallet(&tip_context, owned_identifier).await?;
+
+        let localstore = Wallet::local_store()?;
+
+        let wallet = Wallet::try_new(
+            localstore,
+            Some(tip_context.resolver()),
+            Some(tip_context.network_id()),
+        )?;
+
+        let wallet_arc = Arc::new(wallet.clone());
+
+        let wallet_args: WalletCreateArgs = WalletCreateArgs::new(
+            Some(owned_identifier.into()),
+            None,
+            EncryptionKind::XChaCha20Poly1305,
+            None,
+            true,
+        );
+
+        wallet_arc.store().batch().await?;
+
+        wallet_arc.create_wallet(wallet_secret, wallet_args).await?;
+
+        Ok(wallet_arc)
+    }
+
+    async fn finalize_imported_wallet(
+        tip_context: Arc<TipContext>,
+        wallet_arc: Arc<Wallet>,
+        wallet_secret: &Secret,
+        account: Arc<dyn Account>,
+        owned_identifier: &str,
+        watch_only: bool,
+    ) -> Result<TipOwnedWallet> {
+        let receive_address = account.receive_address()?;
+
+        wallet_arc.store().flush(wallet_secret).await?;
+
+        {
+            let guard = wallet_arc.guard();
+            let guard = guard.lock().await;
+            wallet_arc.activate_accounts(None, &guard).await?;
+
+            connect_wallet_to_rpc(&wallet_arc, tip_context.rpc_api()).await?;
+
+            wallet_arc.start().await?;
+        }
+
+        wallet_arc.autoselect_default_account_if_single().await?;
+
+        wallet_arc
+            .account()?
+            .utxo_context()
+            .register_addresses(&[receive_address.clone()])
+            .await?;
+
+        let (tip_owned_wallet, metadata) = match watch_only {
+            true => (
+                TipOwnedWallet::new_watch_only(
+                    owned_identifier.into(),
+                    wallet_arc,
+                    receive_address.clone(),
+                ),
+                OwnedWalletMetadata::new_watch_only(owned_identifier.into(), receive_address),
+            ),
+            false => (
+                TipOwnedWallet::new(owned_identifier.into(), wallet_arc, receive_address.clone()),
+                OwnedWalletMetadata::new(owned_identifier.into(), receive_address),
+            ),
+        };
+
+        tip_context
+            .owned_wallet_metadata_store
+            .remove_by_owner_identifier(owned_identifier.into())
+            .await?;
+
+        tip_context
+            .owned_wallet_metadata_store
+            .add(&metadata)
+            .await?;
+
+        let tip_owned_wallet =
+            tip_context.add_opened_owned_wallet(owned_identifier.into(), tip_owned_wallet);
+
+        Ok(tip_owned_wallet)
+    }
+
+    /**
+     * read the encrypted wallet file, still protected by the wallet password
+     */
+    pub async fn read_wallet_file(owned_identifier: &str) -> Result<Vec<u8>> {
+        let wallet_file = get_wallet_file_path(owned_identifier)?;
+
+        if !wallet_file.exists() {
+            return Err(Error::OwnedWalletNotFound());
+        }
+
+        Ok(fs::read(&wallet_file).await?)
+    }
+
+    /**
+     * install an encrypted wallet file (previously read by `read_wallet_file`) under `owned_identifier`
+     * the file must decrypt with `wallet_secret`, override any already existing wallet owned by `owned_identifier`
+     * the previously opened wallet is only closed once the file is verified
+     */
+    pub async fn import_wallet_file(
+        tip_context: Arc<TipContext>,
+        wallet_secret: &Secret,
+        wallet_file_data: &[u8],
+        owned_identifier: &str,
+    ) -> Result<TipOwnedWallet> {
+        let staging_identifier = format!("import-{}", owned_identifier);
+        let staging_file = get_wallet_file_path(&staging_identifier)?;
+
+        fs::write(&staging_file, wallet_file_data).await?;
+
+        // make sure the file decrypts with the given secret before replacing anything
+        let localstore = Wallet::local_store()?;
+        let verification = localstore
+            .open(wallet_secret, OpenArgs::new(Some(staging_identifier)))
+            .await;
+
+        if let Err(e) = verification {
+            fs::remove_file(&staging_file).await?;
+            return Err(Error::from(e));
+        }
+
+        localstore.close().await?;
+
+        Self::close_replaced_wallet(&tip_context, owned_identifier).await?;
+
+        fs::rename(&staging_file, get_wallet_file_path(owned_identifier)?).await?;
+
+        let tip_owned_wallet =
+            TipOwnedWallet::open(tip_context.clone(), wallet_secret, owned_identifier).await?;
+
+        tip_context
+            .owned_wallet_metadata_store
+            .remove_by_owner_identifier(owned_identifier.into())
+            .await?;
+
+        tip_context
+            .owned_wallet_metadata_store
+            .add(&OwnedWalletMetadata::new(
+                owned_identifier.into(),
+                tip_owned_wallet.receive_address(),
+            ))
+            .await?;
+
+        Ok(tip_owned_wallet)
+    }
+
+    /**
+     * register a watch-only wallet from an exported xpub
+     * the local wallet file is protected by a secret derived from the operator key,
+     * so the wallet can be opened without any password
+     */
+    pub async fn create_watch_only(
+        tip_context: Arc<TipContext>,
+        xpub: &str,
+        owned_identifier: &str,
+    ) -> Result<TipOwnedWallet> {
+        let wallet_secret = tip_context.watch_only_wallet_secret(owned_identifier)?;
+
+        let wallet_arc =
+            Self::create_empty_wallet(tip_context.clone(), &wallet_secret, owned_identifier)
+                .await?;
+
+        let account_args = AccountCreateArgsBip32Watch::new(None, vec![xpub.trim().into()]);
+        let account = wallet_arc
+            .create_account_bip32_watch(&wallet_secret, account_args)
+            .await?;
+
+        Self::finalize_imported_wallet(
+            tip_context,
+            wallet_arc,
+            &wallet_secret,
+            account,
+            owned_identifier,
+            true,
+        )
+        .await
+    }
+
+    /**
+     * open a watch-only wallet with the secret derived from the operator key
+     */
+    pub async fn open_watch_only(
+        tip_context: Arc<TipContext>,
+        owned_identifier: &str,
+    ) -> Result<TipOwnedWallet> {
+        let metadata = tip_context
+            .owned_wallet_metadata_store
+            .find_owned_wallet_metadata_by_owner_identifier(owned_identifier)
+            .await?;
+
+        if !metadata.is_watch_only() {
+            return Err(Error::custom("Wallet is not a watch-only wallet"));
+        }
+
+        let wallet_secret = tip_context.watch_only_wallet_secret(owned_identifier)?;
+
+        let localstore = Wallet::local_store()?;
+
+        let wallet = Wallet::try_new(
+            localstore,
+            Some(tip_context.resolver()),
+            Some(tip_context.network_id()),
+        )?;
+        let wallet_arc = Arc::new(wallet.clone());
+
+        let args = WalletOpenArgs::default_with_legacy_accounts();
+
+        {
+            let guard = wallet_arc.guard();
+            let guard = guard.lock().await;
+
+            connect_wallet_to_rpc(&wallet_arc, tip_context.rpc_api()).await?;
+
+            wallet_arc
+                .open(&wallet_secret, Some(owned_identifier.into()), args, &guard)
+                .await?;
+
+            wallet_arc.start().await?;
+
+            wallet_arc.activate_accounts(None, &guard).await?;
+            wallet_arc.autoselect_default_account_if_single().await?;
+        }
+
+        let receive_address = wallet_arc.account()?.receive_address()?;
+
+        wallet_arc
+            .account()?
+            .utxo_context()
+            .register_addresses(&[receive_address.clone()])
+            .await?;
+
+        let tip_wallet =
+            TipOwnedWallet::new_watch_only(owned_identifier.into(), wallet_arc, receive_address);
+
+        let tip_owned_wallet =
+            tip_context.add_opened_owned_wallet(owned_identifier.into(), tip_wallet);
+
+        Ok(tip_owned_wallet)
+    }
+
     pub fn owned_identifier(&self) -> &str {
         &self.owned_identifier
     }
@@ -270,8 +653,56 @@ impl TipOwnedWallet {
         self.receive_address.clone()
     }
 
+    pub fn is_watch_only(&self) -> bool {
+        self.watch_only
+    }
+
+    /// fail if the wallet cannot sign transactions
+    pub fn ensure_can_sign(&self) -> Result<()> {
+        if self.watch_only {
+            return Err(Error::WatchOnlyWallet());
+        }
+        Ok(())
+    }
+
+    /// fail if the secret does not decrypt the private key data of the account
+    pub async fn verify_secret(&self, wallet_secret: &Secret) -> Result<()> {
+        self.ensure_can_sign()?;
+
+        self.wallet
+            .account()?
+            .prv_key_data(wallet_secret.clone())
+            .await?;
+        Ok(())
+    }
+
+    /// most recent transaction records observed by the wallet, newest first
+    pub async fn recent_transactions(&self, limit: usize) -> Result<Vec<Arc<TransactionRecord>>> {
+        let account = self.wallet.account()?;
+        let binding = Binding::Account(*account.id());
+        let network_id = self.wallet.network_id()?;
+
+        let transaction_store = self.wallet.store().as_transaction_record_store()?;
+        let total = transaction_store
+            .load_range(&binding, &network_id, None, 0..0)
+            .await?
+            .total as usize;
+
+        let start = total.saturating_sub(limit);
+        let mut transactions = transaction_store
+            .load_range(&binding, &network_id, None, start..total)
+            .await?
+            .transactions;
+
+        transactions.reverse();
+
+        Ok(transactions)
+    }
+
     /// change secret
     pub async fn change_secret(&self, old_secret: &Secret, new_secret: &Secret) -> Result<()> {
+        self.ensure_can_sign()?;
+
         self.wallet
             .clone()
             .wallet_change_secret(old_secret.clone(), new_secret.clone())
@@ -279,11 +710,54 @@ impl TipOwnedWallet {
         Ok(())
     }
 
+    /// sign a personal message with the private key of the receive address, hex encoded signature
+    pub async fn sign_message(&self, wallet_secret: &Secret, message: &str) -> Result<String> {
+        self.ensure_can_sign()?;
+
+        let account = self.wallet.account()?;
+        let prv_key_data_id = account.prv_key_data_id()?;
+
+        let prv_key_data_store = self.wallet.store().as_prv_key_data_store()?;
+        let prv_key_data = prv_key_data_store
+            .load_key_data(wallet_secret, prv_key_data_id)
+            .await?
+            .ok_or(Error::OwnedWalletNotFound())?;
+
+        let private_key = match account.clone().as_derivation_capable() {
+            // bip32 accounts, derive the key of the receive address
+            Ok(account) => {
+                let (receive, change) = account
+                    .derivation()
+                    .addresses_indexes(&[&self.receive_address])?;
+                account
+                    .create_private_keys(&prv_key_data, &None, &receive, &change)?
+                    .first()
+                    .map(|(_, private_key)| *private_key)
+            }
+            // keypair accounts hold the key directly
+            Err(_) => prv_key_data.payload.decrypt(None)?.as_secret_key()?,
+        }
+        .ok_or(Error::custom(
+            "Private key of the receive address not found",
+        ))?;
+
+        let signature = sign_message(
+            &PersonalMessage(message),
+            &private_key.secret_bytes(),
+            &SignMessageOptions { no_aux_rand: false },
+        )
+        .map_err(|e| Error::custom(format!("Unable to sign the message: {}", e)))?;
+
+        Ok(hex::encode(signature))
+    }
+
     /// export mnemonic with xpub
     pub async fn export_mnemonic_and_xpub(
         &self,
         wallet_secret: &Secret,
     ) -> Result<(Option<Mnemonic>, String)> {
+        self.ensure_can_sign()?;
+
         let account = self.wallet.account()?;
         let prv_key_data_id = account.prv_key_data_id()?;
 
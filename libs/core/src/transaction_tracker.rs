@@ -0,0 +1,179 @@
+use std::{fmt::Display, str::FromStr, sync::Arc, time::Duration};
+
+use spectre_addresses::Address;
+use spectre_consensus_core::Hash;
+use spectre_rpc_core::RpcUtxosByAddressesEntry;
+use spectre_wallet_core::rpc::RpcApi;
+use tokio::time::sleep;
+use tracing::warn;
+
+use crate::{
+    error::Error,
+    result::Result,
+    utils::{find_accepting_block_hash, is_transaction_in_mempool, now_unix_secs},
+};
+
+// DAA score depth after which the outputs of an accepted user transaction are spendable
+pub const USER_TRANSACTION_MATURITY_DAA: u64 = 10;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+// a transaction neither in the mempool nor accepted after this delay is no longer followed
+const ACCEPTANCE_TIMEOUT_SECS: u64 = 10 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransactionState {
+    Pending,
+    Accepted { confirmations: u64 },
+    Confirmed,
+    Failed,
+    // not found on the node, and its acceptance could not be searched
+    Unknown,
+}
+
+impl TransactionState {
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            TransactionState::Confirmed | TransactionState::Failed | TransactionState::Unknown
+        )
+    }
+}
+
+impl Display for TransactionState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TransactionState::Pending => write!(f, "Pending"),
+            TransactionState::Accepted { confirmations } => write!(
+                f,
+                "Accepted ({}/{} confirmations)",
+                confirmations, USER_TRANSACTION_MATURITY_DAA
+            ),
+            TransactionState::Confirmed => write!(f, "Confirmed"),
+            TransactionState::Failed => write!(f, "Failed (rejected by the network)"),
+            TransactionState::Unknown => write!(f, "Unknown (check the explorer)"),
+        }
+    }
+}
+
+/**
+ * submitted transaction followed until its output to `recipient_address` matures
+ */
+#[derive(Debug, Clone)]
+pub struct TrackedTransaction {
+    pub tx_id: String,
+    pub recipient_address: Address,
+    // sink of the virtual chain before the submission, acceptance is searched from there
+    pub chain_start_hash: Option<String>,
+    pub submitted_at: u64,
+}
+
+impl TrackedTransaction {
+    pub fn new(
+        tx_id: String,
+        recipient_address: Address,
+        chain_start_hash: Option<String>,
+    ) -> Self {
+        TrackedTransaction {
+            tx_id,
+            recipient_address,
+            chain_start_hash,
+            submitted_at: now_unix_secs(),
+        }
+    }
+
+    fn is_past_acceptance_timeout(&self) -> bool {
+        now_unix_secs().saturating_sub(self.submitted_at) > ACCEPTANCE_TIMEOUT_SECS
+    }
+
+    fn state_from_depth(virtual_daa_score: u64, accepting_daa_score: u64) -> TransactionState {
+        let confirmations = virtual_daa_score.saturating_sub(accepting_daa_score);
+
+        match confirmations >= USER_TRANSACTION_MATURITY_DAA {
+            true => TransactionState::Confirmed,
+            false => TransactionState::Accepted { confirmations },
+        }
+    }
+
+    /**
+     * output of the transaction to `recipient_address`, found once accepted and while unspent
+     */
+    pub async fn find_recipient_output(
+        &self,
+        rpc_api: Arc<dyn RpcApi>,
+    ) -> Result<Option<RpcUtxosByAddressesEntry>> {
+        let transaction_id = Hash::from_str(&self.tx_id)
+            .map_err(|_| Error::custom(format!("Invalid transaction id: '{}'", self.tx_id)))?;
+
+        let entries = rpc_api
+            .get_utxos_by_addresses(vec![self.recipient_address.clone()])
+            .await?;
+
+        Ok(entries
+            .into_iter()
+            .find(|entry| entry.outpoint.transaction_id == transaction_id))
+    }
+
+    /**
+     * accepted transactions are found in the utxo index while their output is unspent,
+     * or in the virtual chain, their depth is given by the virtual DAA score
+     */
+    pub async fn check_state(&self, rpc_api: Arc<dyn RpcApi>) -> Result<TransactionState> {
+        if is_transaction_in_mempool(rpc_api.clone(), &self.tx_id).await? {
+            return Ok(TransactionState::Pending);
+        }
+
+        if let Some(entry) = self.find_recipient_output(rpc_api.clone()).await? {
+            let virtual_daa_score = rpc_api.get_block_dag_info().await?.virtual_daa_score;
+
+            return Ok(Self::state_from_depth(
+                virtual_daa_score,
+                entry.utxo_entry.block_daa_score,
+            ));
+        }
+
+        // the recipient may already have spent the output
+        if let Some(chain_start_hash) = &self.chain_start_hash {
+            if let Some(accepting_block_hash) =
+                find_accepting_block_hash(rpc_api.clone(), &self.tx_id, chain_start_hash).await?
+            {
+                let accepting_block = rpc_api.get_block(accepting_block_hash, false).await?;
+                let virtual_daa_score = rpc_api.get_block_dag_info().await?.virtual_daa_score;
+
+                return Ok(Self::state_from_depth(
+                    virtual_daa_score,
+                    accepting_block.header.daa_score,
+                ));
+            }
+        }
+
+        if !self.is_past_acceptance_timeout() {
+            return Ok(TransactionState::Pending);
+        }
+
+        // without the virtual chain, a missing transaction may as well have been spent
+        match self.chain_start_hash {
+            Some(_) => Ok(TransactionState::Failed),
+            None => Ok(TransactionState::Unknown),
+        }
+    }
+
+    /**
+     * node errors do not end the tracking, until the acceptance timeout
+     */
+    pub async fn wait_for_final_state(&self, rpc_api: Arc<dyn RpcApi>) -> Result<TransactionState> {
+        loop {
+            match self.check_state(rpc_api.clone()).await {
+                Ok(state) if state.is_final() => return Ok(state),
+                Ok(_) => (),
+                Err(e) if self.is_past_acceptance_timeout() => {
+                    warn!("Transaction {} could not be checked: {}", self.tx_id, e);
+                    return Ok(TransactionState::Unknown);
+                }
+                Err(e) => warn!("Error while checking transaction {}: {}", self.tx_id, e),
+            }
+
+            sleep(POLL_INTERVAL).await;
+        }
+    }
+}
@@ -116,6 +116,12 @@ impl TransitionWalletMetadataStore {
         Ok(metadata)
     }
 
+    pub async fn find_all_transition_wallet_metadata(&self) -> Vec<TransitionWalletMetadata> {
+        let all_metadata = self.metadata.read().await;
+
+        all_metadata.clone()
+    }
+
     pub async fn find_transition_wallet_metadata_by_identifier_couple(
         &self,
         initiator_identifier: &str,
@@ -0,0 +1,466 @@
+use std::{
+    fmt,
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use spectre_addresses::Address;
+use tokio::sync::RwLock;
+
+use crate::{error::Error, result::Result};
+
+/**
+ * guild treasury, an owned wallet under the treasury identifier namespace signed by the bot
+ * once enough approvers confirmed a payout
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Treasury {
+    pub guild_id: u64,
+    pub wallet_identifier: String,
+    pub receive_address: Address,
+    // treasuries created before `TREASURY_WALLET_KEY` stored their secret, it is replaced by the
+    // derived one on first use
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    // members allowed to propose, approve and reject payouts
+    pub approver_role_id: u64,
+    // payouts can only go to members of this role
+    pub payee_role_id: u64,
+    // approvals needed before a payout is signed
+    pub threshold: u32,
+}
+
+#[derive(Debug)]
+pub struct TreasuryStore {
+    treasuries: RwLock<Vec<Treasury>>,
+    path_buf: PathBuf,
+}
+
+impl TreasuryStore {
+    pub fn new(path_buf: &PathBuf) -> Result<Self> {
+        let path = Path::new(path_buf);
+
+        let file = match File::open(path) {
+            Ok(f) => f,
+            Err(_) => {
+                let mut created_file = File::create(path)?;
+
+                created_file.write_all(b"[]")?;
+
+                File::open(path)?
+            }
+        };
+
+        let treasuries: Vec<Treasury> = serde_json::from_reader(file)?;
+
+        Ok(TreasuryStore {
+            treasuries: RwLock::new(treasuries),
+            path_buf: path_buf.clone(),
+        })
+    }
+
+    fn persist(&self, treasuries: &[Treasury]) -> Result<()> {
+        let file = File::create(Path::new(&self.path_buf))?;
+
+        serde_json::to_writer(file, treasuries)?;
+
+        Ok(())
+    }
+
+    /**
+     * add the treasury of the guild, or replace it
+     */
+    pub async fn save(&self, treasury: &Treasury) -> Result<()> {
+        let mut treasuries = self.treasuries.write().await;
+
+        treasuries.retain(|t| t.guild_id != treasury.guild_id);
+        treasuries.push(treasury.clone());
+
+        self.persist(&treasuries)
+    }
+
+    /**
+     * change the roles and threshold of the treasury, leaving its wallet untouched
+     */
+    pub async fn configure(
+        &self,
+        guild_id: u64,
+        approver_role_id: u64,
+        payee_role_id: u64,
+        threshold: u32,
+    ) -> Result<()> {
+        let mut treasuries = self.treasuries.write().await;
+
+        if let Some(treasury) = treasuries.iter_mut().find(|t| t.guild_id == guild_id) {
+            treasury.approver_role_id = approver_role_id;
+            treasury.payee_role_id = payee_role_id;
+            treasury.threshold = threshold;
+        }
+
+        self.persist(&treasuries)
+    }
+
+    /**
+     * drop the stored secret once the wallet password was switched to the derived one
+     */
+    pub async fn forget_secret(&self, guild_id: u64) -> Result<()> {
+        let mut treasuries = self.treasuries.write().await;
+
+        if let Some(treasury) = treasuries.iter_mut().find(|t| t.guild_id == guild_id) {
+            treasury.secret = None;
+        }
+
+        self.persist(&treasuries)
+    }
+
+    pub async fn find_by_guild_id(&self, guild_id: u64) -> Option<Treasury> {
+        let treasuries = self.treasuries.read().await;
+
+        treasuries.iter().find(|t| t.guild_id == guild_id).cloned()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum TreasuryPayoutStatus {
+    // waiting for approvals
+    Proposed,
+    // the threshold has been reached, the payout is being signed
+    Executing,
+    Executed,
+    Rejected,
+    Failed,
+}
+
+impl fmt::Display for TreasuryPayoutStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self {
+            TreasuryPayoutStatus::Proposed => "proposed",
+            TreasuryPayoutStatus::Executing => "executing",
+            TreasuryPayoutStatus::Executed => "executed",
+            TreasuryPayoutStatus::Rejected => "rejected",
+            TreasuryPayoutStatus::Failed => "failed",
+        };
+
+        write!(f, "{}", status)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TreasuryApproval {
+    pub approver_identifier: String,
+    pub at: u64,
+}
+
+/**
+ * payout proposal, kept once settled as the audit trail of the treasury
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TreasuryPayout {
+    pub id: u64,
+    pub guild_id: u64,
+    pub proposer_identifier: String,
+    pub recipient_identifier: String,
+    pub amount_sompi: u64,
+    pub reason: String,
+    // funds a bounty wallet instead of paying a member
+    #[serde(default)]
+    pub bounty_id: Option<u64>,
+    // public message holding the approve and reject buttons
+    pub channel_id: u64,
+    pub message_id: u64,
+    pub approvals: Vec<TreasuryApproval>,
+    pub status: TreasuryPayoutStatus,
+    // approver who rejected, or error of a failed payout
+    pub rejected_by: Option<String>,
+    pub error: Option<String>,
+    pub tx_id: Option<String>,
+    pub created_at: u64,
+    pub settled_at: Option<u64>,
+}
+
+impl TreasuryPayout {
+    pub fn has_approved(&self, approver_identifier: &str) -> bool {
+        self.approvals
+            .iter()
+            .any(|approval| approval.approver_identifier == approver_identifier)
+    }
+
+    pub fn is_approved(&self, threshold: u32) -> bool {
+        self.approvals.len() >= threshold as usize
+    }
+
+    /**
+     * an approver confirms once, only while the payout is proposed, the approval reaching the
+     * threshold claims the payout for execution so a concurrent rejection is refused
+     */
+    pub fn approve(&mut self, approver_identifier: &str, at: u64, threshold: u32) -> Result<()> {
+        if self.status != TreasuryPayoutStatus::Proposed {
+            return Err(Error::TreasuryPayoutNotProposed());
+        }
+
+        if self.has_approved(approver_identifier) {
+            return Err(Error::TreasuryPayoutAlreadyApproved());
+        }
+
+        self.approvals.push(TreasuryApproval {
+            approver_identifier: approver_identifier.to_string(),
+            at,
+        });
+
+        if self.is_approved(threshold) {
+            self.status = TreasuryPayoutStatus::Executing;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct TreasuryPayoutStore {
+    payouts: RwLock<Vec<TreasuryPayout>>,
+    path_buf: PathBuf,
+}
+
+impl TreasuryPayoutStore {
+    pub fn new(path_buf: &PathBuf) -> Result<Self> {
+        let path = Path::new(path_buf);
+
+        let file = match File::open(path) {
+            Ok(f) => f,
+            Err(_) => {
+                let mut created_file = File::create(path)?;
+
+                created_file.write_all(b"[]")?;
+
+                File::open(path)?
+            }
+        };
+
+        let payouts: Vec<TreasuryPayout> = serde_json::from_reader(file)?;
+
+        Ok(TreasuryPayoutStore {
+            payouts: RwLock::new(payouts),
+            path_buf: path_buf.clone(),
+        })
+    }
+
+    fn persist(&self, payouts: &[TreasuryPayout]) -> Result<()> {
+        let file = File::create(Path::new(&self.path_buf))?;
+
+        serde_json::to_writer(file, payouts)?;
+
+        Ok(())
+    }
+
+    async fn update<F: FnOnce(&mut TreasuryPayout) -> Result<()>>(
+        &self,
+        id: u64,
+        update: F,
+    ) -> Result<TreasuryPayout> {
+        let mut payouts = self.payouts.write().await;
+
+        let payout = payouts
+            .iter_mut()
+            .find(|p| p.id == id)
+            .ok_or(Error::TreasuryPayoutNotFound())?;
+
+        update(payout)?;
+
+        let updated = payout.clone();
+
+        self.persist(&payouts)?;
+
+        Ok(updated)
+    }
+
+    /**
+     * assign the next id to the payout and store it
+     */
+    pub async fn add(&self, mut payout: TreasuryPayout) -> Result<TreasuryPayout> {
+        let mut payouts = self.payouts.write().await;
+
+        payout.id = payouts.iter().map(|p| p.id).max().unwrap_or(0) + 1;
+
+        payouts.push(payout.clone());
+
+        self.persist(&payouts)?;
+
+        Ok(payout)
+    }
+
+    pub async fn set_message_id(&self, id: u64, message_id: u64) -> Result<TreasuryPayout> {
+        self.update(id, |payout| {
+            payout.message_id = message_id;
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn approve(
+        &self,
+        id: u64,
+        approver_identifier: &str,
+        at: u64,
+        threshold: u32,
+    ) -> Result<TreasuryPayout> {
+        self.update(id, |payout| {
+            payout.approve(approver_identifier, at, threshold)
+        })
+        .await
+    }
+
+    pub async fn reject(
+        &self,
+        id: u64,
+        approver_identifier: &str,
+        at: u64,
+    ) -> Result<TreasuryPayout> {
+        self.update(id, |payout| {
+            if payout.status != TreasuryPayoutStatus::Proposed {
+                return Err(Error::TreasuryPayoutNotProposed());
+            }
+
+            payout.status = TreasuryPayoutStatus::Rejected;
+            payout.rejected_by = Some(approver_identifier.to_string());
+            payout.settled_at = Some(at);
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn mark_executed(&self, id: u64, tx_id: String, at: u64) -> Result<TreasuryPayout> {
+        self.update(id, |payout| {
+            if payout.status != TreasuryPayoutStatus::Executing {
+                return Err(Error::TreasuryPayoutNotExecuting());
+            }
+
+            payout.status = TreasuryPayoutStatus::Executed;
+            payout.tx_id = Some(tx_id);
+            payout.settled_at = Some(at);
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn mark_failed(&self, id: u64, error: String, at: u64) -> Result<TreasuryPayout> {
+        self.update(id, |payout| {
+            if payout.status != TreasuryPayoutStatus::Executing {
+                return Err(Error::TreasuryPayoutNotExecuting());
+            }
+
+            payout.status = TreasuryPayoutStatus::Failed;
+            payout.error = Some(error);
+            payout.settled_at = Some(at);
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn find_by_id(&self, id: u64) -> Option<TreasuryPayout> {
+        let payouts = self.payouts.read().await;
+
+        payouts.iter().find(|p| p.id == id).cloned()
+    }
+
+    /**
+     * payouts of the guild, most recent first
+     */
+    pub async fn find_by_guild_id(&self, guild_id: u64) -> Vec<TreasuryPayout> {
+        let payouts = self.payouts.read().await;
+
+        payouts
+            .iter()
+            .rev()
+            .filter(|p| p.guild_id == guild_id)
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_payout() -> TreasuryPayout {
+        TreasuryPayout {
+            id: 1,
+            guild_id: 1,
+            proposer_identifier: "proposer".to_string(),
+            recipient_identifier: "recipient".to_string(),
+            amount_sompi: 100,
+            reason: "reason".to_string(),
+            bounty_id: None,
+            channel_id: 1,
+            message_id: 1,
+            approvals: vec![],
+            status: TreasuryPayoutStatus::Proposed,
+            rejected_by: None,
+            error: None,
+            tx_id: None,
+            created_at: 0,
+            settled_at: None,
+        }
+    }
+
+    #[test]
+    fn test_payout_approvals() {
+        let mut payout = build_payout();
+
+        assert!(payout.approve("a", 1, 2).is_ok());
+        assert!(!payout.is_approved(2));
+        assert_eq!(payout.status, TreasuryPayoutStatus::Proposed);
+
+        assert!(matches!(
+            payout.approve("a", 2, 2),
+            Err(Error::TreasuryPayoutAlreadyApproved())
+        ));
+
+        assert!(payout.approve("b", 3, 2).is_ok());
+        assert!(payout.is_approved(2));
+        assert_eq!(payout.status, TreasuryPayoutStatus::Executing);
+
+        assert!(matches!(
+            payout.approve("c", 4, 2),
+            Err(Error::TreasuryPayoutNotProposed())
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_payout_execution_claim() {
+        let path_buf =
+            std::env::temp_dir().join(format!("treasury_payouts_{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path_buf);
+
+        let store = TreasuryPayoutStore::new(&path_buf).unwrap();
+        let payout = store.add(build_payout()).await.unwrap();
+
+        // only a payout being executed can be settled
+        assert!(matches!(
+            store.mark_executed(payout.id, "tx".to_string(), 1).await,
+            Err(Error::TreasuryPayoutNotExecuting())
+        ));
+
+        store.approve(payout.id, "a", 1, 1).await.unwrap();
+
+        // the threshold was reached before the rejection
+        assert!(matches!(
+            store.reject(payout.id, "b", 2).await,
+            Err(Error::TreasuryPayoutNotProposed())
+        ));
+
+        let executed = store
+            .mark_executed(payout.id, "tx".to_string(), 3)
+            .await
+            .unwrap();
+        assert_eq!(executed.status, TreasuryPayoutStatus::Executed);
+
+        assert!(matches!(
+            store.mark_failed(payout.id, "error".to_string(), 4).await,
+            Err(Error::TreasuryPayoutNotExecuting())
+        ));
+
+        let _ = std::fs::remove_file(&path_buf);
+    }
+}
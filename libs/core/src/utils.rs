@@ -1,18 +1,37 @@
-use std::{fmt::Display, sync::Arc};
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    path::PathBuf,
+    str::FromStr,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use futures_util::TryStreamExt;
-use spectre_bip32::secp256k1::rand::{
-    self,
-    distributions::{Alphanumeric, DistString},
+use spectre_addresses::{Address, Prefix, Version};
+use spectre_bip32::{
+    secp256k1::{
+        rand::{
+            self,
+            distributions::{Alphanumeric, DistString},
+        },
+        SecretKey, XOnlyPublicKey,
+    },
+    ExtendedPrivateKey,
 };
-use spectre_consensus_core::constants::SOMPI_PER_SPECTRE;
+use spectre_consensus_core::{constants::SOMPI_PER_SPECTRE, network::NetworkId, Hash};
 use spectre_rpc_core::GetServerInfoResponse;
 use spectre_wallet_core::{
+    encryption::sha256_hash,
+    message::{verify_message, PersonalMessage},
     prelude::Account,
     rpc::{Rpc, RpcApi, RpcCtl},
+    settings::application_folder,
+    storage::{keydata::PrvKeyDataVariant, Encryptable, PrvKeyData, PrvKeyDataPayload},
     tx::{Fees, Generator, GeneratorSettings, GeneratorSummary, PaymentOutputs},
     wallet::Wallet,
 };
+use spectre_wallet_keys::secret::Secret;
 use spectre_wrpc_client::prelude::{NetworkType, SpectreRpcClient};
 use tokio::task::yield_now;
 use tracing::info;
@@ -51,6 +70,42 @@ pub fn generate_random_transition_wallet_secret() -> String {
     Alphanumeric.sample_string(&mut rand::thread_rng(), 12)
 }
 
+/**
+ * wallet core has no public constructor for xprv backed key data, the payload is built
+ * from the public variant through its serialized representation, the shape is covered by a test
+ */
+pub fn try_build_prv_key_data_from_xprv(xprv: &str) -> Result<PrvKeyData> {
+    ExtendedPrivateKey::<SecretKey>::from_str(xprv)
+        .map_err(|_| Error::custom("Invalid extended private key"))?;
+
+    let variant = PrvKeyDataVariant::ExtendedPrivateKey(xprv.to_string());
+
+    let payload: PrvKeyDataPayload = serde_json::from_value(serde_json::json!({
+        "prvKeyVariant": serde_json::to_value(&variant)?
+    }))?;
+
+    Ok(PrvKeyData::new(
+        payload.id(),
+        None,
+        Encryptable::Plain(payload),
+    ))
+}
+
+pub fn generate_link_challenge_message(owner_identifier: &str) -> String {
+    format!(
+        "spectre-tipper: link this address to discord user {} ({})",
+        owner_identifier,
+        Alphanumeric.sample_string(&mut rand::thread_rng(), 16)
+    )
+}
+
+pub fn now_unix_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
 pub async fn connect_wallet_to_rpc(wallet: &Arc<Wallet>, rpc_api: Arc<dyn RpcApi>) -> Result<()> {
     let ctl = RpcCtl::new();
     ctl.signal_open().await?;
@@ -69,6 +124,62 @@ pub fn build_transition_wallet_identifier(
     format!("transition-{}-{}", target_identifier, initiator_identifier)
 }
 
+/**
+ * treasuries are owned wallets, their identifier cannot collide with a discord user id
+ */
+pub fn build_treasury_identifier(guild_id: u64) -> String {
+    format!("treasury-{}", guild_id)
+}
+
+/**
+ * watch-only accounts registered by operators, `name` keeps them apart from discord user ids
+ */
+pub fn build_operator_watch_only_identifier(name: &str) -> String {
+    format!("watch-{}", name)
+}
+
+/**
+ * secret of the local file of an operator held wallet, derived so that it is never stored
+ */
+fn derive_operator_wallet_secret(operator_key: &str, owned_identifier: &str) -> Secret {
+    let hash = sha256_hash(format!("{}:{}", operator_key, owned_identifier).as_bytes());
+
+    Secret::from(hex::encode(hash.as_ref()))
+}
+
+pub fn derive_watch_only_wallet_secret(
+    watch_only_wallet_key: &str,
+    owned_identifier: &str,
+) -> Secret {
+    derive_operator_wallet_secret(watch_only_wallet_key, owned_identifier)
+}
+
+pub fn derive_treasury_wallet_secret(treasury_wallet_key: &str, owned_identifier: &str) -> Secret {
+    derive_operator_wallet_secret(treasury_wallet_key, owned_identifier)
+}
+
+/**
+ * `kind` tells what the custodial wallet is used for, e.g. escrow
+ */
+pub fn build_custodial_wallet_identifier(kind: &str, interaction_id: u64) -> String {
+    format!("{}-{}", kind, interaction_id)
+}
+
+/**
+ * discord may deliver the same interaction more than once, its id identifies the operation
+ */
+pub fn build_idempotency_key(operation: &str, interaction_id: u64) -> String {
+    format!("{}-{}", operation, interaction_id)
+}
+
+/**
+ * path of the encrypted wallet file kept by the local store
+ */
+pub fn get_wallet_file_path(wallet_identifier: &str) -> Result<PathBuf> {
+    let wallet_folder = application_folder()?;
+    Ok(wallet_folder.join(format!("{}.wallet", wallet_identifier)))
+}
+
 pub async fn estimate_fees(
     account: &Arc<dyn Account>,
     payment_outputs: PaymentOutputs,
@@ -91,6 +202,45 @@ pub async fn estimate_fees(
     Ok(generator.summary())
 }
 
+/**
+ * reject addresses belonging to another network than the one the bot runs on
+ */
+pub fn ensure_address_network(address: &Address, network_id: NetworkId) -> Result<()> {
+    let expected_prefix = Prefix::from(network_id);
+
+    if address.prefix != expected_prefix {
+        return Err(Error::custom(format!(
+            "Address belongs to the '{}' network, expected a '{}' address",
+            address.prefix, expected_prefix
+        )));
+    }
+
+    Ok(())
+}
+
+/**
+ * verify a hex encoded personal message signature against a schnorr (pubkey) address
+ */
+pub fn verify_message_signature(
+    address: &Address,
+    message: &str,
+    signature_hex: &str,
+) -> Result<bool> {
+    if address.version != Version::PubKey {
+        return Err(Error::custom(
+            "Only schnorr public key addresses can verify message signatures",
+        ));
+    }
+
+    let public_key = XOnlyPublicKey::from_slice(&address.payload)
+        .map_err(|_| Error::custom("Address does not contain a valid public key"))?;
+
+    let signature = hex::decode(signature_hex.trim())
+        .map_err(|_| Error::custom("Signature is not a valid hex string"))?;
+
+    Ok(verify_message(&PersonalMessage(message), &signature, &public_key).is_ok())
+}
+
 pub fn get_tx_explorer_url(tx_id: &str, network_type: NetworkType) -> String {
     let sub_domain = match network_type {
         NetworkType::Mainnet => "explorer",
@@ -100,6 +250,78 @@ pub fn get_tx_explorer_url(tx_id: &str, network_type: NetworkType) -> String {
     format!("https://{}.spectre-network.org/txs/{}", sub_domain, tx_id)
 }
 
+/**
+ * the node answers with an error when the transaction is not (or no longer) in its mempool
+ */
+pub async fn is_transaction_in_mempool(rpc_api: Arc<dyn RpcApi>, tx_id: &str) -> Result<bool> {
+    let transaction_id = Hash::from_str(tx_id)
+        .map_err(|_| Error::custom(format!("Invalid transaction id: '{tx_id}'")))?;
+
+    Ok(rpc_api
+        .get_mempool_entry(transaction_id, true, false)
+        .await
+        .is_ok())
+}
+
+/**
+ * block of the virtual selected chain accepting the transaction, searched from `start_hash` onward
+ */
+pub async fn find_accepting_block_hash(
+    rpc_api: Arc<dyn RpcApi>,
+    tx_id: &str,
+    start_hash: &str,
+) -> Result<Option<Hash>> {
+    let transaction_id = Hash::from_str(tx_id)
+        .map_err(|_| Error::custom(format!("Invalid transaction id: '{tx_id}'")))?;
+    let start_hash = Hash::from_str(start_hash)
+        .map_err(|_| Error::custom(format!("Invalid block hash: '{start_hash}'")))?;
+
+    let virtual_chain = rpc_api
+        .get_virtual_chain_from_block(start_hash, true)
+        .await?;
+
+    Ok(virtual_chain
+        .accepted_transaction_ids
+        .iter()
+        .find(|accepted| accepted.accepted_transaction_ids.contains(&transaction_id))
+        .map(|accepted| accepted.accepting_block_hash))
+}
+
+/**
+ * total balance of the addresses, from the node utxo index
+ */
+pub async fn get_addresses_balance(
+    rpc_api: Arc<dyn RpcApi>,
+    addresses: Vec<Address>,
+) -> Result<u64> {
+    if addresses.is_empty() {
+        return Ok(0);
+    }
+
+    let entries = rpc_api.get_balances_by_addresses(addresses).await?;
+
+    Ok(entries.iter().map(|entry| entry.balance.unwrap_or(0)).sum())
+}
+
+/**
+ * balance of each address, addresses unknown to the utxo index have a zero balance
+ */
+pub async fn get_balances_by_address(
+    rpc_api: Arc<dyn RpcApi>,
+    addresses: Vec<Address>,
+) -> Result<HashMap<Address, u64>> {
+    if addresses.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let entries = rpc_api.get_balances_by_addresses(addresses).await?;
+
+    Ok(entries
+        .into_iter()
+        .map(|entry| (entry.address, entry.balance.unwrap_or(0)))
+        .collect())
+}
+
 pub async fn check_node_status(wrpc_client: &Arc<SpectreRpcClient>) -> Result<()> {
     let GetServerInfoResponse {
         is_synced,
@@ -124,3 +346,54 @@ pub async fn check_node_status(wrpc_client: &Arc<SpectreRpcClient>) -> Result<()
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use spectre_bip32::Prefix as KeyPrefix;
+
+    use super::*;
+
+    #[test]
+    fn test_build_prv_key_data_from_xprv() {
+        let xprv = ExtendedPrivateKey::<SecretKey>::new([7u8; 64])
+            .unwrap()
+            .to_string(KeyPrefix::XPRV);
+
+        let prv_key_data = try_build_prv_key_data_from_xprv(&xprv).unwrap();
+
+        // fails if wallet core changes the serialized shape of the payload
+        let payload = prv_key_data.payload.decrypt(None).unwrap();
+        assert_eq!(
+            payload
+                .get_xprv(None)
+                .unwrap()
+                .to_string(KeyPrefix::XPRV)
+                .as_str(),
+            xprv.as_str()
+        );
+        assert_eq!(
+            prv_key_data.id,
+            PrvKeyDataVariant::ExtendedPrivateKey(xprv.to_string()).id()
+        );
+
+        assert!(try_build_prv_key_data_from_xprv("xprv-invalid").is_err());
+    }
+
+    #[test]
+    fn test_derive_watch_only_wallet_secret() {
+        let secret = derive_watch_only_wallet_secret("key", "1");
+
+        assert_eq!(
+            secret.as_ref(),
+            derive_watch_only_wallet_secret("key", "1").as_ref()
+        );
+        assert_ne!(
+            secret.as_ref(),
+            derive_watch_only_wallet_secret("key", "2").as_ref()
+        );
+        assert_ne!(
+            secret.as_ref(),
+            derive_watch_only_wallet_secret("other key", "1").as_ref()
+        );
+    }
+}